@@ -0,0 +1,376 @@
+// Generated from the rrmounter types, do not edit by hand.
+
+export type Badge = { text: string, badgeType: BadgeColor, };
+
+export type BadgeColor = "default" | "success" | "info" | "warning" | "danger";
+
+export type Banner = { id: string, imageUrl: string, title: string | null, subtitle: string | null, link: BannerLink, };
+
+/**
+ * Where tapping a banner leads
+ */
+export type BannerLink = { "type": "series", seriesId: string, } | { "type": "section", sectionId: string, } | { "type": "search", request: SearchRequest, } | { "type": "url", url: string, };
+
+export type BlockType = "geographic" | "ageRestriction" | "authentication" | "paywall" | "maintenance";
+
+export type Bookmark = { 
+/**
+ * Page index (0-indexed)
+ */
+page: number, note: string | null, createdAt: number, };
+
+/**
+ * Identifies a cached response by source, operation and arguments
+ */
+export type CacheKey = { sourceId: string, operation: CacheOperation, seriesId?: string | null, chapterId?: string | null, 
+/**
+ * Canonical JSON of the search request
+ */
+request?: string | null, };
+
+/**
+ * The source operation a cache entry belongs to
+ */
+export type CacheOperation = "homePage" | "search" | "series" | "chapter";
+
+/**
+ * How long responses stay fresh and how large the cache may grow
+ */
+export type CachePolicy = { homePageTtl: Duration, searchTtl: Duration, seriesTtl: Duration, chapterTtl: Duration, 
+/**
+ * How long after expiring an entry may still be served while it is refreshed
+ */
+staleWhileRevalidate: Duration, 
+/**
+ * Maximum size of the cached responses in bytes of JSON, least recently used ones are
+ * evicted first
+ */
+maxBytes: number, };
+
+export type Chapter = { id: string, seriesId: string, sourceId: string, title: string, chapterNumber: string, volumeNumber: string, groupName: string, time: number, read: boolean, };
+
+export type ChapterParams = { seriesId: string | null, chapterId: string, };
+
+export type ContentRating = "everyone" | "mature" | "adult" | "multi";
+
+export type DownloadState = { "state": "queued" } | { "state": "downloading" } | { "state": "completed", completedAt: number, } | { "state": "failed", error: ErrorContext, };
+
+/**
+ * A chapter in the download queue
+ */
+export type DownloadTask = { seriesTitle: string, chapterLabel: string, 
+/**
+ * Directory of the chapter, relative to the download directory
+ */
+directory: string, state: DownloadState, 
+/**
+ * One slot per page of the chapter, filled once the page is on disk
+ */
+pages: Array<SavedPage | null>, queuedAt: number, sourceId: string, seriesId: string, chapterId: string, };
+
+export type Duration = { secs: number, nanos: number };
+
+export type ErrorContext = { error: SourceError, severity: ErrorSeverity, userMessage: string, suggestedActions: Array<string>, shouldReport: boolean, timestamp: number, };
+
+export type ErrorSeverity = "info" | "warning" | "error" | "critical";
+
+/**
+ * The structured search filters a source may or may not honour server-side
+ */
+export type FilterKind = "genres" | "excludedGenres" | "status" | "contentRating" | "language" | "yearRange" | "authors" | "artists";
+
+/**
+ * A genre or tag shortcut that opens a search
+ */
+export type GenreChip = { id: string, title: string, imageUrl: string | null, };
+
+export type HomeSection = { id: string, title: string, containMoreItems: boolean, sectionType: HomeSectionType, entries: Array<SeriesEntry>, items?: Array<HomeSectionItem>, 
+/**
+ * A placeholder from `home_sections` whose content `home_section` resolves
+ */
+skeleton?: boolean, };
+
+/**
+ * A non-series item of a home section
+ */
+export type HomeSectionItem = { "kind": "genre" } & GenreChip | { "kind": "chapterUpdate" } & LatestUpdate | { "kind": "banner" } & Banner | { "kind": "ranked" } & RankedEntry;
+
+export type HomeSectionType = "singleRowNormal" | "singleRowLarge" | "doubleRow" | "featured" | "genres" | "chapterUpdates" | "banners" | "ranked";
+
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]: JsonValue } | null;
+
+export type Language = { key: LanguageKey, name: string, flagCode: string, iso6391: string, };
+
+export type LanguageKey = "multi" | "arabic" | "bengali" | "bulgarian" | "burmese" | "catalan" | "chineseSimp" | "chineseTrad" | "czech" | "danish" | "dutch" | "english" | "filipino" | "finnish" | "french" | "german" | "greek" | "hebrew" | "hindi" | "hungarian" | "indonesian" | "italian" | "japanese" | "korean" | "lithuanian" | "malay" | "mongolian" | "persian" | "polish" | "portugueseBr" | "portuguesePt" | "romanian" | "russian" | "serboCroatian" | "spanishEs" | "spanishLatam" | "swedish" | "thai" | "turkish" | "ukrainian" | "vietnamese";
+
+/**
+ * Short description of a chapter, as shown in update feeds and lists
+ */
+export type LatestChapter = { chapterId: string, chapterNumber: string, title: string | null, groupName: string | null, };
+
+/**
+ * A series that recently received new chapters
+ */
+export type LatestUpdate = { entry: SeriesEntry, chapters: Array<LatestChapter>, updatedAt: number, };
+
+/**
+ * Tracks the library and reading progress, independent of what sources report
+ */
+export type Library = { formatVersion: number, entries: Array<LibraryEntry>, progress: Array<ProgressRecord>, };
+
+/**
+ * A series the user added to their library
+ */
+export type LibraryEntry = { sourceId: string, seriesId: string, title: string, coverUrl: string, addedAt: number, 
+/**
+ * The series as last seen by the update checker
+ */
+snapshot: SeriesSnapshot | null, };
+
+/**
+ * Series metadata written by the user next to the chapters, every field is optional
+ */
+export type LocalSeriesMetadata = { title: string | null, altTitles: Array<string>, description: string | null, status: Status | null, author: Array<string>, artist: Array<string>, tags: Array<string>, originalLanguage: LanguageKey | null, contentRating: ContentRating | null, hentai: boolean, year: number | null, 
+/**
+ * Cover image file name, relative to the series folder
+ */
+cover: string | null, };
+
+export type Manifest = { protocolVersion: number, info: SourceInfo, };
+
+export type ManifestParams = { protocolVersion: number, };
+
+/**
+ * How closely two series match, every component ranging from 0.0 to 1.0
+ */
+export type MatchScore = { total: number, title: number, 
+/**
+ * `None` when either side has no author/artist information
+ */
+people: number | null, 
+/**
+ * `None` when either side has no chapter count
+ */
+chapters: number | null, };
+
+/**
+ * A unified view of the same series on several sources
+ */
+export type MergedSeries = { sources: Array<Provenance>, title: Sourced<string>, altTitles: Array<Sourced<string>>, description: Sourced<string>, status: Sourced<Status>, coverUrl: Sourced<string>, chapters: Sourced<Array<Chapter>>, author: Array<Sourced<string>>, artist: Array<Sourced<string>>, tags: Array<Sourced<string>>, contentRating: Sourced<ContentRating> | null, year: Sourced<number> | null, };
+
+/**
+ * Metadata fields whose change is reported by the update checker
+ */
+export type MetadataField = "title" | "description" | "status" | "cover" | "authors" | "artists" | "tags";
+
+export type PaginatedResponse<T> = { 
+/**
+ * Format version the payload was read in, it is always written in the current one
+ */
+formatVersion: number, 
+/**
+ * The actual items for this page
+ */
+items: Array<T>, 
+/**
+ * Current page number (1-indexed)
+ */
+currentPage: number, 
+/**
+ * Total number of pages (if known by the source)
+ */
+totalPages: number | null, 
+/**
+ * Whether there's a next page available
+ */
+hasNextPage: boolean, 
+/**
+ * Total number of items (if known by the source)
+ */
+totalItems: number | null, };
+
+export type PaginationRequest = { 
+/**
+ * Page number (1-indexed)
+ */
+page: number, 
+/**
+ * Number of items per page
+ */
+perPage: number, };
+
+export type ParseContentType = "searchResults" | "seriesMetadata" | "chapterList" | "homePage" | "imageUrl" | "json" | "html";
+
+export type ProgressRecord = { sourceId: string, seriesId: string, chapterId: string, read: boolean, 
+/**
+ * Last page the user had open (0-indexed)
+ */
+lastPageRead: number, 
+/**
+ * Number of pages, when known from the reader
+ */
+totalPages: number | null, 
+/**
+ * When the chapter was last opened
+ */
+lastReadAt: number | null, 
+/**
+ * When the chapter was first finished
+ */
+completedAt: number | null, bookmarks: Array<Bookmark>, };
+
+/**
+ * Identifies which source a merged value came from
+ */
+export type Provenance = { sourceId: string, seriesId: string, };
+
+export type RankedEntry = { 
+/**
+ * Position in the ranking (1-indexed)
+ */
+position: number, entry: SeriesEntry, 
+/**
+ * Positions gained (positive) or lost (negative) since the previous ranking
+ */
+change: number | null, };
+
+export type RpcError = { code: number, message: string, data?: JsonValue | null, };
+
+/**
+ * A call from the host to the extension, one JSON object per line on stdin
+ */
+export type RpcRequest = { jsonrpc: string, id: number, method: string, params: JsonValue, };
+
+/**
+ * The answer to an `RpcRequest`, one JSON object per line on stdout
+ */
+export type RpcResponse = { jsonrpc: string, id: number | null, result?: JsonValue | null, error?: RpcError | null, };
+
+/**
+ * A page written to disk
+ */
+export type SavedPage = { fileName: string, size: number, 
+/**
+ * CRC-32 of the file, missing for pages saved by older versions
+ */
+checksum?: number | null, };
+
+export type SearchFilters = { 
+/**
+ * Filter by genres (e.g., "Action", "Romance", "Comedy")
+ */
+genres: Array<string> | null, 
+/**
+ * Exclude series tagged with any of these genres
+ */
+excludedGenres: Array<string> | null, 
+/**
+ * Filter by publication status
+ */
+status: Status | null, 
+/**
+ * Filter by content rating
+ */
+contentRating: ContentRating | null, 
+/**
+ * Filter by original language
+ */
+language: LanguageKey | null, 
+/**
+ * Filter by publication year range (start_year, end_year), either end may be open
+ */
+yearRange: [number | null, number | null] | null, 
+/**
+ * Filter by author names
+ */
+authors: Array<string> | null, 
+/**
+ * Filter by artist names
+ */
+artists: Array<string> | null, 
+/**
+ * Custom filters specific to individual manga sources
+ * Key-value pairs where the key is the filter name and value is the filter value
+ * Examples: {"sort_by": "latest"}, {"has_cover": "true"}, {"min_chapters": "10"}
+ */
+customFilters: { [key in string]: string }, };
+
+export type SearchRequest = { 
+/**
+ * The search query string (manga title, author, etc.)
+ */
+query: string, 
+/**
+ * Pagination information
+ */
+pagination: PaginationRequest, 
+/**
+ * Structured filters for common manga attributes
+ */
+filters: SearchFilters, };
+
+export type SectionParams = { sectionId: string, };
+
+export type Series = { 
+/**
+ * Format version the payload was read in, it is always written in the current one
+ */
+formatVersion: number, sourceId: string, seriesId: string, title: string, altTitles: Array<string>, description: string, status: Status, coverUrl: string, chapters: Array<Chapter>, author: Array<string>, artist: Array<string>, tags: Array<string>, hentai: boolean, originalLanguage: LanguageKey, contentRating: ContentRating | null, year: number | null, numberUnread: number, numberChapters: number, };
+
+export type SeriesEntry = { 
+/**
+ * Format version the payload was read in, it is always written in the current one
+ */
+formatVersion: number, sourceId: string, seriesId: string, title: string, coverUrl: string, subtitle?: string | null, badges?: Array<Badge>, latestChapter?: LatestChapter | null, rating?: number | null, languages?: Array<LanguageKey>, };
+
+export type SeriesParams = { seriesId: string, };
+
+/**
+ * The parts of a series the update checker compares between runs
+ */
+export type SeriesSnapshot = { title: string, description: string, status: Status, coverUrl: string, author: Array<string>, artist: Array<string>, tags: Array<string>, chapters: Array<Chapter>, takenAt: number, };
+
+export type SettingType = { "type": "string", "value": string } | { "type": "boolean", "value": boolean } | { "type": "number", "value": number } | { "type": "languageKey", "value": LanguageKey };
+
+/**
+ * Current values of the settings a source declares
+ */
+export type SettingValues = { values: { [key in string]: SettingType }, };
+
+/**
+ * The optional operations a source implements
+ */
+export type SourceCapabilities = { latestUpdates: boolean, viewMore: boolean, lazyHomePage: boolean, };
+
+export type SourceError = { "networkError": { message: string, retryable: boolean, } } | { "timeout": { timeout: Duration, } } | { "rateLimit": { retryAfter: number, message: string | null, } } | { "authRequired": { reason: string, authUrl: string | null, } } | { "forbidden": { reason: string, } } | { "notFound": { resource: string, suggestion: string | null, } } | { "parseError": { details: string, contentType: ParseContentType, structureChanged: boolean, } } | { "invalidQuery": { reason: string, suggestions: Array<string>, } } | { "sourceUnavailable": { reason: string, estimatedDowntime: Duration | null, } } | { "configurationError": { setting: string, expected: string, current: string, } } | { "unsupportedOperation": { operation: string, alternatives: Array<string>, } } | { "invalidPagination": { reason: string, maxAllowed: number | null, } } | { "invalidFilter": { filterName: string, reason: string, validValues: Array<string>, } } | { "contentBlocked": { reason: string, blockType: BlockType, } } | { "imageError": { url: string, src: string | null, } } | { "unexpected": { message: string, errorCode: string | null, } };
+
+/**
+ * Describes a source to the app before any of its operations is called
+ */
+export type SourceInfo = { id: string, name: string, version: string, languages: Array<LanguageKey>, contentRating: ContentRating, baseUrl: string | null, iconUrl: string | null, capabilities: SourceCapabilities, settings: Array<SourceSetting>, };
+
+export type SourceSetting = { key: string, name: string, description: string, defaultType: SettingType, required: boolean, };
+
+export type Sourced<T> = { value: T, from: Provenance, };
+
+export type Status = "ongoing" | "completed" | "hiatus" | "cancelled";
+
+/**
+ * Something that changed on a library series since the last check
+ */
+export type UpdateEvent = { sourceId: string, seriesId: string, kind: UpdateKind, };
+
+/**
+ * A series that could not be checked
+ */
+export type UpdateFailure = { sourceId: string, seriesId: string, error: ErrorContext, };
+
+export type UpdateKind = { "newChapter": { chapter: Chapter, } } | { "chapterRemoved": { chapter: Chapter, } } | { "metadataChanged": { fields: Array<MetadataField>, } };
+
+export type UpdateReport = { events: Array<UpdateEvent>, failures: Array<UpdateFailure>, checked: number, 
+/**
+ * Series not re-fetched because the latest-updates feed showed no change
+ */
+skipped: number, };
+
+export type ViewMoreParams = { sectionId: string, pagination: PaginationRequest, };
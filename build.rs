@@ -0,0 +1,19 @@
+use std::process::Command;
+
+fn main() {
+    // Native extensions must be built by the same compiler as the host, see `native::RUSTC_VERSION`.
+    // An unknown version would let any extension pass the check, so it fails the build instead.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = match Command::new(&rustc).arg("--version").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => panic!("`{} --version` failed with {}", rustc, output.status),
+        Err(error) => panic!("could not run `{} --version`: {}", rustc, error),
+    };
+    let version = String::from_utf8(output.stdout).unwrap_or_default();
+    if version.trim().is_empty() {
+        panic!("`{} --version` printed no version", rustc);
+    }
+
+    println!("cargo:rustc-env=RRMOUNTER_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
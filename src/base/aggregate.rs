@@ -0,0 +1,113 @@
+use futures::{Stream, StreamExt, stream::FuturesUnordered};
+use std::time::{Duration, Instant};
+
+use crate::{
+    base::{PaginatedResponse, SearchRequest, SourceInstance},
+    error::{ErrorContext, SourceError},
+    generate::SeriesEntry,
+};
+
+/// Outcome of searching a single source as part of a global search
+#[derive(Debug, Clone)]
+pub struct SourceSearchResult {
+    pub source_id: String,
+    pub result: Result<PaginatedResponse<SeriesEntry>, ErrorContext>,
+    pub elapsed: Duration,
+}
+
+/// Results of a global search, grouped by source in registration order
+#[derive(Debug, Clone, Default)]
+pub struct GlobalSearchResults {
+    pub groups: Vec<(String, PaginatedResponse<SeriesEntry>)>,
+    pub errors: Vec<(String, ErrorContext)>,
+}
+
+impl GlobalSearchResults {
+    /// Total number of entries across every source
+    pub fn item_count(&self) -> usize {
+        self.groups.iter().map(|(_, page)| page.item_count()).sum()
+    }
+
+    /// Get the results of a single source
+    pub fn get(&self, source_id: &str) -> Option<&PaginatedResponse<SeriesEntry>> {
+        self.groups
+            .iter()
+            .find(|(id, _)| id == source_id)
+            .map(|(_, page)| page)
+    }
+}
+
+/// Runs one `SearchRequest` against many sources concurrently
+pub struct GlobalSearch<'a> {
+    sources: Vec<(String, &'a SourceInstance)>,
+    timeout: Duration,
+}
+
+impl<'a> GlobalSearch<'a> {
+    /// Create an aggregator that gives each source up to `timeout` to answer
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            sources: Vec::new(),
+            timeout,
+        }
+    }
+
+    /// Builder method to add an enabled source
+    pub fn with_source(mut self, source_id: impl Into<String>, source: &'a SourceInstance) -> Self {
+        self.sources.push((source_id.into(), source));
+        self
+    }
+
+    /// Search every source, yielding each result as soon as its source finishes.
+    ///
+    /// Failures and timeouts are reported per source as an `ErrorContext`
+    /// and never end the stream early.
+    pub fn stream(&self, request: SearchRequest) -> impl Stream<Item = SourceSearchResult> {
+        let timeout = self.timeout;
+
+        self.sources
+            .iter()
+            .map(|(source_id, source)| {
+                let source_id = source_id.clone();
+                let search = (source.search)(request.clone());
+                async move {
+                    let started = Instant::now();
+                    let result = match tokio::time::timeout(timeout, search).await {
+                        Ok(Ok(page)) => Ok(page),
+                        Ok(Err(error)) => Err(error.to_context()),
+                        Err(_) => Err(SourceError::Timeout { timeout }.to_context()),
+                    };
+
+                    SourceSearchResult {
+                        source_id,
+                        result,
+                        elapsed: started.elapsed(),
+                    }
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+    }
+
+    /// Search every source and wait for all of them, grouping results by source
+    pub async fn collect(&self, request: SearchRequest) -> GlobalSearchResults {
+        let mut finished: Vec<SourceSearchResult> = self.stream(request).collect().await;
+        finished.sort_by_key(|result| {
+            self.sources
+                .iter()
+                .position(|(id, _)| *id == result.source_id)
+        });
+
+        let mut results = GlobalSearchResults::default();
+        for SourceSearchResult {
+            source_id, result, ..
+        } in finished
+        {
+            match result {
+                Ok(page) => results.groups.push((source_id, page)),
+                Err(context) => results.errors.push((source_id, context)),
+            }
+        }
+
+        results
+    }
+}
@@ -1,13 +1,19 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct Badge {
     pub text: String,
+    #[serde(alias = "badge_type")]
     pub badge_type: BadgeColor,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub enum BadgeColor {
     Default,
     Success,
@@ -16,6 +22,15 @@ pub enum BadgeColor {
     Danger,
 }
 
+impl Badge {
+    pub fn new(text: impl Into<String>, badge_type: BadgeColor) -> Self {
+        Self {
+            text: text.into(),
+            badge_type,
+        }
+    }
+}
+
 impl BadgeColor {
     pub fn to_str(&self) -> &'static str {
         match self {
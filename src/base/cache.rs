@@ -0,0 +1,477 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    base::{HomeSection, PaginatedResponse, SearchRequest, SourceInstance},
+    error::{AsyncSourceResult, IntoSourceError, SourceResult},
+    generate::{Series, SeriesEntry},
+    utils::unix_timestamp,
+    wire::{FORMAT_VERSION, check_format_version, legacy_format_version},
+};
+
+/// The source operation a cache entry belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum CacheOperation {
+    HomePage,
+    Search,
+    Series,
+    Chapter,
+}
+
+/// Identifies a cached response by source, operation and arguments
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CacheKey {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    pub operation: CacheOperation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapter_id: Option<String>,
+    /// Canonical JSON of the search request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+}
+
+impl CacheKey {
+    fn new(source_id: impl Into<String>, operation: CacheOperation) -> Self {
+        Self {
+            source_id: source_id.into(),
+            operation,
+            series_id: None,
+            chapter_id: None,
+            request: None,
+        }
+    }
+
+    pub fn home_page(source_id: impl Into<String>) -> Self {
+        Self::new(source_id, CacheOperation::HomePage)
+    }
+
+    pub fn search(source_id: impl Into<String>, request: &SearchRequest) -> Self {
+        // Going through `Value` sorts object keys, so equal requests always produce the same key
+        let request = serde_json::to_value(request)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+
+        Self {
+            request: Some(request),
+            ..Self::new(source_id, CacheOperation::Search)
+        }
+    }
+
+    pub fn series(source_id: impl Into<String>, series_id: &str) -> Self {
+        Self {
+            series_id: Some(series_id.to_string()),
+            ..Self::new(source_id, CacheOperation::Series)
+        }
+    }
+
+    pub fn chapter(
+        source_id: impl Into<String>,
+        series_id: Option<&str>,
+        chapter_id: &str,
+    ) -> Self {
+        Self {
+            series_id: series_id.map(str::to_string),
+            chapter_id: Some(chapter_id.to_string()),
+            ..Self::new(source_id, CacheOperation::Chapter)
+        }
+    }
+
+    /// Approximate memory used by the key, counted towards the size limit
+    fn size(&self) -> usize {
+        [&self.series_id, &self.chapter_id, &self.request]
+            .into_iter()
+            .flatten()
+            .map(String::len)
+            .sum::<usize>()
+            + self.source_id.len()
+    }
+}
+
+/// How long responses stay fresh and how large the cache may grow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CachePolicy {
+    #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+    #[serde(alias = "home_page_ttl")]
+    pub home_page_ttl: Duration,
+    #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+    #[serde(alias = "search_ttl")]
+    pub search_ttl: Duration,
+    #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+    #[serde(alias = "series_ttl")]
+    pub series_ttl: Duration,
+    #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+    #[serde(alias = "chapter_ttl")]
+    pub chapter_ttl: Duration,
+
+    /// How long after expiring an entry may still be served while it is refreshed
+    #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+    #[serde(alias = "stale_while_revalidate")]
+    pub stale_while_revalidate: Duration,
+
+    /// Maximum size of the cached responses in bytes of JSON, least recently used ones are
+    /// evicted first
+    pub max_bytes: u64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            home_page_ttl: Duration::from_secs(30 * 60),
+            search_ttl: Duration::from_secs(10 * 60),
+            series_ttl: Duration::from_secs(60 * 60),
+            chapter_ttl: Duration::from_secs(24 * 60 * 60),
+            stale_while_revalidate: Duration::from_secs(24 * 60 * 60),
+            max_bytes: 32 * 1024 * 1024,
+        }
+    }
+}
+
+impl CachePolicy {
+    pub fn ttl(&self, operation: CacheOperation) -> Duration {
+        match operation {
+            CacheOperation::HomePage => self.home_page_ttl,
+            CacheOperation::Search => self.search_ttl,
+            CacheOperation::Series => self.series_ttl,
+            CacheOperation::Chapter => self.chapter_ttl,
+        }
+    }
+}
+
+/// A response served from the cache or freshly fetched
+pub struct Cached<T> {
+    pub value: T,
+
+    /// Whether the value outlived its TTL and is being served stale
+    pub stale: bool,
+
+    /// For stale values, a future that fetches a fresh copy and updates the cache
+    pub refresh: Option<AsyncSourceResult<T>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    value: serde_json::Value,
+    #[serde(alias = "stored_at")]
+    stored_at: u64,
+    #[serde(alias = "last_access")]
+    last_access: u64,
+
+    /// Bytes counted towards the size limit
+    #[serde(skip)]
+    size: usize,
+    /// Position in the access order, higher is more recent
+    #[serde(skip)]
+    recency: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheStore {
+    entries: HashMap<CacheKey, CacheEntry>,
+    total_size: usize,
+    accesses: u64,
+}
+
+impl CacheStore {
+    fn insert(&mut self, key: CacheKey, value: serde_json::Value, max_bytes: u64) {
+        let now = unix_timestamp();
+        let entry = CacheEntry {
+            value,
+            stored_at: now,
+            last_access: now,
+            size: 0,
+            recency: 0,
+        };
+        self.restore(key, entry);
+        self.evict(max_bytes);
+    }
+
+    /// Insert an entry keeping its timestamps, as the most recently used one
+    fn restore(&mut self, key: CacheKey, mut entry: CacheEntry) {
+        entry.size = key.size() + entry.value.to_string().len();
+        entry.recency = self.next_access();
+        self.total_size += entry.size;
+        if let Some(previous) = self.entries.insert(key, entry) {
+            self.total_size -= previous.size;
+        }
+    }
+
+    fn get_mut(&mut self, key: &CacheKey) -> Option<&mut CacheEntry> {
+        let recency = self.next_access();
+        let entry = self.entries.get_mut(key)?;
+        entry.recency = recency;
+        entry.last_access = unix_timestamp();
+        Some(entry)
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_size -= entry.size;
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&CacheKey) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+        self.total_size = self.entries.values().map(|entry| entry.size).sum();
+    }
+
+    /// Drop least recently used entries until the store fits in `max_bytes`
+    fn evict(&mut self, max_bytes: u64) {
+        while self.total_size as u64 > max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.recency)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    fn next_access(&mut self) -> u64 {
+        self.accesses += 1;
+        self.accesses
+    }
+}
+
+/// On-disk layout: JSON objects cannot use structured keys, so entries are stored as pairs
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedStore<K> {
+    #[serde(default = "legacy_format_version")]
+    format_version: u32,
+    entries: Vec<(K, CacheEntry)>,
+}
+
+/// Read every entry of a cache file, whatever source it belongs to
+fn read_persisted(path: &Path) -> SourceResult<Vec<(CacheKey, CacheEntry)>> {
+    let json = match std::fs::read(path) {
+        Ok(json) => json,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into_source_error()),
+    };
+    let persisted: PersistedStore<CacheKey> =
+        serde_json::from_slice(&json).map_err(IntoSourceError::into_source_error)?;
+    check_format_version(&path.display().to_string(), persisted.format_version)?;
+
+    Ok(persisted.entries)
+}
+
+/// Caches the responses of a source with per-operation TTLs
+pub struct CachedSource<'a> {
+    source_id: String,
+    source: &'a SourceInstance,
+    policy: CachePolicy,
+    store: Arc<Mutex<CacheStore>>,
+}
+
+impl<'a> CachedSource<'a> {
+    pub fn new(
+        source_id: impl Into<String>,
+        source: &'a SourceInstance,
+        policy: CachePolicy,
+    ) -> Self {
+        Self {
+            source_id: source_id.into(),
+            source,
+            policy,
+            store: Arc::default(),
+        }
+    }
+
+    pub async fn home_page(&self) -> SourceResult<Cached<Vec<HomeSection>>> {
+        let home_page = self.source.home_page;
+        self.fetch(CacheKey::home_page(&self.source_id), move || home_page())
+            .await
+    }
+
+    pub async fn search(
+        &self,
+        request: SearchRequest,
+    ) -> SourceResult<Cached<PaginatedResponse<SeriesEntry>>> {
+        let search = self.source.search;
+        let key = CacheKey::search(&self.source_id, &request);
+        self.fetch(key, move || search(request.clone())).await
+    }
+
+    pub async fn series(&self, series_id: String) -> SourceResult<Cached<Series>> {
+        let series = self.source.series;
+        let key = CacheKey::series(&self.source_id, &series_id);
+        self.fetch(key, move || series(series_id.clone())).await
+    }
+
+    pub async fn chapter(
+        &self,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> SourceResult<Cached<Vec<String>>> {
+        let chapter = self.source.chapter;
+        let key = CacheKey::chapter(&self.source_id, series_id.as_deref(), &chapter_id);
+        self.fetch(key, move || chapter(series_id.clone(), chapter_id.clone()))
+            .await
+    }
+
+    /// Remove a single entry
+    pub fn invalidate(&self, key: &CacheKey) {
+        self.lock().remove(key);
+    }
+
+    /// Remove every entry of an operation, e.g. all search results
+    pub fn invalidate_operation(&self, operation: CacheOperation) {
+        self.lock().retain(|key| key.operation != operation);
+    }
+
+    /// Remove a series and the cached pages of its chapters
+    pub fn invalidate_series(&self, series_id: &str) {
+        self.lock()
+            .retain(|key| key.series_id.as_deref() != Some(series_id));
+    }
+
+    pub fn clear(&self) {
+        self.lock().retain(|_| false);
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes currently counted towards `CachePolicy::max_bytes`
+    pub fn size(&self) -> u64 {
+        self.lock().total_size as u64
+    }
+
+    /// Write the entries of this source to `path` as JSON.
+    ///
+    /// Entries other sources saved to the same file are kept.
+    pub fn save(&self, path: impl AsRef<Path>) -> SourceResult<()> {
+        let path = path.as_ref();
+        let mut entries: Vec<(CacheKey, CacheEntry)> = read_persisted(path)?
+            .into_iter()
+            .filter(|(key, _)| key.source_id != self.source_id)
+            .collect();
+        entries.extend(
+            self.lock()
+                .entries
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.clone())),
+        );
+
+        let persisted = PersistedStore {
+            format_version: FORMAT_VERSION,
+            entries,
+        };
+        let json = serde_json::to_vec(&persisted).map_err(IntoSourceError::into_source_error)?;
+
+        // Write next to the file first so a crash never leaves it half written
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, json).map_err(IntoSourceError::into_source_error)?;
+        std::fs::rename(&temporary, path).map_err(IntoSourceError::into_source_error)
+    }
+
+    /// Load entries previously written with `save`, keeping their original age.
+    ///
+    /// A missing file is not an error, the cache simply starts empty.
+    pub fn load(&self, path: impl AsRef<Path>) -> SourceResult<()> {
+        let mut entries: Vec<(CacheKey, CacheEntry)> = read_persisted(path.as_ref())?
+            .into_iter()
+            .filter(|(key, _)| key.source_id == self.source_id)
+            .collect();
+        entries.sort_by_key(|(_, entry)| entry.last_access);
+
+        let mut store = self.lock();
+        for (key, entry) in entries {
+            store.restore(key, entry);
+        }
+        store.evict(self.policy.max_bytes);
+        Ok(())
+    }
+
+    async fn fetch<T, F>(&self, key: CacheKey, fetch: F) -> SourceResult<Cached<T>>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+        F: Fn() -> AsyncSourceResult<T> + 'static,
+    {
+        let ttl = self.policy.ttl(key.operation).as_secs();
+        let stale_window = self.policy.stale_while_revalidate.as_secs();
+
+        let hit = {
+            let mut store = self.lock();
+            store.get_mut(&key).and_then(|entry| {
+                let age = unix_timestamp().saturating_sub(entry.stored_at);
+                if age > ttl + stale_window {
+                    return None;
+                }
+                let value = serde_json::from_value::<T>(entry.value.clone()).ok()?;
+                Some((value, age > ttl))
+            })
+        };
+
+        match hit {
+            Some((value, false)) => Ok(Cached {
+                value,
+                stale: false,
+                refresh: None,
+            }),
+            Some((value, true)) => {
+                let store = self.store.clone();
+                let max_bytes = self.policy.max_bytes;
+                let refresh: AsyncSourceResult<T> = Box::pin(async move {
+                    let value = fetch().await?;
+                    store_value(&store, key, &value, max_bytes);
+                    Ok(value)
+                });
+
+                Ok(Cached {
+                    value,
+                    stale: true,
+                    refresh: Some(refresh),
+                })
+            }
+            None => {
+                let value = fetch().await?;
+                store_value(&self.store, key, &value, self.policy.max_bytes);
+                Ok(Cached {
+                    value,
+                    stale: false,
+                    refresh: None,
+                })
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheStore> {
+        self.store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn store_value<T: Serialize>(store: &Mutex<CacheStore>, key: CacheKey, value: &T, max_bytes: u64) {
+    if let Ok(json) = serde_json::to_value(value) {
+        store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(key, json, max_bytes);
+    }
+}
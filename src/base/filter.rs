@@ -0,0 +1,275 @@
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    base::{
+        ContentRating, LanguageKey, PaginatedResponse, SearchFilters, SearchRequest, SourceInstance,
+    },
+    error::SourceResult,
+    generate::{Series, SeriesEntry},
+};
+
+/// Number of `Series` fetched at the same time while post-filtering a page
+const DETAIL_CONCURRENCY: usize = 4;
+
+/// Extra pages `search_with_fallback` reads to refill a page emptied by filtering
+pub const MAX_REFILL_PAGES: u32 = 4;
+
+/// The structured search filters a source may or may not honour server-side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum FilterKind {
+    Genres,
+    ExcludedGenres,
+    Status,
+    ContentRating,
+    Language,
+    YearRange,
+    Authors,
+    Artists,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 8] = [
+        FilterKind::Genres,
+        FilterKind::ExcludedGenres,
+        FilterKind::Status,
+        FilterKind::ContentRating,
+        FilterKind::Language,
+        FilterKind::YearRange,
+        FilterKind::Authors,
+        FilterKind::Artists,
+    ];
+}
+
+impl SearchFilters {
+    /// Whether a value is set for the given filter
+    pub fn is_set(&self, kind: FilterKind) -> bool {
+        match kind {
+            FilterKind::Genres => self.genres.as_ref().is_some_and(|v| !v.is_empty()),
+            FilterKind::ExcludedGenres => {
+                self.excluded_genres.as_ref().is_some_and(|v| !v.is_empty())
+            }
+            FilterKind::Status => self.status.is_some(),
+            FilterKind::ContentRating => self.content_rating.is_some(),
+            FilterKind::Language => self.language.is_some(),
+            FilterKind::YearRange => self.year_range.is_some(),
+            FilterKind::Authors => self.authors.as_ref().is_some_and(|v| !v.is_empty()),
+            FilterKind::Artists => self.artists.as_ref().is_some_and(|v| !v.is_empty()),
+        }
+    }
+}
+
+/// Applies the filters a source ignores to its results on the client.
+///
+/// Entries only carry ids, title and cover, so every active filter needs the
+/// full `Series`, which is fetched lazily and only when at least one filter
+/// has to be applied locally. Values the series does not provide (an unknown
+/// year or content rating) never exclude it.
+#[derive(Debug, Clone)]
+pub struct ClientFilter {
+    filters: SearchFilters,
+    kinds: Vec<FilterKind>,
+}
+
+impl ClientFilter {
+    /// Create a filter for the given `unsupported` kinds that are actually set in `filters`
+    pub fn new(filters: SearchFilters, unsupported: &[FilterKind]) -> Self {
+        let kinds = unsupported
+            .iter()
+            .copied()
+            .filter(|kind| filters.is_set(*kind))
+            .collect();
+
+        Self { filters, kinds }
+    }
+
+    /// Whether any filter has to be applied locally
+    pub fn is_active(&self) -> bool {
+        !self.kinds.is_empty()
+    }
+
+    /// Check a series against every locally applied filter
+    pub fn matches(&self, series: &Series) -> bool {
+        self.kinds
+            .iter()
+            .all(|kind| self.matches_kind(*kind, series))
+    }
+
+    /// Drop the entries of a page that do not pass the local filters.
+    ///
+    /// The language filter is checked against the languages an entry lists,
+    /// so details are only fetched for the filters an entry cannot answer.
+    /// Entries whose details fail to load are dropped too, since they cannot be
+    /// shown to pass. If no entry could be checked the first error is returned.
+    pub async fn apply(
+        &self,
+        source: &SourceInstance,
+        mut response: PaginatedResponse<SeriesEntry>,
+    ) -> SourceResult<PaginatedResponse<SeriesEntry>> {
+        if !self.is_active() || response.items.is_empty() {
+            return Ok(response);
+        }
+
+        let series = source.series;
+        let original_count = response.items.len();
+        let checks: Vec<SourceResult<bool>> = stream::iter(&response.items)
+            .map(|entry| async move {
+                let language = self.entry_language(entry);
+                if language == Some(false) {
+                    return Ok(false);
+                }
+
+                // The language is settled by the entry when it lists any
+                let pending: Vec<FilterKind> = self
+                    .kinds
+                    .iter()
+                    .copied()
+                    .filter(|kind| *kind != FilterKind::Language || language.is_none())
+                    .collect();
+                if pending.is_empty() {
+                    return Ok(true);
+                }
+
+                let series = series(entry.series_id.clone()).await?;
+                Ok(pending.iter().all(|kind| self.matches_kind(*kind, &series)))
+            })
+            .buffered(DETAIL_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut checks = checks.into_iter();
+        if checks.as_slice().iter().all(Result::is_err)
+            && let Some(Err(error)) = checks.next()
+        {
+            return Err(error);
+        }
+
+        response
+            .items
+            .retain(|_| matches!(checks.next(), Some(Ok(true))));
+
+        if response.items.len() != original_count {
+            response.total_items = None;
+        }
+
+        Ok(response)
+    }
+
+    /// Whether the languages listed by `entry` pass the language filter,
+    /// `None` if the filter is not applied locally or the entry lists none
+    fn entry_language(&self, entry: &SeriesEntry) -> Option<bool> {
+        let language = self.filters.language?;
+        if !self.kinds.contains(&FilterKind::Language) || entry.languages.is_empty() {
+            return None;
+        }
+        Some(language == LanguageKey::Multi || entry.languages.contains(&language))
+    }
+
+    fn matches_kind(&self, kind: FilterKind, series: &Series) -> bool {
+        let filters = &self.filters;
+        match kind {
+            FilterKind::Genres => filters
+                .genres
+                .iter()
+                .flatten()
+                .all(|genre| contains_ignore_case(&series.tags, genre)),
+            FilterKind::ExcludedGenres => !filters
+                .excluded_genres
+                .iter()
+                .flatten()
+                .any(|genre| contains_ignore_case(&series.tags, genre)),
+            FilterKind::Status => filters.status.is_none_or(|status| series.status == status),
+            FilterKind::ContentRating => match (filters.content_rating, series_rating(series)) {
+                (None | Some(ContentRating::Multi), _) | (_, None) => true,
+                (Some(wanted), Some(actual)) => wanted == actual,
+            },
+            FilterKind::Language => filters.language.is_none_or(|language| {
+                language == LanguageKey::Multi || series.original_language == language
+            }),
+            FilterKind::YearRange => match (filters.year_range, series.year) {
+                (Some((start, end)), Some(year)) => {
+                    start.is_none_or(|start| year >= start) && end.is_none_or(|end| year <= end)
+                }
+                _ => true,
+            },
+            FilterKind::Authors => filters
+                .authors
+                .iter()
+                .flatten()
+                .any(|author| mentions_ignore_case(&series.author, author)),
+            FilterKind::Artists => filters
+                .artists
+                .iter()
+                .flatten()
+                .any(|artist| mentions_ignore_case(&series.artist, artist)),
+        }
+    }
+}
+
+/// Search a source and apply the `unsupported` filters of the request locally.
+///
+/// Pages that shrink below `per_page` are refilled from the following pages,
+/// fetching at most `MAX_REFILL_PAGES` extra pages, and cut back to `per_page`
+/// entries. Entries cut from the last page are not returned by the next search.
+/// The response reports the last page it read, so the next search continues
+/// after it. A refill page that cannot be searched or checked ends the refill
+/// with the entries found so far, and the next search retries that page.
+pub async fn search_with_fallback(
+    source: &SourceInstance,
+    request: SearchRequest,
+    unsupported: &[FilterKind],
+) -> SourceResult<PaginatedResponse<SeriesEntry>> {
+    let filter = ClientFilter::new(request.filters.clone(), unsupported);
+    let per_page = request.pagination.per_page as usize;
+    let mut next_request = request.clone();
+    let mut response = filter
+        .apply(source, (source.search)(request).await?)
+        .await?;
+
+    let mut refills = 0;
+    while filter.is_active()
+        && response.items.len() < per_page
+        && response.has_next_page
+        && refills < MAX_REFILL_PAGES
+    {
+        refills += 1;
+        next_request.pagination.page += 1;
+        let page = match (source.search)(next_request.clone()).await {
+            Ok(page) => filter.apply(source, page).await,
+            Err(error) => Err(error),
+        };
+        let Ok(page) = page else {
+            break;
+        };
+
+        response.items.extend(page.items);
+        response.current_page = next_request.pagination.page;
+        response.has_next_page = page.has_next_page;
+        response.total_items = None;
+    }
+
+    response.items.truncate(per_page);
+    Ok(response)
+}
+
+fn series_rating(series: &Series) -> Option<ContentRating> {
+    series
+        .content_rating
+        .or(series.hentai.then_some(ContentRating::Adult))
+}
+
+fn contains_ignore_case(values: &[String], wanted: &str) -> bool {
+    values
+        .iter()
+        .any(|value| value.eq_ignore_ascii_case(wanted))
+}
+
+fn mentions_ignore_case(values: &[String], wanted: &str) -> bool {
+    let wanted = wanted.to_lowercase();
+    values
+        .iter()
+        .any(|value| value.to_lowercase().contains(&wanted))
+}
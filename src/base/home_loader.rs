@@ -0,0 +1,103 @@
+use futures::{
+    Stream, StreamExt,
+    future::ready,
+    stream::{self, FuturesUnordered},
+};
+
+use crate::{
+    base::{HomeSection, SourceInstance},
+    error::{AsyncSourceResult, ErrorContext, SourceError, SourceResult},
+};
+
+/// Progress of a home page loaded section by section
+#[derive(Debug, Clone)]
+pub enum HomePageEvent {
+    /// Every section in display order, possibly without entries yet
+    Skeletons(Vec<HomeSection>),
+
+    /// A section whose entries were resolved
+    Loaded(HomeSection),
+
+    /// A section that failed to resolve, the others are unaffected
+    SectionFailed {
+        section_id: String,
+        error: ErrorContext,
+    },
+
+    /// The section list itself could not be loaded
+    Failed(ErrorContext),
+}
+
+impl HomeSection {
+    /// Whether the section still needs its content resolved
+    pub fn is_skeleton(&self) -> bool {
+        self.skeleton
+    }
+}
+
+/// Load the home page of a source, yielding sections as soon as they are ready.
+///
+/// The sections come first so the screen can be laid out, then every one marked
+/// as a skeleton is resolved concurrently through `home_section`. Sources without
+/// lazy loading fall back to `home_page`, whose sections arrive already filled.
+///
+/// Source futures are not `Send`, so each call runs on the blocking pool of the
+/// current Tokio runtime and the stream itself can move between threads.
+pub fn load_home_page(source: &SourceInstance) -> impl Stream<Item = HomePageEvent> + Send + use<> {
+    // Skeletons are only useful when every one of them can be resolved
+    let (home_sections, home_section) = match (source.home_sections, source.home_section) {
+        (Some(home_sections), Some(home_section)) => (home_sections, Some(home_section)),
+        _ => (source.home_page, None),
+    };
+
+    stream::once(detached(home_sections)).flat_map(move |result| {
+        let skeletons = match result {
+            Ok(skeletons) => skeletons,
+            Err(error) => {
+                return stream::once(ready(HomePageEvent::Failed(error.to_context()))).boxed();
+            }
+        };
+
+        let pending: FuturesUnordered<_> = match home_section {
+            Some(home_section) => skeletons
+                .iter()
+                .filter(|section| section.is_skeleton())
+                .map(|section| {
+                    let section_id = section.id.clone();
+                    let id = section_id.clone();
+                    let resolve = detached(move || home_section(id));
+                    async move {
+                        match resolve.await {
+                            Ok(section) => HomePageEvent::Loaded(section),
+                            Err(error) => HomePageEvent::SectionFailed {
+                                section_id,
+                                error: error.to_context(),
+                            },
+                        }
+                    }
+                })
+                .collect(),
+            None => FuturesUnordered::new(),
+        };
+
+        stream::once(ready(HomePageEvent::Skeletons(skeletons)))
+            .chain(pending)
+            .boxed()
+    })
+}
+
+/// Drive a source call to completion on the blocking pool
+async fn detached<T: Send + 'static>(
+    call: impl FnOnce() -> AsyncSourceResult<T> + Send + 'static,
+) -> SourceResult<T> {
+    let unexpected = |message: String| SourceError::Unexpected {
+        message,
+        error_code: None,
+    };
+    let runtime = tokio::runtime::Handle::try_current()
+        .map_err(|error| unexpected(format!("home page loaded outside a runtime: {}", error)))?;
+
+    tokio::task::spawn_blocking(move || runtime.block_on(call()))
+        .await
+        .map_err(|error| unexpected(format!("home page task failed: {}", error)))?
+}
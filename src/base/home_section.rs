@@ -1,21 +1,185 @@
 use serde::{Deserialize, Serialize};
 
-use crate::generate::SeriesEntry;
+use crate::base::SearchRequest;
+use crate::generate::{LatestUpdate, SeriesEntry};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum HomeSectionType {
     SingleRowNormal,
     SingleRowLarge,
     DoubleRow,
     Featured,
+    Genres,
+    ChapterUpdates,
+    Banners,
+    Ranked,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct HomeSection {
     pub id: String,
     pub title: String,
+    #[serde(alias = "contain_more_items")]
     pub contain_more_items: bool,
+    #[serde(alias = "section_type")]
     pub section_type: HomeSectionType,
     pub entries: Vec<SeriesEntry>,
+
+    // Content for the layouts that do not show plain series rows
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<HomeSectionItem>,
+
+    /// A placeholder from `home_sections` whose content `home_section` resolves
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skeleton: bool,
+}
+
+/// A non-series item of a home section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HomeSectionItem {
+    Genre(GenreChip),
+    ChapterUpdate(LatestUpdate),
+    Banner(Banner),
+    Ranked(RankedEntry),
+}
+
+/// A genre or tag shortcut that opens a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct GenreChip {
+    pub id: String,
+    pub title: String,
+    #[serde(alias = "image_url")]
+    pub image_url: Option<String>,
+}
+
+impl GenreChip {
+    /// The search the chip leads to
+    pub fn search_request(&self) -> SearchRequest {
+        SearchRequest::simple(String::new()).with_genres(vec![self.id.clone()])
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Banner {
+    pub id: String,
+    #[serde(alias = "image_url")]
+    pub image_url: String,
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub link: BannerLink,
+}
+
+/// Where tapping a banner leads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum BannerLink {
+    Series {
+        #[serde(alias = "series_id")]
+        series_id: String,
+    },
+    Section {
+        #[serde(alias = "section_id")]
+        section_id: String,
+    },
+    Search {
+        request: SearchRequest,
+    },
+    Url {
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct RankedEntry {
+    /// Position in the ranking (1-indexed)
+    pub position: u32,
+    pub entry: SeriesEntry,
+
+    /// Positions gained (positive) or lost (negative) since the previous ranking
+    pub change: Option<i32>,
+}
+
+impl HomeSection {
+    /// Create an empty section
+    pub fn new(
+        id: impl Into<String>,
+        title: impl Into<String>,
+        section_type: HomeSectionType,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            contain_more_items: false,
+            section_type,
+            entries: Vec::new(),
+            items: Vec::new(),
+            skeleton: false,
+        }
+    }
+
+    /// Create a placeholder section to be resolved through `home_section`
+    pub fn skeleton(
+        id: impl Into<String>,
+        title: impl Into<String>,
+        section_type: HomeSectionType,
+    ) -> Self {
+        Self {
+            skeleton: true,
+            ..Self::new(id, title, section_type)
+        }
+    }
+
+    /// Builder method to set the series of a row layout
+    pub fn with_entries(mut self, entries: Vec<SeriesEntry>) -> Self {
+        self.entries = entries;
+        self
+    }
+
+    /// Builder method to set the items of a genre, update, banner or ranked layout
+    pub fn with_items(mut self, items: Vec<HomeSectionItem>) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Builder method to mark that `view_more` can load the full list
+    pub fn with_more_items(mut self) -> Self {
+        self.contain_more_items = true;
+        self
+    }
+
+    /// Every series referenced by the section, whatever its layout
+    pub fn series_entries(&self) -> Vec<&SeriesEntry> {
+        self.entries
+            .iter()
+            .chain(self.items.iter().filter_map(|item| match item {
+                HomeSectionItem::ChapterUpdate(update) => Some(&update.entry),
+                HomeSectionItem::Ranked(ranked) => Some(&ranked.entry),
+                HomeSectionItem::Genre(_) | HomeSectionItem::Banner(_) => None,
+            }))
+            .collect()
+    }
 }
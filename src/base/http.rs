@@ -0,0 +1,260 @@
+use reqwest::{StatusCode, header};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::{IntoSourceError, SourceError, SourceResult};
+
+/// Retry delay used when a rate-limited response has no usable `Retry-After`
+const DEFAULT_RETRY_AFTER: u64 = 60;
+
+/// A body fetched through `HttpClient`
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+
+    /// Whether the body was reused from the cache after a 304 or a rate limit
+    pub from_cache: bool,
+}
+
+impl HttpResponse {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> SourceResult<T> {
+        serde_json::from_slice(&self.body).map_err(IntoSourceError::into_source_error)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Validated {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Vec<u8>,
+
+    /// Position in the access order, higher is more recent
+    recency: u64,
+}
+
+#[derive(Debug, Default)]
+struct ValidatedStore {
+    entries: HashMap<String, Validated>,
+    accesses: u64,
+}
+
+impl ValidatedStore {
+    fn next_access(&mut self) -> u64 {
+        self.accesses += 1;
+        self.accesses
+    }
+}
+
+/// Shared HTTP layer for sources with conditional request caching.
+///
+/// Successful GET responses that carry an `ETag` or `Last-Modified` header are
+/// remembered, and the next request for the same URL sends `If-None-Match` /
+/// `If-Modified-Since`. A 304 reuses the stored body. Rate limits are reported
+/// as errors, unless `serve_stale_on_rate_limit` is turned on to serve the
+/// stored body instead. The least recently used bodies are dropped first.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    validated: Arc<Mutex<ValidatedStore>>,
+    max_entries: usize,
+    serve_stale_on_rate_limit: bool,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+impl HttpClient {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            validated: Arc::default(),
+            max_entries: 500,
+            serve_stale_on_rate_limit: false,
+        }
+    }
+
+    /// Builder method to limit how many response bodies are kept for revalidation
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Builder method to serve the cached body of a URL when rate limited instead of failing
+    pub fn with_serve_stale_on_rate_limit(mut self, enabled: bool) -> Self {
+        self.serve_stale_on_rate_limit = enabled;
+        self
+    }
+
+    /// The underlying client, for requests that should bypass the cache
+    pub fn inner(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Send a conditional GET request
+    pub async fn get(&self, url: &str) -> SourceResult<HttpResponse> {
+        let cached = self.lookup(url);
+
+        let mut request = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(IntoSourceError::into_source_error)?;
+        let status = response.status();
+
+        let reuse_cached = status == StatusCode::NOT_MODIFIED
+            || (status == StatusCode::TOO_MANY_REQUESTS && self.serve_stale_on_rate_limit);
+        if reuse_cached && let Some(cached) = cached {
+            return Ok(HttpResponse {
+                status: status.as_u16(),
+                body: cached.body,
+                from_cache: true,
+            });
+        }
+
+        if !status.is_success() {
+            return Err(status_error(url, status, response.headers()));
+        }
+
+        let etag = header_string(response.headers(), header::ETAG);
+        let last_modified = header_string(response.headers(), header::LAST_MODIFIED);
+        let body = response
+            .bytes()
+            .await
+            .map_err(IntoSourceError::into_source_error)?
+            .to_vec();
+
+        if etag.is_some() || last_modified.is_some() {
+            self.store(
+                url,
+                Validated {
+                    etag,
+                    last_modified,
+                    body: body.clone(),
+                    recency: 0,
+                },
+            );
+        }
+
+        Ok(HttpResponse {
+            status: status.as_u16(),
+            body,
+            from_cache: false,
+        })
+    }
+
+    pub async fn get_text(&self, url: &str) -> SourceResult<String> {
+        Ok(self.get(url).await?.text())
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> SourceResult<T> {
+        self.get(url).await?.json()
+    }
+
+    /// Forget the validators and body stored for a URL
+    pub fn invalidate(&self, url: &str) {
+        self.lock().entries.remove(url);
+    }
+
+    pub fn clear_cache(&self) {
+        self.lock().entries.clear();
+    }
+
+    fn lookup(&self, url: &str) -> Option<Validated> {
+        let mut store = self.lock();
+        let recency = store.next_access();
+        let validated = store.entries.get_mut(url)?;
+        validated.recency = recency;
+        Some(validated.clone())
+    }
+
+    fn store(&self, url: &str, mut validated: Validated) {
+        let mut store = self.lock();
+        validated.recency = store.next_access();
+        store.entries.insert(url.to_string(), validated);
+
+        while store.entries.len() > self.max_entries {
+            let Some(evicted) = store
+                .entries
+                .iter()
+                .min_by_key(|(_, validated)| validated.recency)
+                .map(|(url, _)| url.clone())
+            else {
+                break;
+            };
+            store.entries.remove(&evicted);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ValidatedStore> {
+        self.validated
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Map an unsuccessful HTTP status to the matching `SourceError`
+pub fn status_error(url: &str, status: StatusCode, headers: &header::HeaderMap) -> SourceError {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = retry_after(headers).unwrap_or(DEFAULT_RETRY_AFTER);
+            SourceError::rate_limit_with_message(retry_after, format!("{} returned 429", url))
+        }
+        StatusCode::SERVICE_UNAVAILABLE => SourceError::SourceUnavailable {
+            reason: format!("{} returned 503", url),
+            estimated_downtime: retry_after(headers).map(Duration::from_secs),
+        },
+        StatusCode::UNAUTHORIZED => SourceError::AuthRequired {
+            reason: format!("{} returned 401", url),
+            auth_url: None,
+        },
+        StatusCode::FORBIDDEN => SourceError::Forbidden {
+            reason: format!("{} returned 403", url),
+        },
+        StatusCode::NOT_FOUND | StatusCode::GONE => SourceError::NotFound {
+            resource: url.to_string(),
+            suggestion: None,
+        },
+        status => SourceError::network(
+            format!("{} returned {}", url, status),
+            status.is_server_error(),
+        ),
+    }
+}
+
+/// Parse a `Retry-After` header given either as seconds or as an HTTP date
+fn retry_after(headers: &header::HeaderMap) -> Option<u64> {
+    let value = header_string(headers, header::RETRY_AFTER)?;
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(seconds);
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let seconds = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(seconds.max(0) as u64)
+}
+
+fn header_string(headers: &header::HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
@@ -1,51 +1,139 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub enum LanguageKey {
+    #[serde(alias = "Multi")]
     Multi,
+    #[serde(alias = "Arabic")]
     Arabic,
+    #[serde(alias = "Bengali")]
     Bengali,
+    #[serde(alias = "Bulgarian")]
     Bulgarian,
+    #[serde(alias = "Burmese")]
     Burmese,
+    #[serde(alias = "Catalan")]
     Catalan,
+    #[serde(alias = "ChineseSimp")]
     ChineseSimp,
+    #[serde(alias = "ChineseTrad")]
     ChineseTrad,
+    #[serde(alias = "Czech")]
     Czech,
+    #[serde(alias = "Danish")]
     Danish,
+    #[serde(alias = "Dutch")]
     Dutch,
+    #[serde(alias = "English")]
     English,
+    #[serde(alias = "Filipino")]
     Filipino,
+    #[serde(alias = "Finnish")]
     Finnish,
+    #[serde(alias = "French")]
     French,
+    #[serde(alias = "German")]
     German,
+    #[serde(alias = "Greek")]
     Greek,
+    #[serde(alias = "Hebrew")]
     Hebrew,
+    #[serde(alias = "Hindi")]
     Hindi,
+    #[serde(alias = "Hungarian")]
     Hungarian,
+    #[serde(alias = "Indonesian")]
     Indonesian,
+    #[serde(alias = "Italian")]
     Italian,
+    #[serde(alias = "Japanese")]
     Japanese,
+    #[serde(alias = "Korean")]
     Korean,
+    #[serde(alias = "Lithuanian")]
     Lithuanian,
+    #[serde(alias = "Malay")]
     Malay,
+    #[serde(alias = "Mongolian")]
     Mongolian,
+    #[serde(alias = "Persian")]
     Persian,
+    #[serde(alias = "Polish")]
     Polish,
+    #[serde(alias = "PortugueseBr")]
     PortugueseBr,
+    #[serde(alias = "PortuguesePt")]
     PortuguesePt,
+    #[serde(alias = "Romanian")]
     Romanian,
+    #[serde(alias = "Russian")]
     Russian,
+    #[serde(alias = "SerboCroatian")]
     SerboCroatian,
+    #[serde(alias = "SpanishEs")]
     SpanishEs,
+    #[serde(alias = "SpanishLatam")]
     SpanishLatam,
+    #[serde(alias = "Swedish")]
     Swedish,
+    #[serde(alias = "Thai")]
     Thai,
+    #[serde(alias = "Turkish")]
     Turkish,
+    #[serde(alias = "Ukrainian")]
     Ukrainian,
+    #[serde(alias = "Vietnamese")]
     Vietnamese,
 }
 
 impl LanguageKey {
+    pub const ALL: [LanguageKey; 41] = [
+        LanguageKey::Multi,
+        LanguageKey::Arabic,
+        LanguageKey::Bengali,
+        LanguageKey::Bulgarian,
+        LanguageKey::Burmese,
+        LanguageKey::Catalan,
+        LanguageKey::ChineseSimp,
+        LanguageKey::ChineseTrad,
+        LanguageKey::Czech,
+        LanguageKey::Danish,
+        LanguageKey::Dutch,
+        LanguageKey::English,
+        LanguageKey::Filipino,
+        LanguageKey::Finnish,
+        LanguageKey::French,
+        LanguageKey::German,
+        LanguageKey::Greek,
+        LanguageKey::Hebrew,
+        LanguageKey::Hindi,
+        LanguageKey::Hungarian,
+        LanguageKey::Indonesian,
+        LanguageKey::Italian,
+        LanguageKey::Japanese,
+        LanguageKey::Korean,
+        LanguageKey::Lithuanian,
+        LanguageKey::Malay,
+        LanguageKey::Mongolian,
+        LanguageKey::Persian,
+        LanguageKey::Polish,
+        LanguageKey::PortugueseBr,
+        LanguageKey::PortuguesePt,
+        LanguageKey::Romanian,
+        LanguageKey::Russian,
+        LanguageKey::SerboCroatian,
+        LanguageKey::SpanishEs,
+        LanguageKey::SpanishLatam,
+        LanguageKey::Swedish,
+        LanguageKey::Thai,
+        LanguageKey::Turkish,
+        LanguageKey::Ukrainian,
+        LanguageKey::Vietnamese,
+    ];
+
     pub fn name(&self) -> &'static str {
         match self {
             LanguageKey::Multi => "Multi",
@@ -162,7 +250,7 @@ impl LanguageKey {
             LanguageKey::Hungarian => "hu",
             LanguageKey::Indonesian => "id",
             LanguageKey::Italian => "it",
-            LanguageKey::Japanese => "jp",
+            LanguageKey::Japanese => "ja",
             LanguageKey::Korean => "ko",
             LanguageKey::Lithuanian => "lt",
             LanguageKey::Malay => "ms",
@@ -183,13 +271,48 @@ impl LanguageKey {
             LanguageKey::Vietnamese => "vi",
         }
     }
+
+    /// Look up a language by key, display name, ISO 639-1 code or flag code (case-insensitive).
+    ///
+    /// Codes shared by several languages, such as "pt", give `None`; `matching` lists them.
+    pub fn from_code(code: &str) -> Option<LanguageKey> {
+        match Self::matching(code).as_slice() {
+            [key] => Some(*key),
+            _ => None,
+        }
+    }
+
+    /// Every language a code could refer to.
+    ///
+    /// Keys and display names take precedence over ISO 639-1 codes, which take
+    /// precedence over flag codes, so "my" is Burmese rather than Malay's flag.
+    pub fn matching(code: &str) -> Vec<LanguageKey> {
+        let code = code.trim();
+        let is = |value: &str| !value.is_empty() && value.eq_ignore_ascii_case(code);
+        let tiers: [&dyn Fn(&LanguageKey) -> bool; 3] = [
+            &|key| is(&format!("{:?}", key)) || is(key.name()),
+            &|key| is(key.iso639_1()),
+            &|key| is(key.flag_code()),
+        ];
+
+        tiers
+            .iter()
+            .map(|tier| Self::ALL.iter().copied().filter(|key| tier(key)).collect())
+            .find(|matches: &Vec<LanguageKey>| !matches.is_empty())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct Language {
     pub key: LanguageKey,
     pub name: String,
+    #[serde(alias = "flag_code")]
     pub flag_code: String,
+    #[serde(alias = "iso639_1")]
     pub iso639_1: String,
 }
 
@@ -1,15 +1,30 @@
+pub mod aggregate;
 pub mod badge;
+pub mod cache;
+pub mod filter;
+pub mod home_loader;
 pub mod home_section;
-pub mod source_info;
+pub mod http;
 pub mod lang;
+pub mod pagination;
+pub mod query;
+pub mod search;
 pub mod setting;
 pub mod source;
-pub mod search;
+pub mod source_info;
+pub mod stateful;
 
+pub use aggregate::*;
 pub use badge::*;
+pub use cache::*;
+pub use filter::*;
+pub use home_loader::*;
 pub use home_section::*;
-pub use source_info::*;
+pub use http::*;
 pub use lang::*;
+pub use pagination::*;
+pub use search::*;
 pub use setting::*;
 pub use source::*;
-pub use search::*;
\ No newline at end of file
+pub use source_info::*;
+pub use stateful::*;
@@ -0,0 +1,237 @@
+use futures::{Stream, stream};
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    base::{PaginatedResponse, PaginationRequest, SearchRequest, SourceInstance},
+    error::{AsyncSourceResult, SourceResult},
+    generate::SeriesEntry,
+};
+
+/// Shared flag used to stop a running pagination stream from the outside
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the stream before it requests another page or yields another item
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Walks every page of a paginated source operation, yielding items one by one
+pub struct Paginator<T, F> {
+    fetch: F,
+    pagination: PaginationRequest,
+    max_pages: Option<u32>,
+    max_items: Option<usize>,
+    dedup_key: Option<fn(&T) -> String>,
+    cancel: Option<CancelToken>,
+}
+
+impl<T, F, Fut> Paginator<T, F>
+where
+    F: FnMut(PaginationRequest) -> Fut,
+    Fut: Future<Output = SourceResult<PaginatedResponse<T>>>,
+{
+    /// Create a paginator that calls `fetch` once per page, starting at page 1
+    pub fn new(fetch: F) -> Self {
+        Self {
+            fetch,
+            pagination: PaginationRequest::default(),
+            max_pages: None,
+            max_items: None,
+            dedup_key: None,
+            cancel: None,
+        }
+    }
+
+    /// Builder method to start from a specific page and page size
+    pub fn starting_at(mut self, pagination: PaginationRequest) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// Builder method to stop after fetching this many pages
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Builder method to stop after yielding this many items
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Builder method to skip items whose key was already yielded on a previous page
+    pub fn dedup_by(mut self, key: fn(&T) -> String) -> Self {
+        self.dedup_key = Some(key);
+        self
+    }
+
+    /// Builder method to stop the stream when the token is cancelled
+    pub fn cancel_on(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Turn the paginator into a stream of items.
+    ///
+    /// The stream ends when the source reports no next page, a page adds no new
+    /// items, a limit is reached or the token is cancelled. A failed page is
+    /// yielded as an error and ends the stream.
+    pub fn into_stream(self) -> impl Stream<Item = SourceResult<T>> {
+        let state = PaginatorState {
+            paginator: self,
+            buffer: VecDeque::new(),
+            seen: HashSet::new(),
+            pages_fetched: 0,
+            items_yielded: 0,
+            exhausted: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        })
+    }
+}
+
+impl<F, Fut> Paginator<SeriesEntry, F>
+where
+    F: FnMut(PaginationRequest) -> Fut,
+    Fut: Future<Output = SourceResult<PaginatedResponse<SeriesEntry>>>,
+{
+    /// Builder method to skip entries whose `series_id` was already yielded
+    pub fn dedup_by_series_id(self) -> Self {
+        self.dedup_by(|entry| entry.series_id.clone())
+    }
+
+    /// Builder method to skip entries whose normalized title was already yielded
+    pub fn dedup_by_title(self) -> Self {
+        self.dedup_by(SeriesEntry::normalized_title)
+    }
+}
+
+struct PaginatorState<T, F> {
+    paginator: Paginator<T, F>,
+    buffer: VecDeque<T>,
+    seen: HashSet<String>,
+    pages_fetched: u32,
+    items_yielded: usize,
+    exhausted: bool,
+}
+
+impl<T, F, Fut> PaginatorState<T, F>
+where
+    F: FnMut(PaginationRequest) -> Fut,
+    Fut: Future<Output = SourceResult<PaginatedResponse<T>>>,
+{
+    async fn next(&mut self) -> Option<SourceResult<T>> {
+        loop {
+            if self.is_cancelled() || self.reached_item_limit() {
+                return None;
+            }
+
+            if let Some(item) = self.buffer.pop_front() {
+                self.items_yielded += 1;
+                return Some(Ok(item));
+            }
+
+            if self.exhausted || self.reached_page_limit() {
+                return None;
+            }
+
+            let request = self.paginator.pagination.clone();
+            let response = match (self.paginator.fetch)(request).await {
+                Ok(response) => response,
+                Err(error) => {
+                    self.exhausted = true;
+                    return Some(Err(error));
+                }
+            };
+
+            // Advance from the requested page, the page a source reports may not move
+            self.pages_fetched += 1;
+            self.paginator.pagination.page += 1;
+
+            for item in response.items {
+                if let Some(key) = self.paginator.dedup_key
+                    && !self.seen.insert(key(&item))
+                {
+                    continue;
+                }
+                self.buffer.push_back(item);
+            }
+
+            // A page without new items means the source is repeating itself
+            self.exhausted = !response.has_next_page || self.buffer.is_empty();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.paginator
+            .cancel
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn reached_item_limit(&self) -> bool {
+        self.paginator
+            .max_items
+            .is_some_and(|max| self.items_yielded >= max)
+    }
+
+    fn reached_page_limit(&self) -> bool {
+        self.paginator
+            .max_pages
+            .is_some_and(|max| self.pages_fetched >= max)
+    }
+}
+
+/// Paginate through every search result of a source, deduplicated by `series_id`
+pub fn search_all(
+    source: &SourceInstance,
+    request: SearchRequest,
+) -> Paginator<
+    SeriesEntry,
+    impl FnMut(PaginationRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>,
+> {
+    let search = source.search;
+    let pagination = request.pagination.clone();
+    let fetch = move |pagination: PaginationRequest| {
+        search(SearchRequest {
+            pagination,
+            ..request.clone()
+        })
+    };
+
+    Paginator::new(fetch)
+        .starting_at(pagination)
+        .dedup_by_series_id()
+}
+
+/// Paginate through the full list behind a home section, deduplicated by `series_id`
+pub fn view_more_all(
+    source: &SourceInstance,
+    section_id: String,
+) -> Paginator<
+    SeriesEntry,
+    impl FnMut(PaginationRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>,
+> {
+    let fetch =
+        move |pagination: PaginationRequest| source.fetch_view_more(section_id.clone(), pagination);
+
+    Paginator::new(fetch).dedup_by_series_id()
+}
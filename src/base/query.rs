@@ -0,0 +1,324 @@
+use crate::{
+    base::{ContentRating, LanguageKey, SearchRequest},
+    error::{SourceError, SourceResult},
+    generate::Status,
+};
+
+const KNOWN_KEYS: [&str; 8] = [
+    "author", "artist", "genre", "status", "rating", "year", "lang", "language",
+];
+
+/// A single `key:value` or free-text term of a search query
+#[derive(Debug, Clone, PartialEq)]
+enum QueryToken {
+    Text(String),
+    Term {
+        key: String,
+        value: String,
+        negated: bool,
+    },
+}
+
+impl SearchRequest {
+    /// Parse a search query written in the filter mini-language.
+    ///
+    /// Supported terms: `author:`, `artist:`, `genre:` (and `-genre:` to exclude),
+    /// `status:`, `rating:`, `year:2010`, `year:2010..2020`, `lang:`. Values with
+    /// spaces can be quoted, e.g. `author:"Eiichiro Oda"`. Everything else,
+    /// including words like `Re:Zero` whose prefix is no filter name, becomes the
+    /// free-text `query`.
+    pub fn parse(input: &str) -> SourceResult<Self> {
+        Self::parse_with_custom_filters(input, &[])
+    }
+
+    /// Parse a search query like `parse`, turning terms whose key is one of
+    /// `custom_filters` (compared case-insensitively) into `custom_filters` entries
+    pub fn parse_with_custom_filters(input: &str, custom_filters: &[&str]) -> SourceResult<Self> {
+        let mut request = SearchRequest::simple(String::new());
+        let mut text = Vec::new();
+
+        for token in tokenize(input, custom_filters)? {
+            match token {
+                QueryToken::Text(word) => text.push(word),
+                QueryToken::Term {
+                    key,
+                    value,
+                    negated,
+                } => apply_term(&mut request, &key, value, negated)?,
+            }
+        }
+
+        request.query = text.join(" ");
+        Ok(request)
+    }
+}
+
+fn apply_term(
+    request: &mut SearchRequest,
+    key: &str,
+    value: String,
+    negated: bool,
+) -> SourceResult<()> {
+    let filters = &mut request.filters;
+
+    if negated && key != "genre" {
+        return Err(invalid_query(
+            format!("'-{}:' is not supported, only genres can be excluded", key),
+            vec![format!("-genre:{}", value)],
+        ));
+    }
+
+    match key {
+        "author" => filters.authors.get_or_insert_with(Vec::new).push(value),
+        "artist" => filters.artists.get_or_insert_with(Vec::new).push(value),
+        "genre" if negated => filters
+            .excluded_genres
+            .get_or_insert_with(Vec::new)
+            .push(value),
+        "genre" => filters.genres.get_or_insert_with(Vec::new).push(value),
+        "status" => filters.status = Some(parse_status(&value)?),
+        "rating" => filters.content_rating = Some(parse_content_rating(&value)?),
+        "year" => filters.year_range = Some(parse_year_range(&value)?),
+        "lang" | "language" => filters.language = Some(parse_language(&value)?),
+        _ => {
+            filters.custom_filters.insert(key.to_string(), value);
+        }
+    }
+
+    Ok(())
+}
+
+fn tokenize(input: &str, custom_filters: &[&str]) -> SourceResult<Vec<QueryToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+            let phrase = read_quoted(&mut chars, input)?;
+            if !phrase.is_empty() {
+                tokens.push(QueryToken::Text(phrase));
+            }
+            continue;
+        }
+
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == ':' {
+                break;
+            }
+            word.push(c);
+            chars.next();
+        }
+
+        if chars.peek() != Some(&':') {
+            tokens.push(QueryToken::Text(word));
+            continue;
+        }
+
+        let (negated, name) = match word.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, word.as_str()),
+        };
+
+        if name.is_empty() {
+            return Err(invalid_query(
+                format!("Missing filter name before ':' in '{}'", input),
+                KNOWN_KEYS.iter().map(|k| format!("{}:", k)).collect(),
+            ));
+        }
+
+        let key = match filter_key(name, custom_filters) {
+            Some(key) => key,
+            None => {
+                // Not a filter, so the colon is part of a word such as "Re:Zero"
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(QueryToken::Text(word));
+                continue;
+            }
+        };
+        chars.next();
+
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            read_quoted(&mut chars, input)?
+        } else {
+            let mut value = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            value
+        };
+
+        if value.trim().is_empty() {
+            return Err(invalid_query(
+                format!("Filter '{}:' has no value", key),
+                vec![format!("{}:\"value\"", key)],
+            ));
+        }
+
+        tokens.push(QueryToken::Term {
+            key,
+            value: value.trim().to_string(),
+            negated,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// The key a filter term is stored under, if `name` is a known or registered filter
+fn filter_key(name: &str, custom_filters: &[&str]) -> Option<String> {
+    let lowercase = name.to_lowercase();
+    if KNOWN_KEYS.contains(&lowercase.as_str()) {
+        return Some(lowercase);
+    }
+    custom_filters
+        .iter()
+        .find(|key| key.eq_ignore_ascii_case(name))
+        .map(|key| key.to_string())
+}
+
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    input: &str,
+) -> SourceResult<String> {
+    let mut value = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(value.trim().to_string());
+        }
+        value.push(c);
+    }
+
+    Err(invalid_query(
+        format!("Unterminated quote in '{}'", input),
+        vec![format!("{}\"", input.trim_end())],
+    ))
+}
+
+fn parse_status(value: &str) -> SourceResult<Status> {
+    match value.to_lowercase().as_str() {
+        "ongoing" => Ok(Status::Ongoing),
+        "completed" | "complete" | "finished" => Ok(Status::Completed),
+        "hiatus" => Ok(Status::Hiatus),
+        "cancelled" | "canceled" => Ok(Status::Cancelled),
+        _ => Err(invalid_query(
+            format!("Unknown status '{}'", value),
+            ["ongoing", "completed", "hiatus", "cancelled"]
+                .iter()
+                .map(|s| format!("status:{}", s))
+                .collect(),
+        )),
+    }
+}
+
+fn parse_content_rating(value: &str) -> SourceResult<ContentRating> {
+    match value.to_lowercase().as_str() {
+        "everyone" | "safe" => Ok(ContentRating::Everyone),
+        "mature" => Ok(ContentRating::Mature),
+        "adult" => Ok(ContentRating::Adult),
+        "multi" => Ok(ContentRating::Multi),
+        _ => Err(invalid_query(
+            format!("Unknown content rating '{}'", value),
+            ["everyone", "mature", "adult", "multi"]
+                .iter()
+                .map(|s| format!("rating:{}", s))
+                .collect(),
+        )),
+    }
+}
+
+fn parse_year_range(value: &str) -> SourceResult<(Option<u32>, Option<u32>)> {
+    let invalid = || {
+        invalid_query(
+            format!("Invalid year range '{}'", value),
+            vec![
+                "year:2010".to_string(),
+                "year:2010..2020".to_string(),
+                "year:2010..".to_string(),
+                "year:..2020".to_string(),
+            ],
+        )
+    };
+    let parse_year = |s: &str| s.trim().parse::<u32>().map_err(|_| invalid());
+    let parse_bound = |s: &str| match s.trim() {
+        "" => Ok(None),
+        year => parse_year(year).map(Some),
+    };
+
+    let (start, end) = match value.split_once("..") {
+        Some((start, end)) => (parse_bound(start)?, parse_bound(end)?),
+        None => {
+            let year = parse_year(value)?;
+            (Some(year), Some(year))
+        }
+    };
+
+    match (start, end) {
+        (None, None) => Err(invalid()),
+        (Some(start), Some(end)) if start > end => Err(invalid_query(
+            format!("Year range '{}' starts after it ends", value),
+            vec![format!("year:{}..{}", end, start)],
+        )),
+        range => Ok(range),
+    }
+}
+
+fn parse_language(value: &str) -> SourceResult<LanguageKey> {
+    LanguageKey::from_code(value).ok_or_else(|| {
+        let candidates = LanguageKey::matching(value);
+        if candidates.len() > 1 {
+            return invalid_query(
+                format!("Language '{}' is ambiguous", value),
+                candidates.iter().map(language_term).collect(),
+            );
+        }
+
+        let initial = value.chars().next().map(|c| c.to_ascii_lowercase());
+        let mut suggestions: Vec<String> = LanguageKey::ALL
+            .iter()
+            .filter(|key| key.name().to_lowercase().chars().next() == initial)
+            .map(language_term)
+            .collect();
+        suggestions.dedup();
+
+        invalid_query(format!("Unknown language '{}'", value), suggestions)
+    })
+}
+
+/// A `lang:` term that only matches `key`
+fn language_term(key: &LanguageKey) -> String {
+    match LanguageKey::from_code(key.iso639_1()) {
+        Some(_) => format!("lang:{}", key.iso639_1()),
+        None => format!(
+            "lang:{}",
+            serde_json::to_value(key)
+                .unwrap_or_default()
+                .as_str()
+                .unwrap_or_default()
+        ),
+    }
+}
+
+fn invalid_query(reason: String, suggestions: Vec<String>) -> SourceError {
+    SourceError::InvalidQuery {
+        reason,
+        suggestions,
+    }
+}
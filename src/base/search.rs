@@ -1,9 +1,15 @@
 use crate::base::{ContentRating, LanguageKey};
 use crate::generate::Status;
+use crate::wire::{
+    FORMAT_VERSION, deserialize_format_version, legacy_format_version, serialize_format_version,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct SearchRequest {
     /// The search query string (manga title, author, etc.)
     pub query: String,
@@ -16,30 +22,43 @@ pub struct SearchRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct PaginationRequest {
     /// Page number (1-indexed)
     pub page: u32,
 
     /// Number of items per page
+    #[serde(alias = "per_page")]
     pub per_page: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct SearchFilters {
     /// Filter by genres (e.g., "Action", "Romance", "Comedy")
     pub genres: Option<Vec<String>>,
 
+    /// Exclude series tagged with any of these genres
+    #[serde(alias = "excluded_genres")]
+    pub excluded_genres: Option<Vec<String>>,
+
     /// Filter by publication status
     pub status: Option<Status>,
 
     /// Filter by content rating
+    #[serde(alias = "content_rating")]
     pub content_rating: Option<ContentRating>,
 
     /// Filter by original language
     pub language: Option<LanguageKey>,
 
-    /// Filter by publication year range (start_year, end_year)
-    pub year_range: Option<(u32, u32)>,
+    /// Filter by publication year range (start_year, end_year), either end may be open
+    #[serde(alias = "year_range")]
+    pub year_range: Option<(Option<u32>, Option<u32>)>,
 
     /// Filter by author names
     pub authors: Option<Vec<String>>,
@@ -50,24 +69,44 @@ pub struct SearchFilters {
     /// Custom filters specific to individual manga sources
     /// Key-value pairs where the key is the filter name and value is the filter value
     /// Examples: {"sort_by": "latest"}, {"has_cover": "true"}, {"min_chapters": "10"}
+    #[serde(alias = "custom_filters")]
     pub custom_filters: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "schema",
+    derive(schemars::JsonSchema),
+    schemars(rename = "PaginatedResponse_for_{T}")
+)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct PaginatedResponse<T> {
+    /// Format version the payload was read in, it is always written in the current one
+    #[serde(
+        default = "legacy_format_version",
+        deserialize_with = "deserialize_format_version",
+        serialize_with = "serialize_format_version"
+    )]
+    pub format_version: u32,
+
     /// The actual items for this page
     pub items: Vec<T>,
 
     /// Current page number (1-indexed)
+    #[serde(alias = "current_page")]
     pub current_page: u32,
 
     /// Total number of pages (if known by the source)
+    #[serde(alias = "total_pages")]
     pub total_pages: Option<u32>,
 
     /// Whether there's a next page available
+    #[serde(alias = "has_next_page")]
     pub has_next_page: bool,
 
     /// Total number of items (if known by the source)
+    #[serde(alias = "total_items")]
     pub total_items: Option<u32>,
 }
 
@@ -80,21 +119,6 @@ impl Default for PaginationRequest {
     }
 }
 
-impl Default for SearchFilters {
-    fn default() -> Self {
-        Self {
-            genres: None,
-            status: None,
-            content_rating: None,
-            language: None,
-            year_range: None,
-            authors: None,
-            artists: None,
-            custom_filters: HashMap::new(),
-        }
-    }
-}
-
 impl SearchRequest {
     /// Create a simple search request with just a query
     pub fn simple(query: String) -> Self {
@@ -137,6 +161,7 @@ impl<T> PaginatedResponse<T> {
     /// Create a new paginated response
     pub fn new(items: Vec<T>, current_page: u32, has_next_page: bool) -> Self {
         Self {
+            format_version: FORMAT_VERSION,
             items,
             current_page,
             total_pages: None,
@@ -153,6 +178,7 @@ impl<T> PaginatedResponse<T> {
         total_items: u32,
     ) -> Self {
         Self {
+            format_version: FORMAT_VERSION,
             items,
             current_page,
             total_pages: Some(total_pages),
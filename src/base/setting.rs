@@ -1,5 +1,16 @@
-use crate::base::LanguageKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
+use crate::{
+    base::LanguageKey,
+    error::{SourceError, SourceResult},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
 pub enum SettingType {
     String(String),
     Boolean(bool),
@@ -7,10 +18,124 @@ pub enum SettingType {
     LanguageKey(LanguageKey),
 }
 
+impl SettingType {
+    /// Whether two values hold the same kind of setting
+    pub fn same_kind(&self, other: &SettingType) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct SourceSetting {
     pub key: String,
     pub name: String,
     pub description: String,
+    #[serde(alias = "default_type")]
     pub default_type: SettingType,
     pub required: bool,
 }
+
+/// Current values of the settings a source declares
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SettingValues {
+    values: HashMap<String, SettingType>,
+}
+
+/// Setting values shared between a source and whatever updates them
+pub type SharedSettings = Arc<RwLock<SettingValues>>;
+
+impl SettingValues {
+    /// Start from the default value of every setting
+    pub fn from_defaults(settings: &[SourceSetting]) -> Self {
+        Self {
+            values: settings
+                .iter()
+                .map(|setting| (setting.key.clone(), setting.default_type.clone()))
+                .collect(),
+        }
+    }
+
+    /// Add the default value of every setting that has no value yet
+    pub fn insert_defaults(&mut self, settings: &[SourceSetting]) {
+        for setting in settings {
+            self.values
+                .entry(setting.key.clone())
+                .or_insert_with(|| setting.default_type.clone());
+        }
+    }
+
+    pub fn shared(self) -> SharedSettings {
+        Arc::new(RwLock::new(self))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SettingType> {
+        self.values.get(key)
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        match self.values.get(key)? {
+            SettingType::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key)? {
+            SettingType::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_number(&self, key: &str) -> Option<f64> {
+        match self.values.get(key)? {
+            SettingType::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_language(&self, key: &str) -> Option<LanguageKey> {
+        match self.values.get(key)? {
+            SettingType::LanguageKey(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn values(&self) -> &HashMap<String, SettingType> {
+        &self.values
+    }
+
+    /// Update values, rejecting keys that are not declared or values of the wrong kind.
+    ///
+    /// Nothing is changed if any value is rejected.
+    pub fn update(
+        &mut self,
+        settings: &[SourceSetting],
+        values: HashMap<String, SettingType>,
+    ) -> SourceResult<()> {
+        for (key, value) in &values {
+            let Some(setting) = settings.iter().find(|setting| &setting.key == key) else {
+                return Err(SourceError::ConfigurationError {
+                    setting: key.clone(),
+                    expected: "a declared setting".to_string(),
+                    current: "unknown key".to_string(),
+                });
+            };
+            if !setting.default_type.same_kind(value) {
+                return Err(SourceError::ConfigurationError {
+                    setting: key.clone(),
+                    expected: format!("{:?}", setting.default_type),
+                    current: format!("{:?}", value),
+                });
+            }
+        }
+
+        self.values.extend(values);
+        Ok(())
+    }
+}
@@ -1,20 +1,146 @@
-use std::pin::Pin;
-
 use crate::{
-    base::{HomeSection, PaginatedResponse, SearchRequest},
-    error::SourceResult,
-    generate::{Series, SeriesEntry},
+    base::{HomeSection, PaginatedResponse, PaginationRequest, SearchRequest, SourceCapabilities},
+    error::{AsyncSourceResult, SourceError},
+    generate::{LatestUpdate, Series, SeriesEntry},
 };
 
+pub type LatestUpdatesFn =
+    fn(PaginationRequest) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>>;
+
+pub type ViewMoreFn =
+    fn(section_id: String, PaginationRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>;
+
+pub type HomeSectionsFn = fn() -> AsyncSourceResult<Vec<HomeSection>>;
+
+pub type HomeSectionFn = fn(section_id: String) -> AsyncSourceResult<HomeSection>;
+
+/// The operations of a source.
+///
+/// Operations are plain functions. A source that needs state, such as a root
+/// directory or a connection to an extension, implements `StatefulSource` and
+/// is exposed through `SourceInstance::bind`.
+#[derive(Clone, Copy)]
 pub struct SourceInstance {
-    pub home_page: fn() -> Pin<Box<dyn Future<Output = SourceResult<Vec<HomeSection>>>>>,
-    pub search: fn(
-        SearchRequest,
-    )
-        -> Pin<Box<dyn Future<Output = SourceResult<PaginatedResponse<SeriesEntry>>>>>,
-    pub series: fn(id: String) -> Pin<Box<dyn Future<Output = SourceResult<Series>>>>,
-    pub chapter: fn(
-        series_id: Option<String>,
-        chapter_id: String,
-    ) -> Pin<Box<dyn Future<Output = SourceResult<Vec<String>>>>>,
+    pub home_page: fn() -> AsyncSourceResult<Vec<HomeSection>>,
+    pub search: fn(SearchRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>,
+    pub series: fn(id: String) -> AsyncSourceResult<Series>,
+    pub chapter:
+        fn(series_id: Option<String>, chapter_id: String) -> AsyncSourceResult<Vec<String>>,
+
+    /// Recently updated series, newest first (`None` if the source has no such listing)
+    pub latest_updates: Option<LatestUpdatesFn>,
+
+    /// Full paginated list behind a `HomeSection` with `contain_more_items`
+    pub view_more: Option<ViewMoreFn>,
+
+    /// Home page sections, the ones marked as skeletons resolved one by one through `home_section`
+    pub home_sections: Option<HomeSectionsFn>,
+
+    /// Resolve the entries of a single home section
+    pub home_section: Option<HomeSectionFn>,
+}
+
+impl SourceInstance {
+    /// Create a source from its required operations
+    pub fn new(
+        home_page: fn() -> AsyncSourceResult<Vec<HomeSection>>,
+        search: fn(SearchRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>,
+        series: fn(String) -> AsyncSourceResult<Series>,
+        chapter: fn(Option<String>, String) -> AsyncSourceResult<Vec<String>>,
+    ) -> Self {
+        Self {
+            home_page,
+            search,
+            series,
+            chapter,
+            latest_updates: None,
+            view_more: None,
+            home_sections: None,
+            home_section: None,
+        }
+    }
+
+    /// Builder method to provide the latest-updates feed
+    pub fn with_latest_updates(mut self, latest_updates: LatestUpdatesFn) -> Self {
+        self.latest_updates = Some(latest_updates);
+        self
+    }
+
+    /// Builder method to let home sections be expanded
+    pub fn with_view_more(mut self, view_more: ViewMoreFn) -> Self {
+        self.view_more = Some(view_more);
+        self
+    }
+
+    /// Builder method to load the home page section by section
+    pub fn with_lazy_home_page(
+        mut self,
+        home_sections: HomeSectionsFn,
+        home_section: HomeSectionFn,
+    ) -> Self {
+        self.home_sections = Some(home_sections);
+        self.home_section = Some(home_section);
+        self
+    }
+
+    /// Drop the optional operations `capabilities` does not list
+    pub fn with_capabilities(mut self, capabilities: SourceCapabilities) -> Self {
+        if !capabilities.latest_updates {
+            self.latest_updates = None;
+        }
+        if !capabilities.view_more {
+            self.view_more = None;
+        }
+        if !capabilities.lazy_home_page {
+            self.home_sections = None;
+            self.home_section = None;
+        }
+        self
+    }
+
+    pub fn supports_latest_updates(&self) -> bool {
+        self.latest_updates.is_some()
+    }
+
+    /// Fetch the latest-updates feed, failing with `UnsupportedOperation` if there is none
+    pub fn fetch_latest_updates(
+        &self,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+        match self.latest_updates {
+            Some(latest_updates) => latest_updates(pagination),
+            None => unsupported("latest_updates", &["series"]),
+        }
+    }
+
+    /// Whether the home page can be loaded section by section
+    pub fn supports_lazy_home_page(&self) -> bool {
+        self.home_sections.is_some() && self.home_section.is_some()
+    }
+
+    pub fn supports_view_more(&self) -> bool {
+        self.view_more.is_some()
+    }
+
+    /// Fetch a page of the list behind a home section, failing with
+    /// `UnsupportedOperation` if the source cannot expand sections
+    pub fn fetch_view_more(
+        &self,
+        section_id: String,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        match self.view_more {
+            Some(view_more) => view_more(section_id, pagination),
+            None => unsupported("view_more", &["home_page", "search"]),
+        }
+    }
+}
+
+/// A future failing with `UnsupportedOperation`
+pub(crate) fn unsupported<T>(operation: &str, alternatives: &[&str]) -> AsyncSourceResult<T> {
+    let error = SourceError::UnsupportedOperation {
+        operation: operation.to_string(),
+        alternatives: alternatives.iter().map(|name| name.to_string()).collect(),
+    };
+    Box::pin(async move { Err(error) })
 }
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::base::{LanguageKey, SourceInstance, SourceSetting};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum ContentRating {
     Everyone,
@@ -8,3 +12,94 @@ pub enum ContentRating {
     Adult,
     Multi,
 }
+
+/// The optional operations a source implements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SourceCapabilities {
+    #[serde(alias = "latest_updates")]
+    pub latest_updates: bool,
+    #[serde(alias = "view_more")]
+    pub view_more: bool,
+    #[serde(alias = "lazy_home_page")]
+    pub lazy_home_page: bool,
+}
+
+impl SourceCapabilities {
+    pub fn of(source: &SourceInstance) -> Self {
+        Self {
+            latest_updates: source.supports_latest_updates(),
+            view_more: source.supports_view_more(),
+            lazy_home_page: source.supports_lazy_home_page(),
+        }
+    }
+}
+
+/// Describes a source to the app before any of its operations is called
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SourceInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub languages: Vec<LanguageKey>,
+    #[serde(alias = "content_rating")]
+    pub content_rating: ContentRating,
+    #[serde(default, alias = "base_url")]
+    pub base_url: Option<String>,
+    #[serde(default, alias = "icon_url")]
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub capabilities: SourceCapabilities,
+    #[serde(default)]
+    pub settings: Vec<SourceSetting>,
+}
+
+impl SourceInfo {
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        version: impl Into<String>,
+        content_rating: ContentRating,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            version: version.into(),
+            languages: Vec::new(),
+            content_rating,
+            base_url: None,
+            icon_url: None,
+            capabilities: SourceCapabilities::default(),
+            settings: Vec::new(),
+        }
+    }
+
+    /// Builder method to set the languages the source serves
+    pub fn with_languages(mut self, languages: Vec<LanguageKey>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Builder method to set the website of the source
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Builder method to declare a setting
+    pub fn with_setting(mut self, setting: SourceSetting) -> Self {
+        self.settings.push(setting);
+        self
+    }
+
+    /// Builder method to record which optional operations a source implements
+    pub fn with_capabilities_of(mut self, source: &SourceInstance) -> Self {
+        self.capabilities = SourceCapabilities::of(source);
+        self
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{
+    base::{
+        HomeSection, PaginatedResponse, PaginationRequest, SearchRequest, SourceCapabilities,
+        SourceInstance, source::unsupported,
+    },
+    error::{AsyncSourceResult, SourceError, SourceResult},
+    generate::{LatestUpdate, Series, SeriesEntry},
+};
+
+/// Number of stateful sources one process can bind
+pub const MAX_STATEFUL_SOURCES: usize = 64;
+
+/// A source whose operations share state, such as a library root or a
+/// connection to an extension.
+///
+/// The optional operations are only exposed when `capabilities` lists them.
+pub trait StatefulSource: Send + Sync + 'static {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>>;
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>;
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series>;
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>>;
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities::default()
+    }
+
+    fn latest_updates(
+        self: Arc<Self>,
+        _pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+        unsupported("latest_updates", &["series"])
+    }
+
+    fn view_more(
+        self: Arc<Self>,
+        _section_id: String,
+        _pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        unsupported("view_more", &["home_page", "search"])
+    }
+
+    fn home_sections(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        unsupported("home_sections", &["home_page"])
+    }
+
+    fn home_section(self: Arc<Self>, _section_id: String) -> AsyncSourceResult<HomeSection> {
+        unsupported("home_section", &["home_page"])
+    }
+}
+
+static SLOTS: [OnceLock<Arc<dyn StatefulSource>>; MAX_STATEFUL_SOURCES] =
+    [const { OnceLock::new() }; MAX_STATEFUL_SOURCES];
+
+impl SourceInstance {
+    /// Expose a stateful source through plain function operations.
+    ///
+    /// The source is parked in one of `MAX_STATEFUL_SOURCES` process-wide slots
+    /// that the operations look up. Slots are never released, so bind a source
+    /// once and copy the instance around.
+    pub fn bind(source: Arc<impl StatefulSource>) -> SourceResult<Self> {
+        let capabilities = source.capabilities();
+        let source: Arc<dyn StatefulSource> = source;
+        let slot = SLOTS
+            .iter()
+            .position(|slot| slot.set(source.clone()).is_ok())
+            .ok_or_else(|| SourceError::Unexpected {
+                message: format!(
+                    "Every one of the {} stateful source slots is in use",
+                    MAX_STATEFUL_SOURCES
+                ),
+                error_code: None,
+            })?;
+
+        Ok(bound_instance(slot).with_capabilities(capabilities))
+    }
+}
+
+fn slot<const SLOT: usize>() -> Arc<dyn StatefulSource> {
+    SLOTS[SLOT]
+        .get()
+        .expect("operations are only handed out for bound slots")
+        .clone()
+}
+
+fn home_page<const SLOT: usize>() -> AsyncSourceResult<Vec<HomeSection>> {
+    slot::<SLOT>().home_page()
+}
+
+fn search<const SLOT: usize>(
+    request: SearchRequest,
+) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+    slot::<SLOT>().search(request)
+}
+
+fn series<const SLOT: usize>(series_id: String) -> AsyncSourceResult<Series> {
+    slot::<SLOT>().series(series_id)
+}
+
+fn chapter<const SLOT: usize>(
+    series_id: Option<String>,
+    chapter_id: String,
+) -> AsyncSourceResult<Vec<String>> {
+    slot::<SLOT>().chapter(series_id, chapter_id)
+}
+
+fn latest_updates<const SLOT: usize>(
+    pagination: PaginationRequest,
+) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+    slot::<SLOT>().latest_updates(pagination)
+}
+
+fn view_more<const SLOT: usize>(
+    section_id: String,
+    pagination: PaginationRequest,
+) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+    slot::<SLOT>().view_more(section_id, pagination)
+}
+
+fn home_sections<const SLOT: usize>() -> AsyncSourceResult<Vec<HomeSection>> {
+    slot::<SLOT>().home_sections()
+}
+
+fn home_section<const SLOT: usize>(section_id: String) -> AsyncSourceResult<HomeSection> {
+    slot::<SLOT>().home_section(section_id)
+}
+
+fn instance<const SLOT: usize>() -> SourceInstance {
+    SourceInstance::new(
+        home_page::<SLOT>,
+        search::<SLOT>,
+        series::<SLOT>,
+        chapter::<SLOT>,
+    )
+    .with_latest_updates(latest_updates::<SLOT>)
+    .with_view_more(view_more::<SLOT>)
+    .with_lazy_home_page(home_sections::<SLOT>, home_section::<SLOT>)
+}
+
+macro_rules! bound_instance {
+    ($($slot:literal)*) => {
+        /// Every operation of the source in `slot`
+        fn bound_instance(slot: usize) -> SourceInstance {
+            match slot {
+                $($slot => instance::<$slot>(),)*
+                _ => unreachable!("slot {} is out of range", slot),
+            }
+        }
+    };
+}
+
+bound_instance!(
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+    32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61
+    62 63
+);
@@ -4,6 +4,8 @@ use std::{future::Future, pin::Pin};
 use thiserror::Error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum ParseContentType {
     SearchResults,
@@ -16,6 +18,8 @@ pub enum ParseContentType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum BlockType {
     Geographic,
@@ -26,6 +30,8 @@ pub enum BlockType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum ErrorSeverity {
     Info,
@@ -35,54 +41,76 @@ pub enum ErrorSeverity {
 }
 
 #[derive(Debug, Error, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum SourceError {
     #[error("Network connection failed: {message}")]
+    #[serde(alias = "NetworkError")]
     NetworkError { message: String, retryable: bool },
 
     #[error("Request timeout after {timeout:?}")]
-    Timeout { timeout: Duration },
+    #[serde(alias = "Timeout")]
+    Timeout {
+        #[cfg_attr(feature = "ts", ts(type = "Duration"))]
+        timeout: Duration,
+    },
 
     #[error("Rate limited - retry after {retry_after} seconds")]
+    #[serde(alias = "RateLimit")]
     RateLimit {
+        #[serde(alias = "retry_after")]
         retry_after: u64,
         message: Option<String>,
     },
 
     #[error("Authentication required: {reason}")]
+    #[serde(alias = "AuthRequired")]
     AuthRequired {
         reason: String,
+        #[serde(alias = "auth_url")]
         auth_url: Option<String>,
     },
 
     #[error("Access forbidden: {reason}")]
+    #[serde(alias = "Forbidden")]
     Forbidden { reason: String },
 
     #[error("Content not found: {resource}")]
+    #[serde(alias = "NotFound")]
     NotFound {
         resource: String,
         suggestion: Option<String>,
     },
 
     #[error("Failed to parse content: {details}")]
+    #[serde(alias = "ParseError")]
     ParseError {
         details: String,
+        #[serde(alias = "content_type")]
         content_type: ParseContentType,
+        #[serde(alias = "structure_changed")]
         structure_changed: bool,
     },
 
     #[error("Invalid search query: {reason}")]
+    #[serde(alias = "InvalidQuery")]
     InvalidQuery {
         reason: String,
         suggestions: Vec<String>,
     },
 
     #[error("Source temporarily unavailable: {reason}")]
+    #[serde(alias = "SourceUnavailable")]
     SourceUnavailable {
         reason: String,
+        #[cfg_attr(feature = "ts", ts(type = "Duration | null"))]
+        #[serde(alias = "estimated_downtime")]
         estimated_downtime: Option<Duration>,
     },
 
     #[error("Source configuration error: {setting}")]
+    #[serde(alias = "ConfigurationError")]
     ConfigurationError {
         setting: String,
         expected: String,
@@ -90,46 +118,63 @@ pub enum SourceError {
     },
 
     #[error("Unsupported operation: {operation}")]
+    #[serde(alias = "UnsupportedOperation")]
     UnsupportedOperation {
         operation: String,
         alternatives: Vec<String>,
     },
 
     #[error("Invalid pagination: {reason}")]
+    #[serde(alias = "InvalidPagination")]
     InvalidPagination {
         reason: String,
+        #[serde(alias = "max_allowed")]
         max_allowed: Option<u32>,
     },
 
     #[error("Filter validation failed: {filter_name} - {reason}")]
+    #[serde(alias = "InvalidFilter")]
     InvalidFilter {
+        #[serde(alias = "filter_name")]
         filter_name: String,
         reason: String,
+        #[serde(alias = "valid_values")]
         valid_values: Vec<String>,
     },
 
     #[error("Content blocked: {reason}")]
+    #[serde(alias = "ContentBlocked")]
     ContentBlocked {
         reason: String,
+        #[serde(alias = "block_type")]
         block_type: BlockType,
     },
 
     #[error("Image loading failed: {url}")]
+    #[serde(alias = "ImageError")]
     ImageError { url: String, src: Option<String> },
 
     #[error("Unexpected error: {message}")]
+    #[serde(alias = "Unexpected")]
     Unexpected {
         message: String,
+        #[serde(alias = "error_code")]
         error_code: Option<String>,
     },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct ErrorContext {
     pub error: SourceError,
     pub severity: ErrorSeverity,
+    #[serde(alias = "user_message")]
     pub user_message: String,
+    #[serde(alias = "suggested_actions")]
     pub suggested_actions: Vec<String>,
+    #[serde(alias = "should_report")]
     pub should_report: bool,
     pub timestamp: u64,
 }
@@ -325,3 +370,21 @@ impl IntoSourceError for url::ParseError {
         )
     }
 }
+
+impl IntoSourceError for std::io::Error {
+    fn into_source_error(self) -> SourceError {
+        SourceError::Unexpected {
+            message: format!("I/O error: {}", self),
+            error_code: Some(format!("{:?}", self.kind())),
+        }
+    }
+}
+
+impl IntoSourceError for zip::result::ZipError {
+    fn into_source_error(self) -> SourceError {
+        SourceError::Unexpected {
+            message: format!("Archive error: {}", self),
+            error_code: None,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    base::SourceInstance,
+    error::{IntoSourceError, SourceResult},
+    export::{ComicInfo, DEFAULT_PAGE_CONCURRENCY, PageImage, download_pages},
+    generate::{Chapter, Series},
+};
+
+/// Write pages and their `ComicInfo.xml` as a CBZ archive
+pub fn write_cbz<W: Write + Seek>(
+    writer: W,
+    pages: &[PageImage],
+    comic_info: &ComicInfo,
+) -> SourceResult<W> {
+    let mut zip = ZipWriter::new(writer);
+    // Images are already compressed, deflating them again only costs time
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("ComicInfo.xml", deflated)
+        .map_err(IntoSourceError::into_source_error)?;
+    zip.write_all(comic_info.to_xml().as_bytes())
+        .map_err(IntoSourceError::into_source_error)?;
+
+    for (index, page) in pages.iter().enumerate() {
+        zip.start_file(page.file_name(index, pages.len()), stored)
+            .map_err(IntoSourceError::into_source_error)?;
+        zip.write_all(&page.data)
+            .map_err(IntoSourceError::into_source_error)?;
+    }
+
+    zip.finish().map_err(IntoSourceError::into_source_error)
+}
+
+/// Download a chapter from its source and save it as `<directory>/<series> - <chapter>.cbz`
+pub async fn export_chapter_cbz(
+    source: &SourceInstance,
+    client: &reqwest::Client,
+    series: &Series,
+    chapter: &Chapter,
+    directory: impl AsRef<Path>,
+) -> SourceResult<PathBuf> {
+    let urls = (source.chapter)(Some(series.series_id.clone()), chapter.id.clone()).await?;
+    let pages = download_pages(client, &urls, DEFAULT_PAGE_CONCURRENCY).await?;
+    let comic_info = ComicInfo::from_chapter(series, chapter).with_page_count(pages.len());
+
+    std::fs::create_dir_all(directory.as_ref()).map_err(IntoSourceError::into_source_error)?;
+    let path = directory.as_ref().join(format!(
+        "{} - {}.cbz",
+        sanitize_file_name(&series.title),
+        sanitize_file_name(&chapter_label(chapter)),
+    ));
+
+    write_through_partial(&path, |file| write_cbz(file, &pages, &comic_info))?;
+    Ok(path)
+}
+
+/// Write an export to a `.part` file next to `path` and move it into place once
+/// complete, so a failed export never leaves a truncated file behind
+pub(crate) fn write_through_partial(
+    path: &Path,
+    write: impl FnOnce(File) -> SourceResult<File>,
+) -> SourceResult<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let written = File::create(&partial)
+        .map_err(IntoSourceError::into_source_error)
+        .and_then(write);
+    if let Err(error) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(error);
+    }
+
+    std::fs::rename(&partial, path).map_err(IntoSourceError::into_source_error)
+}
+
+/// Human readable chapter name, e.g. "Vol. 2 Ch. 10"
+pub fn chapter_label(chapter: &Chapter) -> String {
+    let mut parts = Vec::new();
+    if !chapter.volume_number.trim().is_empty() {
+        parts.push(format!("Vol. {}", chapter.volume_number.trim()));
+    }
+    if !chapter.chapter_number.trim().is_empty() {
+        parts.push(format!("Ch. {}", chapter.chapter_number.trim()));
+    }
+    if parts.is_empty() {
+        return chapter.title.clone();
+    }
+    parts.join(" ")
+}
+
+/// Replace characters that are not allowed in file names on common file systems
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let trimmed = sanitized.trim().trim_end_matches('.');
+    if trimmed.is_empty() {
+        "untitled".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{
+    base::{ContentRating, LanguageKey},
+    generate::{Chapter, Series},
+};
+
+/// `AgeRating` values of the ComicRack schema
+const AGE_RATINGS: [&str; 15] = [
+    "Unknown",
+    "Adults Only 18+",
+    "Early Childhood",
+    "Everyone",
+    "Everyone 10+",
+    "G",
+    "Kids to Adults",
+    "M",
+    "MA15+",
+    "Mature 17+",
+    "PG",
+    "R18+",
+    "Rating Pending",
+    "Teen",
+    "X18+",
+];
+
+/// `Manga` values of the ComicRack schema
+const MANGA_VALUES: [&str; 4] = ["Unknown", "No", "Yes", "YesAndRightToLeft"];
+
+/// Metadata written to `ComicInfo.xml` (ComicRack schema v2.1, which added `Translator`)
+#[derive(Debug, Clone, Default)]
+pub struct ComicInfo {
+    pub title: Option<String>,
+    pub series: String,
+    pub number: Option<String>,
+    pub volume: Option<u32>,
+    pub summary: Option<String>,
+    pub year: Option<u32>,
+    pub writer: Vec<String>,
+    pub penciller: Vec<String>,
+    pub translator: Option<String>,
+    pub genre: Vec<String>,
+    pub web: Option<String>,
+    pub page_count: Option<usize>,
+    pub language_iso: Option<String>,
+    pub age_rating: Option<&'static str>,
+    pub manga: Option<&'static str>,
+}
+
+impl ComicInfo {
+    /// Build the metadata of a chapter from its series
+    pub fn from_chapter(series: &Series, chapter: &Chapter) -> Self {
+        let non_empty = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+
+        Self {
+            title: non_empty(&chapter.title),
+            series: series.title.clone(),
+            number: non_empty(&chapter.chapter_number),
+            volume: chapter.volume_number.trim().parse().ok(),
+            summary: non_empty(&series.description),
+            year: series.year,
+            writer: series.author.clone(),
+            penciller: series.artist.clone(),
+            translator: non_empty(&chapter.group_name),
+            genre: series.tags.clone(),
+            web: None,
+            page_count: None,
+            language_iso: language_iso(series.original_language),
+            age_rating: Some(age_rating(series)),
+            manga: Some(manga(series)),
+        }
+    }
+
+    /// Builder method to set the number of pages
+    pub fn with_page_count(mut self, page_count: usize) -> Self {
+        self.page_count = Some(page_count);
+        self
+    }
+
+    /// Builder method to override the language, e.g. with the language of a translation
+    pub fn with_language(mut self, language: LanguageKey) -> Self {
+        self.language_iso = language_iso(language);
+        self
+    }
+
+    /// Builder method to set the web page of the chapter
+    pub fn with_web(mut self, url: impl Into<String>) -> Self {
+        self.web = Some(url.into());
+        self
+    }
+
+    /// Builder method for series read left to right
+    pub fn left_to_right(mut self) -> Self {
+        self.manga = Some("Yes");
+        self
+    }
+
+    /// Serialize to the `ComicInfo.xml` document
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n",
+        );
+
+        let mut element = |name: &str, value: Option<String>| {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, escape_xml(&value)));
+            }
+        };
+        let join = |values: &[String], separator: &str| {
+            (!values.is_empty()).then(|| values.join(separator))
+        };
+
+        element("Title", self.title.clone());
+        element("Series", Some(self.series.clone()));
+        element("Number", self.number.clone());
+        element("Volume", self.volume.map(|volume| volume.to_string()));
+        element("Summary", self.summary.clone());
+        element("Year", self.year.map(|year| year.to_string()));
+        element("Writer", join(&self.writer, ", "));
+        element("Penciller", join(&self.penciller, ", "));
+        element("Translator", self.translator.clone());
+        element("Genre", join(&self.genre, ","));
+        element("Web", self.web.clone());
+        element("PageCount", self.page_count.map(|count| count.to_string()));
+        element("LanguageISO", self.language_iso.clone());
+        element("Manga", self.manga.map(str::to_string));
+        element("AgeRating", self.age_rating.map(str::to_string));
+
+        xml.push_str("</ComicInfo>\n");
+        xml
+    }
+
+    /// Read a `ComicInfo.xml` document, ignoring elements this crate does not use
+    pub fn parse(xml: &str) -> Self {
+        let text = |name: &str| xml_element(xml, name).filter(|value| !value.is_empty());
+        let list = |name: &str| {
+            text(name)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let known = |name: &str, values: &[&'static str]| {
+            let value = text(name)?;
+            values
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(&value))
+                .copied()
+        };
+
+        Self {
+            title: text("Title"),
+            series: text("Series").unwrap_or_default(),
+            number: text("Number"),
+            volume: text("Volume").and_then(|volume| volume.parse().ok()),
+            summary: text("Summary"),
+            year: text("Year").and_then(|year| year.parse().ok()),
+            writer: list("Writer"),
+            penciller: list("Penciller"),
+            translator: text("Translator"),
+            genre: list("Genre"),
+            web: text("Web"),
+            page_count: text("PageCount").and_then(|count| count.parse().ok()),
+            language_iso: text("LanguageISO"),
+            age_rating: known("AgeRating", &AGE_RATINGS),
+            manga: known("Manga", &MANGA_VALUES),
+        }
+    }
+
+    /// The content rating implied by `AgeRating`, if it is known
+    pub fn content_rating(&self) -> Option<ContentRating> {
+        match self.age_rating? {
+            "Adults Only 18+" | "X18+" | "R18+" => Some(ContentRating::Adult),
+            "Mature 17+" | "MA15+" | "M" | "Teen" => Some(ContentRating::Mature),
+            "Everyone" | "Everyone 10+" | "Early Childhood" | "G" | "PG" | "Kids to Adults" => {
+                Some(ContentRating::Everyone)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `AgeRating` marks explicit content
+    pub fn is_explicit(&self) -> bool {
+        self.age_rating == Some("X18+")
+    }
+}
+
+/// Map the series content rating to a ComicInfo `AgeRating` value
+pub fn age_rating(series: &Series) -> &'static str {
+    if series.hentai {
+        return "X18+";
+    }
+
+    match series.content_rating {
+        Some(ContentRating::Everyone) => "Everyone",
+        Some(ContentRating::Mature) => "Mature 17+",
+        Some(ContentRating::Adult) => "Adults Only 18+",
+        Some(ContentRating::Multi) | None => "Unknown",
+    }
+}
+
+/// Map the original language of the series to a ComicInfo `Manga` value.
+///
+/// Japanese series read right to left, Korean and Chinese ones are manga-style
+/// comics read left to right. Nothing is known about the others.
+pub fn manga(series: &Series) -> &'static str {
+    match series.original_language {
+        LanguageKey::Japanese => "YesAndRightToLeft",
+        LanguageKey::Korean | LanguageKey::ChineseSimp | LanguageKey::ChineseTrad => "Yes",
+        _ => "Unknown",
+    }
+}
+
+fn language_iso(language: LanguageKey) -> Option<String> {
+    let iso = language.iso639_1();
+    (!iso.is_empty()).then(|| iso.to_string())
+}
+
+pub(crate) fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Text of the first `<name>` element, with entities decoded.
+///
+/// Attributes are skipped and a self-closing `<name/>` reads as empty text.
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let close = format!("</{}", name);
+    let mut from = 0;
+
+    loop {
+        let start = from + xml[from..].find(&open)? + open.len();
+        let rest = &xml[start..];
+        from = start;
+
+        // `<Series` is also the start of `<SeriesGroup>`
+        if !rest.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+
+        let tag_end = rest.find('>')?;
+        if rest[..tag_end].trim_end().ends_with('/') {
+            return Some(String::new());
+        }
+
+        let content = &rest[tag_end + 1..];
+        let end = content.find(&close)?;
+        return Some(unescape_xml(content[..end].trim()));
+    }
+}
+
+fn unescape_xml(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let Some(semicolon) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semicolon];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match decoded {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[semicolon + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -0,0 +1,655 @@
+use futures::{
+    Stream, StreamExt,
+    channel::mpsc::{self, UnboundedSender},
+    future::ready,
+    stream::{self, FuturesUnordered},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{
+    base::SourceInstance,
+    error::{ErrorContext, IntoSourceError, SourceError, SourceResult},
+    export::{
+        DEFAULT_PAGE_CONCURRENCY, PageImage, chapter_label, download_page, image_dimensions,
+        image_extension, sanitize_file_name,
+    },
+    generate::{Chapter, Series},
+    library::ChapterKey,
+    utils::unix_timestamp,
+    wire::{FORMAT_VERSION, check_format_version, legacy_format_version},
+};
+
+/// Name of the queue file inside the download directory
+const QUEUE_FILE: &str = "downloads.json";
+
+/// Minimum time between two queue saves caused by saved pages; state changes are saved at once
+const PAGE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(
+    rename_all = "camelCase",
+    tag = "state",
+    rename_all_fields = "camelCase"
+)]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Completed {
+        #[serde(alias = "completed_at")]
+        completed_at: u64,
+    },
+    Failed {
+        error: ErrorContext,
+    },
+}
+
+/// A page written to disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPage {
+    #[serde(alias = "file_name")]
+    pub file_name: String,
+    pub size: u64,
+
+    /// CRC-32 of the file, missing for pages saved by older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+}
+
+/// A chapter in the download queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadTask {
+    #[serde(flatten)]
+    pub key: ChapterKey,
+    #[serde(alias = "series_title")]
+    pub series_title: String,
+    #[serde(alias = "chapter_label")]
+    pub chapter_label: String,
+
+    /// Directory of the chapter, relative to the download directory
+    pub directory: PathBuf,
+    pub state: DownloadState,
+
+    /// One slot per page of the chapter, filled once the page is on disk
+    pub pages: Vec<Option<SavedPage>>,
+    #[serde(alias = "queued_at")]
+    pub queued_at: u64,
+}
+
+impl DownloadTask {
+    pub fn saved_pages(&self) -> usize {
+        self.pages.iter().flatten().count()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            DownloadState::Completed { .. } | DownloadState::Failed { .. }
+        )
+    }
+}
+
+/// Progress of a download run
+#[derive(Debug, Clone)]
+pub enum DownloadEvent {
+    /// The page list of a chapter was fetched and its pages are being downloaded
+    Started { key: ChapterKey, total_pages: usize },
+
+    PageSaved {
+        key: ChapterKey,
+        saved: usize,
+        total: usize,
+    },
+
+    /// A request failed with a retryable error and will be tried again
+    Retrying {
+        key: ChapterKey,
+        page: Option<usize>,
+        attempt: u32,
+        retry_after: Duration,
+    },
+
+    /// Every page was saved and verified against its checksum
+    Completed { key: ChapterKey, directory: PathBuf },
+
+    Failed {
+        key: ChapterKey,
+        error: ErrorContext,
+    },
+
+    /// The queue could not be written to disk, progress may be lost on a crash
+    QueueSaveFailed(ErrorContext),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadQueue {
+    #[serde(default = "legacy_format_version")]
+    format_version: u32,
+    tasks: Vec<DownloadTask>,
+}
+
+impl Default for DownloadQueue {
+    fn default() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            tasks: Vec::new(),
+        }
+    }
+}
+
+/// Downloads queued chapters into a directory, one folder of page images per chapter.
+///
+/// The queue is stored as `downloads.json` in the download directory. State
+/// changes are saved at once and page progress at most every few seconds, so an
+/// interrupted run picks up where it stopped: pages already on disk are kept and
+/// only the missing or damaged ones are fetched again. A chapter is only marked
+/// completed once every page on disk is an image matching its checksum.
+pub struct DownloadManager<'a> {
+    root: PathBuf,
+    client: reqwest::Client,
+    sources: HashMap<String, &'a SourceInstance>,
+    source_limits: HashMap<String, usize>,
+    default_source_limit: usize,
+    max_concurrent: usize,
+    page_concurrency: usize,
+    max_retries: u32,
+    queue: Mutex<DownloadQueue>,
+    last_saved: Mutex<Instant>,
+}
+
+impl<'a> DownloadManager<'a> {
+    /// Open the download directory, restoring the queue of a previous run
+    pub fn open(root: impl Into<PathBuf>, client: reqwest::Client) -> SourceResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root).map_err(IntoSourceError::into_source_error)?;
+
+        let mut queue: DownloadQueue = match std::fs::read(root.join(QUEUE_FILE)) {
+            Ok(json) => {
+                serde_json::from_slice(&json).map_err(IntoSourceError::into_source_error)?
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => DownloadQueue::default(),
+            Err(error) => return Err(error.into_source_error()),
+        };
+        check_format_version(QUEUE_FILE, queue.format_version)?;
+        // Older queues are rewritten in the current format on the next save
+        queue.format_version = FORMAT_VERSION;
+
+        // Chapters that were downloading when the previous run stopped are resumed
+        for task in &mut queue.tasks {
+            if matches!(task.state, DownloadState::Downloading) {
+                task.state = DownloadState::Queued;
+            }
+        }
+
+        Ok(Self {
+            root,
+            client,
+            sources: HashMap::new(),
+            source_limits: HashMap::new(),
+            default_source_limit: 2,
+            max_concurrent: 4,
+            page_concurrency: DEFAULT_PAGE_CONCURRENCY,
+            max_retries: 3,
+            queue: Mutex::new(queue),
+            last_saved: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Builder method to register a source
+    pub fn with_source(mut self, source_id: impl Into<String>, source: &'a SourceInstance) -> Self {
+        self.sources.insert(source_id.into(), source);
+        self
+    }
+
+    /// Builder method to limit how many chapters of one source download at the same time
+    pub fn with_source_limit(mut self, source_id: impl Into<String>, limit: usize) -> Self {
+        self.source_limits.insert(source_id.into(), limit.max(1));
+        self
+    }
+
+    /// Builder method to set the limit of sources without their own
+    pub fn with_default_source_limit(mut self, limit: usize) -> Self {
+        self.default_source_limit = limit.max(1);
+        self
+    }
+
+    /// Builder method to limit how many chapters download at the same time overall
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Builder method to set how many pages of a chapter download at the same time
+    pub fn with_page_concurrency(mut self, page_concurrency: usize) -> Self {
+        self.page_concurrency = page_concurrency.max(1);
+        self
+    }
+
+    /// Builder method to set how often a retryable request is tried again
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Queue a chapter, returning `false` if it is already in the queue
+    pub fn enqueue(&self, series: &Series, chapter: &Chapter) -> SourceResult<bool> {
+        let key = ChapterKey::from(chapter);
+        let label = chapter_label(chapter);
+        {
+            let mut queue = self.lock();
+            if queue.tasks.iter().any(|task| task.key == key) {
+                return Ok(false);
+            }
+
+            queue.tasks.push(DownloadTask {
+                directory: Path::new(&sanitize_file_name(&key.source_id))
+                    .join(folder_name(&series.title, &key.series_id))
+                    .join(folder_name(&label, &key.chapter_id)),
+                key,
+                series_title: series.title.clone(),
+                chapter_label: label,
+                state: DownloadState::Queued,
+                pages: Vec::new(),
+                queued_at: unix_timestamp(),
+            });
+        }
+
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Remove a chapter from the queue, keeping the files already downloaded
+    pub fn remove(&self, key: &ChapterKey) -> SourceResult<Option<DownloadTask>> {
+        let removed = {
+            let mut queue = self.lock();
+            let index = queue.tasks.iter().position(|task| &task.key == key);
+            index.map(|index| queue.tasks.remove(index))
+        };
+
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Queue every failed chapter again
+    pub fn retry_failed(&self) -> SourceResult<usize> {
+        let retried = {
+            let mut queue = self.lock();
+            let mut retried = 0;
+            for task in &mut queue.tasks {
+                if matches!(task.state, DownloadState::Failed { .. }) {
+                    task.state = DownloadState::Queued;
+                    retried += 1;
+                }
+            }
+            retried
+        };
+
+        if retried > 0 {
+            self.save()?;
+        }
+        Ok(retried)
+    }
+
+    pub fn tasks(&self) -> Vec<DownloadTask> {
+        self.lock().tasks.clone()
+    }
+
+    pub fn task(&self, key: &ChapterKey) -> Option<DownloadTask> {
+        self.lock()
+            .tasks
+            .iter()
+            .find(|task| &task.key == key)
+            .cloned()
+    }
+
+    /// Absolute directory of a queued chapter
+    pub fn chapter_directory(&self, key: &ChapterKey) -> Option<PathBuf> {
+        self.task(key).map(|task| self.root.join(task.directory))
+    }
+
+    /// Download every queued chapter, yielding progress until the queue is drained.
+    ///
+    /// Chapters are started in queue order as long as neither the overall limit
+    /// nor the limit of their source is reached.
+    pub fn run(&self) -> impl Stream<Item = DownloadEvent> + '_ {
+        let (sender, receiver) = mpsc::unbounded();
+        let driver = stream::once(self.drive(sender)).filter_map(|_| ready(None));
+        stream::select(receiver, driver)
+    }
+
+    async fn drive(&self, events: UnboundedSender<DownloadEvent>) {
+        let mut active: HashMap<String, usize> = HashMap::new();
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            while running.len() < self.max_concurrent {
+                let Some(key) = self.next_queued(&active) else {
+                    break;
+                };
+                *active.entry(key.source_id.clone()).or_default() += 1;
+                self.update(&key, &events, |task| {
+                    task.state = DownloadState::Downloading
+                });
+
+                let events = events.clone();
+                running.push(async move {
+                    let result = self.download_chapter(&key, &events).await;
+                    (key, result)
+                });
+            }
+
+            let Some((key, result)) = running.next().await else {
+                break;
+            };
+            if let Some(count) = active.get_mut(&key.source_id) {
+                *count = count.saturating_sub(1);
+            }
+
+            let event = match result {
+                Ok(directory) => {
+                    self.update(&key, &events, |task| {
+                        task.state = DownloadState::Completed {
+                            completed_at: unix_timestamp(),
+                        }
+                    });
+                    DownloadEvent::Completed { key, directory }
+                }
+                Err(error) => {
+                    let error = error.to_context();
+                    let state = DownloadState::Failed {
+                        error: error.clone(),
+                    };
+                    self.update(&key, &events, |task| task.state = state);
+                    DownloadEvent::Failed { key, error }
+                }
+            };
+            let _ = events.unbounded_send(event);
+        }
+    }
+
+    fn next_queued(&self, active: &HashMap<String, usize>) -> Option<ChapterKey> {
+        self.lock()
+            .tasks
+            .iter()
+            .filter(|task| matches!(task.state, DownloadState::Queued))
+            .find(|task| {
+                let limit = self
+                    .source_limits
+                    .get(&task.key.source_id)
+                    .copied()
+                    .unwrap_or(self.default_source_limit);
+                active.get(&task.key.source_id).copied().unwrap_or(0) < limit
+            })
+            .map(|task| task.key.clone())
+    }
+
+    async fn download_chapter(
+        &self,
+        key: &ChapterKey,
+        events: &UnboundedSender<DownloadEvent>,
+    ) -> SourceResult<PathBuf> {
+        let Some(source) = self.sources.get(&key.source_id) else {
+            return Err(SourceError::not_found_with_suggestion(
+                format!("source '{}'", key.source_id),
+                "Install or enable the source",
+            ));
+        };
+        let directory = self.chapter_directory(key).ok_or_else(|| {
+            SourceError::not_found_with_suggestion("download task", "Queue the chapter again")
+        })?;
+        std::fs::create_dir_all(&directory).map_err(IntoSourceError::into_source_error)?;
+
+        let urls = self
+            .with_retries(key, None, events, || {
+                (source.chapter)(Some(key.series_id.clone()), key.chapter_id.clone())
+            })
+            .await?;
+        let total = urls.len();
+
+        // Pages saved by an earlier run are kept as long as the page count did not change
+        let mut missing = Vec::new();
+        self.update(key, events, |task| {
+            if task.pages.len() != total {
+                task.pages = vec![None; total];
+            }
+            for (index, page) in task.pages.iter_mut().enumerate() {
+                if page
+                    .as_ref()
+                    .is_none_or(|saved| !is_saved(&directory, saved))
+                {
+                    *page = None;
+                    missing.push(index);
+                }
+            }
+        });
+        let _ = events.unbounded_send(DownloadEvent::Started {
+            key: key.clone(),
+            total_pages: total,
+        });
+
+        let (urls, page_directory) = (&urls, &directory);
+        let mut downloads = stream::iter(missing)
+            .map(|index| async move {
+                let url = &urls[index];
+                let image = self
+                    .with_retries(key, Some(index), events, || {
+                        let client = self.client.clone();
+                        let url = url.clone();
+                        Box::pin(async move { download_page(&client, &url).await })
+                    })
+                    .await?;
+                if !is_image(&image.data) {
+                    return Err(SourceError::ImageError {
+                        url: url.clone(),
+                        src: Some("the response is not a supported image".to_string()),
+                    });
+                }
+                let saved = save_page(page_directory, &image, index, total)?;
+                Ok::<_, SourceError>((index, saved))
+            })
+            .buffer_unordered(self.page_concurrency);
+
+        while let Some(result) = downloads.next().await {
+            let (index, saved) = result?;
+            let mut saved_count = 0;
+            self.update_page(key, events, |task| {
+                task.pages[index] = Some(saved);
+                saved_count = task.saved_pages();
+            });
+            let _ = events.unbounded_send(DownloadEvent::PageSaved {
+                key: key.clone(),
+                saved: saved_count,
+                total,
+            });
+        }
+        drop(downloads);
+
+        self.verify(key, &directory)?;
+        Ok(directory)
+    }
+
+    /// Run a request, retrying it while it fails with a retryable error
+    async fn with_retries<T, F>(
+        &self,
+        key: &ChapterKey,
+        page: Option<usize>,
+        events: &UnboundedSender<DownloadEvent>,
+        request: impl Fn() -> F,
+    ) -> SourceResult<T>
+    where
+        F: Future<Output = SourceResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(error) if error.is_retryable() && attempt < self.max_retries => {
+                    attempt += 1;
+                    let retry_after =
+                        Duration::from_secs(error.retry_after().unwrap_or(1 << attempt.min(6)));
+                    let _ = events.unbounded_send(DownloadEvent::Retrying {
+                        key: key.clone(),
+                        page,
+                        attempt,
+                        retry_after,
+                    });
+                    tokio::time::sleep(retry_after).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Check that every page of a chapter is on disk, unchanged since it was downloaded
+    fn verify(&self, key: &ChapterKey, directory: &Path) -> SourceResult<()> {
+        let task = self.task(key).ok_or_else(|| {
+            SourceError::not_found_with_suggestion("download task", "Queue the chapter again")
+        })?;
+
+        let missing: Vec<usize> = task
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| {
+                !page
+                    .as_ref()
+                    .is_some_and(|saved| is_saved(directory, saved))
+            })
+            .map(|(index, _)| index + 1)
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(SourceError::Unexpected {
+            message: format!(
+                "Chapter {} is missing pages {:?} after downloading",
+                task.chapter_label, missing
+            ),
+            error_code: None,
+        })
+    }
+
+    /// Apply a change to a task and save the queue
+    fn update(
+        &self,
+        key: &ChapterKey,
+        events: &UnboundedSender<DownloadEvent>,
+        change: impl FnOnce(&mut DownloadTask),
+    ) {
+        self.change_task(key, change);
+        self.save_or_report(events);
+    }
+
+    /// Apply a page change to a task, saving the queue only if the last save is old enough
+    fn update_page(
+        &self,
+        key: &ChapterKey,
+        events: &UnboundedSender<DownloadEvent>,
+        change: impl FnOnce(&mut DownloadTask),
+    ) {
+        self.change_task(key, change);
+        if self.last_saved().elapsed() >= PAGE_SAVE_INTERVAL {
+            self.save_or_report(events);
+        }
+    }
+
+    fn change_task(&self, key: &ChapterKey, change: impl FnOnce(&mut DownloadTask)) {
+        if let Some(task) = self.lock().tasks.iter_mut().find(|task| &task.key == key) {
+            change(task);
+        }
+    }
+
+    fn save_or_report(&self, events: &UnboundedSender<DownloadEvent>) {
+        if let Err(error) = self.save() {
+            let _ = events.unbounded_send(DownloadEvent::QueueSaveFailed(error.to_context()));
+        }
+    }
+
+    /// Write the queue to disk, replacing the previous file atomically
+    fn save(&self) -> SourceResult<()> {
+        let json = serde_json::to_vec(&*self.lock()).map_err(IntoSourceError::into_source_error)?;
+        let path = self.root.join(QUEUE_FILE);
+        let partial = path.with_extension("json.part");
+        std::fs::write(&partial, json).map_err(IntoSourceError::into_source_error)?;
+        std::fs::rename(partial, path).map_err(IntoSourceError::into_source_error)?;
+
+        *self.last_saved() = Instant::now();
+        Ok(())
+    }
+
+    fn last_saved(&self) -> std::sync::MutexGuard<'_, Instant> {
+        self.last_saved
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DownloadQueue> {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Readable folder name that stays unique through the id, since titles and
+/// chapter numbers repeat across series and scanlation groups
+fn folder_name(name: &str, id: &str) -> String {
+    sanitize_file_name(&format!("{} [{}]", name, id))
+}
+
+/// Write a page through a temporary file so a crash never leaves a truncated page behind
+fn save_page(
+    directory: &Path,
+    image: &PageImage,
+    index: usize,
+    total: usize,
+) -> SourceResult<SavedPage> {
+    let file_name = image.file_name(index, total);
+    let path = directory.join(&file_name);
+    let partial = directory.join(format!("{}.part", file_name));
+
+    std::fs::write(&partial, &image.data).map_err(IntoSourceError::into_source_error)?;
+    std::fs::rename(&partial, &path).map_err(IntoSourceError::into_source_error)?;
+
+    Ok(SavedPage {
+        file_name,
+        size: image.data.len() as u64,
+        checksum: Some(crc32fast::hash(&image.data)),
+    })
+}
+
+/// Whether the page file is an image with the size and checksum it was saved with
+fn is_saved(directory: &Path, saved: &SavedPage) -> bool {
+    let Ok(data) = std::fs::read(directory.join(&saved.file_name)) else {
+        return false;
+    };
+
+    data.len() as u64 == saved.size
+        && saved
+            .checksum
+            .is_none_or(|checksum| crc32fast::hash(&data) == checksum)
+        && is_image(&data)
+}
+
+/// Whether the data starts with the header of a supported image format
+fn is_image(data: &[u8]) -> bool {
+    // AVIF sizes live deep in the container, recognizing the brand is enough
+    image_dimensions(data).is_some() || image_extension(data, "") == "avif"
+}
@@ -0,0 +1,399 @@
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    base::{LanguageKey, SourceInstance},
+    error::{IntoSourceError, SourceResult},
+    export::{
+        DEFAULT_PAGE_CONCURRENCY, PageImage, cbz::write_through_partial, chapter_label,
+        comic_info::escape_xml, download_page, download_pages, sanitize_file_name,
+    },
+    generate::{Chapter, Series},
+};
+
+/// Viewport used for pages whose size cannot be read from the image
+const FALLBACK_PAGE_SIZE: (u32, u32) = (800, 1200);
+
+/// Image types every EPUB 3 reading system has to support
+const CORE_IMAGE_TYPES: [&str; 5] = [
+    "image/gif",
+    "image/jpeg",
+    "image/png",
+    "image/svg+xml",
+    "image/webp",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct EpubOptions {
+    /// Turn pages right to left, as most manga are read
+    pub right_to_left: bool,
+
+    /// Language of the book, defaults to the series' original language
+    pub language: Option<LanguageKey>,
+}
+
+impl EpubOptions {
+    /// Builder method to set right-to-left page progression
+    pub fn with_right_to_left(mut self, right_to_left: bool) -> Self {
+        self.right_to_left = right_to_left;
+        self
+    }
+
+    /// Builder method to override the book language
+    pub fn with_language(mut self, language: LanguageKey) -> Self {
+        self.language = Some(language);
+        self
+    }
+}
+
+/// A chapter and its downloaded pages
+#[derive(Debug, Clone)]
+pub struct EpubChapter {
+    pub chapter: Chapter,
+    pub pages: Vec<PageImage>,
+}
+
+struct EpubPage<'a> {
+    id: String,
+    image: &'a PageImage,
+}
+
+/// Write a fixed-layout EPUB 3 book with one page per image and one TOC entry per chapter
+pub fn write_epub<W: Write + Seek>(
+    writer: W,
+    series: &Series,
+    cover: Option<&PageImage>,
+    chapters: &[EpubChapter],
+    options: &EpubOptions,
+) -> SourceResult<W> {
+    let mut zip = ZipWriter::new(writer);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let add = |zip: &mut ZipWriter<W>, name: &str, data: &[u8], options| {
+        zip.start_file(name, options)
+            .map_err(IntoSourceError::into_source_error)?;
+        zip.write_all(data)
+            .map_err(IntoSourceError::into_source_error)
+    };
+
+    // The mimetype has to be the first entry and must not be compressed
+    add(&mut zip, "mimetype", b"application/epub+zip", stored)?;
+    add(
+        &mut zip,
+        "META-INF/container.xml",
+        CONTAINER_XML.as_bytes(),
+        deflated,
+    )?;
+
+    let cover = cover.map(|image| EpubPage {
+        id: "cover".to_string(),
+        image,
+    });
+    let chapter_pages: Vec<Vec<EpubPage>> = chapters
+        .iter()
+        .enumerate()
+        .map(|(chapter_index, chapter)| {
+            chapter
+                .pages
+                .iter()
+                .enumerate()
+                .map(|(page_index, image)| EpubPage {
+                    id: format!("c{:03}_p{:03}", chapter_index + 1, page_index + 1),
+                    image,
+                })
+                .collect()
+        })
+        .collect();
+
+    for page in cover.iter().chain(chapter_pages.iter().flatten()) {
+        add(
+            &mut zip,
+            &format!("OEBPS/images/{}.{}", page.id, page.image.extension),
+            &page.image.data,
+            stored,
+        )?;
+        add(
+            &mut zip,
+            &format!("OEBPS/pages/{}.xhtml", page.id),
+            page_xhtml(series, page).as_bytes(),
+            deflated,
+        )?;
+    }
+
+    add(
+        &mut zip,
+        "OEBPS/nav.xhtml",
+        nav_xhtml(series, chapters, &chapter_pages).as_bytes(),
+        deflated,
+    )?;
+    add(
+        &mut zip,
+        "OEBPS/content.opf",
+        content_opf(series, cover.as_ref(), chapters, &chapter_pages, options).as_bytes(),
+        deflated,
+    )?;
+
+    zip.finish().map_err(IntoSourceError::into_source_error)
+}
+
+/// Download chapters and the cover and save them as `<directory>/<series>.epub`.
+///
+/// A cover that fails to download is left out rather than failing the export.
+pub async fn export_series_epub(
+    source: &SourceInstance,
+    client: &reqwest::Client,
+    series: &Series,
+    chapters: &[Chapter],
+    directory: impl AsRef<Path>,
+    options: &EpubOptions,
+) -> SourceResult<PathBuf> {
+    let mut downloaded = Vec::with_capacity(chapters.len());
+    for chapter in chapters {
+        let urls = (source.chapter)(Some(series.series_id.clone()), chapter.id.clone()).await?;
+        downloaded.push(EpubChapter {
+            chapter: chapter.clone(),
+            pages: download_pages(client, &urls, DEFAULT_PAGE_CONCURRENCY).await?,
+        });
+    }
+
+    let cover = if series.cover_url.is_empty() {
+        None
+    } else {
+        download_page(client, &series.cover_url).await.ok()
+    };
+
+    std::fs::create_dir_all(directory.as_ref()).map_err(IntoSourceError::into_source_error)?;
+    let file_name = match chapters {
+        [chapter] => format!(
+            "{} - {}.epub",
+            sanitize_file_name(&series.title),
+            sanitize_file_name(&chapter_label(chapter))
+        ),
+        _ => format!("{}.epub", sanitize_file_name(&series.title)),
+    };
+    let path = directory.as_ref().join(file_name);
+
+    write_through_partial(&path, |file| {
+        write_epub(file, series, cover.as_ref(), &downloaded, options)
+    })?;
+    Ok(path)
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// The markup showing a page image.
+///
+/// Images outside the core media types, such as AVIF, are embedded as an
+/// `object` whose text is shown by reading systems that cannot display them.
+fn page_image(page: &EpubPage) -> String {
+    let source = format!("../images/{}.{}", page.id, page.image.extension);
+    let mime_type = page.image.mime_type();
+    if CORE_IMAGE_TYPES.contains(&mime_type) {
+        return format!("<img src=\"{}\" alt=\"\"/>", source);
+    }
+
+    format!(
+        "<object data=\"{}\" type=\"{}\"><p>This page is a {} image, which this reader cannot show.</p></object>",
+        source, mime_type, mime_type
+    )
+}
+
+fn page_xhtml(series: &Series, page: &EpubPage) -> String {
+    let (width, height) = page.image.dimensions().unwrap_or(FALLBACK_PAGE_SIZE);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <title>{title}</title>
+  <meta name="viewport" content="width={width}, height={height}"/>
+  <style>html, body {{ margin: 0; padding: 0; }} img, object {{ display: block; width: {width}px; height: {height}px; }}</style>
+</head>
+<body>
+  {image}
+</body>
+</html>
+"#,
+        title = escape_xml(&series.title),
+        image = page_image(page),
+    )
+}
+
+fn toc_title(chapter: &Chapter) -> String {
+    let label = chapter_label(chapter);
+    let title = chapter.title.trim();
+    if title.is_empty() || title == label {
+        label
+    } else {
+        format!("{}: {}", label, title)
+    }
+}
+
+fn nav_xhtml(series: &Series, chapters: &[EpubChapter], chapter_pages: &[Vec<EpubPage>]) -> String {
+    let entries: String = chapters
+        .iter()
+        .zip(chapter_pages)
+        .filter_map(|(chapter, pages)| {
+            let first = pages.first()?;
+            Some(format!(
+                "      <li><a href=\"pages/{}.xhtml\">{}</a></li>\n",
+                first.id,
+                escape_xml(&toc_title(&chapter.chapter))
+            ))
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <title>{title}</title>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>{title}</h1>
+    <ol>
+{entries}    </ol>
+  </nav>
+</body>
+</html>
+"#,
+        title = escape_xml(&series.title),
+    )
+}
+
+/// Identifies the book by its series and the chapters it holds, so exports of
+/// different chapter selections are told apart by reading systems
+fn book_identifier(series: &Series, chapters: &[EpubChapter]) -> String {
+    let mut hasher = crc32fast::Hasher::new();
+    for chapter in chapters {
+        hasher.update(chapter.chapter.id.as_bytes());
+        hasher.update(&[0]);
+    }
+    format!(
+        "urn:rrmounter:{}:{}:{:08x}",
+        series.source_id,
+        series.series_id,
+        hasher.finalize()
+    )
+}
+
+fn content_opf(
+    series: &Series,
+    cover: Option<&EpubPage>,
+    chapters: &[EpubChapter],
+    chapter_pages: &[Vec<EpubPage>],
+    options: &EpubOptions,
+) -> String {
+    let language = options
+        .language
+        .unwrap_or(series.original_language)
+        .iso639_1();
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut metadata = vec![
+        format!(
+            "<dc:identifier id=\"book-id\">{}</dc:identifier>",
+            escape_xml(&book_identifier(series, chapters))
+        ),
+        format!("<dc:title>{}</dc:title>", escape_xml(&series.title)),
+        format!(
+            "<dc:language>{}</dc:language>",
+            if language.is_empty() { "und" } else { language }
+        ),
+        format!("<meta property=\"dcterms:modified\">{}</meta>", modified),
+        "<meta property=\"rendition:layout\">pre-paginated</meta>".to_string(),
+        "<meta property=\"rendition:orientation\">portrait</meta>".to_string(),
+        "<meta property=\"rendition:spread\">none</meta>".to_string(),
+    ];
+    for (index, creator) in series.author.iter().chain(&series.artist).enumerate() {
+        let role = if index < series.author.len() {
+            "aut"
+        } else {
+            "art"
+        };
+        metadata.push(format!(
+            "<dc:creator id=\"creator{0}\">{1}</dc:creator>\n    <meta refines=\"#creator{0}\" property=\"role\" scheme=\"marc:relators\">{2}</meta>",
+            index,
+            escape_xml(creator),
+            role
+        ));
+    }
+    if !series.description.trim().is_empty() {
+        metadata.push(format!(
+            "<dc:description>{}</dc:description>",
+            escape_xml(series.description.trim())
+        ));
+    }
+    for tag in &series.tags {
+        metadata.push(format!("<dc:subject>{}</dc:subject>", escape_xml(tag)));
+    }
+    if let Some(cover) = cover {
+        metadata.push(format!(
+            "<meta name=\"cover\" content=\"image-{}\"/>",
+            cover.id
+        ));
+    }
+
+    let pages: Vec<&EpubPage> = cover
+        .into_iter()
+        .chain(chapter_pages.iter().flatten())
+        .collect();
+    let mut manifest = vec![
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>"
+            .to_string(),
+    ];
+    for page in &pages {
+        let properties = if page.id == "cover" {
+            " properties=\"cover-image\""
+        } else {
+            ""
+        };
+        manifest.push(format!(
+            "<item id=\"image-{0}\" href=\"images/{0}.{1}\" media-type=\"{2}\"{3}/>",
+            page.id,
+            page.image.extension,
+            page.image.mime_type(),
+            properties
+        ));
+        manifest.push(format!(
+            "<item id=\"page-{0}\" href=\"pages/{0}.xhtml\" media-type=\"application/xhtml+xml\"/>",
+            page.id
+        ));
+    }
+
+    let spine: Vec<String> = pages
+        .iter()
+        .map(|page| format!("<itemref idref=\"page-{}\"/>", page.id))
+        .collect();
+    let direction = if options.right_to_left { "rtl" } else { "ltr" };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" prefix="rendition: http://www.idpf.org/vocab/rendition/#">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    {}
+  </metadata>
+  <manifest>
+    {}
+  </manifest>
+  <spine page-progression-direction="{}">
+    {}
+  </spine>
+</package>
+"#,
+        metadata.join("\n    "),
+        manifest.join("\n    "),
+        direction,
+        spine.join("\n    "),
+    )
+}
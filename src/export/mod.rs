@@ -0,0 +1,11 @@
+pub mod cbz;
+pub mod comic_info;
+pub mod downloads;
+pub mod epub;
+pub mod pages;
+
+pub use cbz::*;
+pub use comic_info::*;
+pub use downloads::*;
+pub use epub::*;
+pub use pages::*;
@@ -0,0 +1,166 @@
+use futures::{StreamExt, TryStreamExt, stream};
+
+use crate::{
+    base::status_error,
+    error::{IntoSourceError, SourceError, SourceResult},
+};
+
+/// Number of pages downloaded at the same time by default
+pub const DEFAULT_PAGE_CONCURRENCY: usize = 4;
+
+/// A downloaded page image
+#[derive(Debug, Clone)]
+pub struct PageImage {
+    pub url: String,
+    pub data: Vec<u8>,
+    /// File extension without the dot, detected from the image data
+    pub extension: &'static str,
+}
+
+impl PageImage {
+    /// File name inside an archive, zero-padded so readers sort pages correctly
+    pub fn file_name(&self, index: usize, total: usize) -> String {
+        let width = total.max(1).to_string().len().max(3);
+        format!("{:0width$}.{}", index + 1, self.extension, width = width)
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self.extension {
+            "png" => "image/png",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "avif" => "image/avif",
+            _ => "image/jpeg",
+        }
+    }
+
+    /// Width and height in pixels, read from the image header
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        image_dimensions(&self.data)
+    }
+}
+
+/// Detect the image format from its magic bytes, falling back to the URL
+pub fn image_extension(data: &[u8], url: &str) -> &'static str {
+    match data {
+        [0xFF, 0xD8, 0xFF, ..] => "jpg",
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, rest @ ..] if rest.starts_with(b"WEBP") => "webp",
+        [_, _, _, _, rest @ ..] if rest.starts_with(b"ftypavif") => "avif",
+        _ => {
+            let path = url
+                .split(['?', '#'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            ["png", "gif", "webp", "avif"]
+                .into_iter()
+                .find(|extension| path.ends_with(&format!(".{}", extension)))
+                .unwrap_or("jpg")
+        }
+    }
+}
+
+/// Read the width and height of a JPEG, PNG, GIF or WebP image from its header
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le16 = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le24 = |at: usize| {
+        let bytes = data.get(at..at + 3)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    };
+    let be32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+
+    match image_extension(data, "") {
+        "png" => Some((be32(16)?, be32(20)?)),
+        "gif" => Some((le16(6)?, le16(8)?)),
+        "webp" => match data.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3FFF, le16(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        },
+        "jpg" if data.starts_with(&[0xFF, 0xD8]) => {
+            // Walk the segments until a start-of-frame marker, which holds the size
+            let mut at = 2;
+            while *data.get(at)? == 0xFF {
+                let marker = *data.get(at + 1)?;
+                let is_frame =
+                    matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+                if is_frame {
+                    return Some((be16(at + 7)?, be16(at + 5)?));
+                }
+                at += 2 + be16(at + 2)? as usize;
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Download a single page image over HTTP(S).
+///
+/// Other schemes are refused, pages of the local source are read through
+/// `LocalSource::read_page` instead.
+pub async fn download_page(client: &reqwest::Client, url: &str) -> SourceResult<PageImage> {
+    // Retryable errors are kept intact so callers can retry them and honour `retry_after`
+    let image_error = |error: SourceError| {
+        if error.is_retryable() {
+            return error;
+        }
+        SourceError::ImageError {
+            url: url.to_string(),
+            src: Some(error.to_string()),
+        }
+    };
+
+    let scheme = url::Url::parse(url)
+        .map(|parsed| parsed.scheme().to_string())
+        .unwrap_or_default();
+    if !matches!(scheme.as_str(), "http" | "https") {
+        return Err(SourceError::ImageError {
+            url: url.to_string(),
+            src: Some(format!("'{}' URLs cannot be downloaded", scheme)),
+        });
+    }
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|error| image_error(error.into_source_error()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(image_error(status_error(url, status, response.headers())));
+    }
+
+    let data = response
+        .bytes()
+        .await
+        .map_err(|error| image_error(error.into_source_error()))?
+        .to_vec();
+
+    Ok(PageImage {
+        url: url.to_string(),
+        extension: image_extension(&data, url),
+        data,
+    })
+}
+
+/// Download every page of a chapter in order, failing on the first page that cannot be fetched
+pub async fn download_pages(
+    client: &reqwest::Client,
+    urls: &[String],
+    concurrency: usize,
+) -> SourceResult<Vec<PageImage>> {
+    stream::iter(urls)
+        .map(|url| download_page(client, url))
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
@@ -1,16 +1,24 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct Chapter {
     // Unique identifier for the chapter
     pub id: String,
+    #[serde(alias = "series_id")]
     pub series_id: String,
+    #[serde(alias = "source_id")]
     pub source_id: String,
 
     // Basic information about the chapter
     pub title: String,
+    #[serde(alias = "chapter_number")]
     pub chapter_number: String,
+    #[serde(alias = "volume_number")]
     pub volume_number: String,
+    #[serde(alias = "group_name")]
     pub group_name: String,
 
     // Additional metadata about the chapter
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::generate::SeriesEntry;
+
+/// Short description of a chapter, as shown in update feeds and lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct LatestChapter {
+    #[serde(alias = "chapter_id")]
+    pub chapter_id: String,
+    #[serde(alias = "chapter_number")]
+    pub chapter_number: String,
+    pub title: Option<String>,
+    #[serde(alias = "group_name")]
+    pub group_name: Option<String>,
+}
+
+/// A series that recently received new chapters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct LatestUpdate {
+    pub entry: SeriesEntry,
+    pub chapters: Vec<LatestChapter>,
+
+    // When the source published the update
+    #[serde(alias = "updated_at")]
+    pub updated_at: u64,
+}
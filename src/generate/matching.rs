@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    base::ContentRating,
+    generate::{Chapter, Series, SeriesEntry, Status, best_title_similarity, normalize_title},
+};
+
+/// Minimum total score for two series to be considered the same work
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.75;
+
+const TITLE_WEIGHT: f64 = 0.6;
+const PEOPLE_WEIGHT: f64 = 0.25;
+const CHAPTERS_WEIGHT: f64 = 0.15;
+
+/// Anything that can be compared against a series from another source
+pub trait Matchable {
+    fn source_id(&self) -> &str;
+    fn series_id(&self) -> &str;
+    /// The main title followed by any alternative titles
+    fn titles(&self) -> Vec<&str>;
+    /// Authors and artists, empty when unknown
+    fn people(&self) -> Vec<&str>;
+    fn chapter_count(&self) -> Option<u64>;
+}
+
+impl Matchable for SeriesEntry {
+    fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    fn series_id(&self) -> &str {
+        &self.series_id
+    }
+
+    fn titles(&self) -> Vec<&str> {
+        vec![&self.title]
+    }
+
+    fn people(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    fn chapter_count(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Matchable for Series {
+    fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    fn series_id(&self) -> &str {
+        &self.series_id
+    }
+
+    fn titles(&self) -> Vec<&str> {
+        self.all_titles().collect()
+    }
+
+    fn people(&self) -> Vec<&str> {
+        self.author
+            .iter()
+            .chain(&self.artist)
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn chapter_count(&self) -> Option<u64> {
+        let count = self.number_chapters.max(self.chapters.len() as u64);
+        (count > 0).then_some(count)
+    }
+}
+
+/// How closely two series match, every component ranging from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MatchScore {
+    pub total: f64,
+    pub title: f64,
+    /// `None` when either side has no author/artist information
+    pub people: Option<f64>,
+    /// `None` when either side has no chapter count
+    pub chapters: Option<f64>,
+}
+
+impl MatchScore {
+    pub fn is_match(&self) -> bool {
+        self.total >= DEFAULT_MATCH_THRESHOLD
+    }
+}
+
+/// Score two series by title, alt titles, author/artist and chapter count
+pub fn score_match(a: &impl Matchable, b: &impl Matchable) -> MatchScore {
+    let title = best_title_similarity(a.titles(), b.titles());
+
+    let people = match (a.people(), b.people()) {
+        (left, right) if left.is_empty() || right.is_empty() => None,
+        (left, right) => Some(jaccard(
+            &left.iter().map(|p| normalize_title(p)).collect(),
+            &right.iter().map(|p| normalize_title(p)).collect(),
+        )),
+    };
+
+    let chapters = match (a.chapter_count(), b.chapter_count()) {
+        (Some(left), Some(right)) => {
+            Some(1.0 - left.abs_diff(right) as f64 / left.max(right) as f64)
+        }
+        _ => None,
+    };
+
+    let mut weighted = title * TITLE_WEIGHT;
+    let mut weights = TITLE_WEIGHT;
+    for (score, weight) in [(people, PEOPLE_WEIGHT), (chapters, CHAPTERS_WEIGHT)] {
+        if let Some(score) = score {
+            weighted += score * weight;
+            weights += weight;
+        }
+    }
+
+    MatchScore {
+        total: weighted / weights,
+        title,
+        people,
+        chapters,
+    }
+}
+
+/// Find the candidate that best matches `target`, if any passes the threshold
+pub fn best_match<'a, T: Matchable>(
+    target: &impl Matchable,
+    candidates: &'a [T],
+    threshold: f64,
+) -> Option<(&'a T, MatchScore)> {
+    candidates
+        .iter()
+        .map(|candidate| (candidate, score_match(target, candidate)))
+        .filter(|(_, score)| score.total >= threshold)
+        .max_by(|(_, a), (_, b)| a.total.total_cmp(&b.total))
+}
+
+/// Identifies which source a merged value came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    #[serde(alias = "series_id")]
+    pub series_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "schema",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Sourced_for_{T}")
+)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Sourced<T> {
+    pub value: T,
+    pub from: Provenance,
+}
+
+/// A unified view of the same series on several sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MergedSeries {
+    pub sources: Vec<Provenance>,
+
+    pub title: Sourced<String>,
+    #[serde(alias = "alt_titles")]
+    pub alt_titles: Vec<Sourced<String>>,
+    pub description: Sourced<String>,
+    pub status: Sourced<Status>,
+    #[serde(alias = "cover_url")]
+    pub cover_url: Sourced<String>,
+    pub chapters: Sourced<Vec<Chapter>>,
+
+    pub author: Vec<Sourced<String>>,
+    pub artist: Vec<Sourced<String>>,
+    pub tags: Vec<Sourced<String>>,
+
+    #[serde(alias = "content_rating")]
+    pub content_rating: Option<Sourced<ContentRating>>,
+    pub year: Option<Sourced<u32>>,
+}
+
+/// Merge the same series from several sources into one view.
+///
+/// Scalar fields come from `primary` unless it leaves them empty, in which
+/// case the first other source that has them wins. The chapter list comes from
+/// whichever source has the most chapters, and list fields are unioned.
+pub fn merge_series(primary: &Series, others: &[Series]) -> MergedSeries {
+    let all: Vec<&Series> = std::iter::once(primary).chain(others).collect();
+
+    let first_non_empty = |field: fn(&Series) -> &String| {
+        all.iter()
+            .find(|series| !field(series).trim().is_empty())
+            .map(|series| sourced(series, field(series).clone()))
+            .unwrap_or_else(|| sourced(primary, field(primary).clone()))
+    };
+
+    let description = all
+        .iter()
+        .find(|series| !series.description.trim().is_empty())
+        .map(|series| sourced(series, series.description.clone()))
+        .unwrap_or_else(|| sourced(primary, String::new()));
+
+    let chapters = all
+        .iter()
+        .rev()
+        .max_by_key(|series| series.chapters.len())
+        .map(|series| sourced(series, series.chapters.clone()))
+        .unwrap_or_else(|| sourced(primary, Vec::new()));
+
+    let title = first_non_empty(|series| &series.title);
+    let main_title = normalize_title(&title.value);
+    let alt_titles = union(&all, |series| {
+        std::iter::once(&series.title)
+            .chain(&series.alt_titles)
+            .collect()
+    })
+    .into_iter()
+    .filter(|title| normalize_title(&title.value) != main_title)
+    .collect();
+
+    MergedSeries {
+        sources: all.iter().map(|series| provenance(series)).collect(),
+        title,
+        alt_titles,
+        description,
+        status: sourced(primary, primary.status),
+        cover_url: first_non_empty(|series| &series.cover_url),
+        chapters,
+        author: union(&all, |series| series.author.iter().collect()),
+        artist: union(&all, |series| series.artist.iter().collect()),
+        tags: union(&all, |series| series.tags.iter().collect()),
+        content_rating: all
+            .iter()
+            .find_map(|series| Some(sourced(series, series.content_rating?))),
+        year: all
+            .iter()
+            .find_map(|series| Some(sourced(series, series.year?))),
+    }
+}
+
+fn provenance(series: &Series) -> Provenance {
+    Provenance {
+        source_id: series.source_id.clone(),
+        series_id: series.series_id.clone(),
+    }
+}
+
+fn sourced<T>(series: &Series, value: T) -> Sourced<T> {
+    Sourced {
+        value,
+        from: provenance(series),
+    }
+}
+
+/// Union a list field across sources, keeping the first occurrence of each normalized value
+fn union(all: &[&Series], field: fn(&Series) -> Vec<&String>) -> Vec<Sourced<String>> {
+    let mut seen = HashSet::new();
+    let mut values = Vec::new();
+
+    for series in all {
+        for value in field(series) {
+            let key = normalize_title(value);
+            if !key.is_empty() && seen.insert(key) {
+                values.push(sourced(series, value.clone()));
+            }
+        }
+    }
+
+    values
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
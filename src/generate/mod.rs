@@ -1,5 +1,11 @@
 pub mod chapter;
+pub mod latest;
+pub mod matching;
 pub mod series;
+pub mod title;
 
 pub use chapter::*;
+pub use latest::*;
+pub use matching::*;
 pub use series::*;
+pub use title::*;
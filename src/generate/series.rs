@@ -1,26 +1,130 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{base::LanguageKey, generate::Chapter};
+use crate::{
+    base::{Badge, ContentRating, LanguageKey},
+    generate::{Chapter, LatestChapter},
+    wire::{
+        FORMAT_VERSION, deserialize_format_version, legacy_format_version, serialize_format_version,
+    },
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct SeriesEntry {
+    /// Format version the payload was read in, it is always written in the current one
+    #[serde(
+        default = "legacy_format_version",
+        deserialize_with = "deserialize_format_version",
+        serialize_with = "serialize_format_version"
+    )]
+    pub format_version: u32,
+
+    #[serde(alias = "source_id")]
     pub source_id: String,
+    #[serde(alias = "series_id")]
     pub series_id: String,
     pub title: String,
+    #[serde(alias = "cover_url")]
     pub cover_url: String,
+
+    // Optional details for list rendering, omitted from the JSON when empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<Badge>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "latest_chapter"
+    )]
+    pub latest_chapter: Option<LatestChapter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<LanguageKey>,
+}
+
+impl SeriesEntry {
+    /// Create an entry with only the required fields
+    pub fn new(
+        source_id: impl Into<String>,
+        series_id: impl Into<String>,
+        title: impl Into<String>,
+        cover_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            source_id: source_id.into(),
+            series_id: series_id.into(),
+            title: title.into(),
+            cover_url: cover_url.into(),
+            subtitle: None,
+            badges: Vec::new(),
+            latest_chapter: None,
+            rating: None,
+            languages: Vec::new(),
+        }
+    }
+
+    /// Builder method to add a subtitle, e.g. the author or an alternative title
+    pub fn with_subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Builder method to add a badge such as "NEW" or "HOT"
+    pub fn with_badge(mut self, badge: Badge) -> Self {
+        self.badges.push(badge);
+        self
+    }
+
+    /// Builder method to set the most recent chapter
+    pub fn with_latest_chapter(mut self, chapter: LatestChapter) -> Self {
+        self.latest_chapter = Some(chapter);
+        self
+    }
+
+    /// Builder method to set the rating, on a 0 to 10 scale
+    pub fn with_rating(mut self, rating: f32) -> Self {
+        self.rating = Some(rating.clamp(0.0, 10.0));
+        self
+    }
+
+    /// Builder method to set the languages the series is available in
+    pub fn with_languages(mut self, languages: Vec<LanguageKey>) -> Self {
+        self.languages = languages;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
 pub struct Series {
+    /// Format version the payload was read in, it is always written in the current one
+    #[serde(
+        default = "legacy_format_version",
+        deserialize_with = "deserialize_format_version",
+        serialize_with = "serialize_format_version"
+    )]
+    pub format_version: u32,
+
     // Unique identifier for the manga
+    #[serde(alias = "source_id")]
     pub source_id: String,
+    #[serde(alias = "series_id")]
     pub series_id: String,
 
     // Basic information about the manga
     pub title: String,
+    #[serde(alias = "alt_titles")]
     pub alt_titles: Vec<String>,
     pub description: String,
     pub status: Status,
+    #[serde(alias = "cover_url")]
     pub cover_url: String,
     pub chapters: Vec<Chapter>,
 
@@ -31,14 +135,23 @@ pub struct Series {
 
     // Information for important filtering
     pub hentai: bool,
+    #[serde(alias = "original_language")]
     pub original_language: LanguageKey,
+    #[serde(default, alias = "content_rating")]
+    pub content_rating: Option<ContentRating>,
+    #[serde(default)]
+    pub year: Option<u32>,
 
     // Statistics about the manga
+    #[serde(alias = "number_unread")]
     pub number_unread: u64,
+    #[serde(alias = "number_chapters")]
     pub number_chapters: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
 pub enum Status {
     Ongoing,
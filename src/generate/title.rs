@@ -0,0 +1,343 @@
+use std::collections::BTreeSet;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use crate::generate::{Series, SeriesEntry};
+
+/// Bracketed suffixes that sources add to titles without changing the work
+const NOISE_SUFFIXES: [&str; 12] = [
+    "official",
+    "official translation",
+    "digital",
+    "colored",
+    "coloured",
+    "full color",
+    "fan colored",
+    "webtoon",
+    "manhwa",
+    "manhua",
+    "raw",
+    "english",
+];
+
+/// Romanization spellings folded onto a single form, applied in order
+const ROMANIZATION: [(&str, &str); 9] = [
+    ("ou", "o"),
+    ("oh", "o"),
+    ("oo", "o"),
+    ("uu", "u"),
+    ("aa", "a"),
+    ("ii", "i"),
+    ("tsu", "tu"),
+    ("shi", "si"),
+    ("chi", "ti"),
+];
+
+/// Consonants that can start a romanized Japanese syllable, longest first
+const ROMAJI_ONSETS: [&str; 31] = [
+    "ky", "gy", "sh", "sy", "ch", "ts", "ty", "zy", "dy", "ny", "hy", "by", "py", "my", "ry", "k",
+    "g", "s", "z", "t", "d", "n", "h", "f", "b", "p", "m", "y", "r", "w", "j",
+];
+
+/// Weight of a match found only after folding romanization variants, so it
+/// never beats a title that matches as written
+const ROMANIZED_WEIGHT: f64 = 0.95;
+
+/// Normalize a title for comparison.
+///
+/// Applies NFKC (which also folds full-width and half-width CJK forms),
+/// strips suffixes like "(Official)", removes diacritics from Latin, Greek and
+/// Cyrillic letters, folds punctuation and whitespace to single spaces and
+/// lowercases. Marks of other scripts, such as the dakuten of "ガ", are kept.
+pub fn normalize_title(title: &str) -> String {
+    let folded: String = title.nfkc().collect();
+    let stripped = strip_noise_suffixes(&folded);
+
+    let mut base = ' ';
+    stripped
+        .nfkd()
+        .filter(|c| {
+            if !is_combining_mark(*c) {
+                base = *c;
+                return true;
+            }
+            !has_removable_diacritics(base)
+        })
+        .collect::<String>()
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether accents on this letter are spelling variants rather than distinct letters
+fn has_removable_diacritics(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | 'a'..='z'
+        | '\u{00C0}'..='\u{024F}'
+        | '\u{1E00}'..='\u{1EFF}'
+        | '\u{0370}'..='\u{03FF}'
+        | '\u{1F00}'..='\u{1FFF}'
+        | '\u{0400}'..='\u{04FF}')
+}
+
+fn strip_noise_suffixes(title: &str) -> &str {
+    let mut title = title.trim();
+
+    loop {
+        let Some(close) = title.chars().last().filter(|c| matches!(c, ')' | ']')) else {
+            return title;
+        };
+        let open = if close == ')' { '(' } else { '[' };
+        let Some(start) = title.rfind(open) else {
+            return title;
+        };
+
+        let inner = title[start + 1..title.len() - 1].trim().to_lowercase();
+        if !NOISE_SUFFIXES.contains(&inner.as_str()) {
+            return title;
+        }
+        title = title[..start].trim_end();
+    }
+}
+
+/// Whether every word of a normalized title reads as romanized Japanese
+fn looks_romanized(title: &str) -> bool {
+    !title.is_empty()
+        && title
+            .split_whitespace()
+            .all(|word| word.bytes().all(|b| b.is_ascii_digit()) || is_romaji_word(word))
+}
+
+/// Whether a word splits into romanized Japanese syllables, e.g. "shounen" but not "school"
+fn is_romaji_word(word: &str) -> bool {
+    let is_vowel = |b: u8| matches!(b, b'a' | b'i' | b'u' | b'e' | b'o');
+    let bytes = word.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        // Syllabic "n", as in "shin" or "kanji"
+        if bytes[i] == b'n' && next.is_none_or(|b| !is_vowel(b) && b != b'y') {
+            i += 1;
+            continue;
+        }
+        // Doubled consonant, as in "kitto"
+        if !is_vowel(bytes[i]) && next == Some(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        // Long vowel written with "h", as in "ohno"
+        if bytes[i] == b'h' && i > 0 && bytes[i - 1] == b'o' && next.is_none_or(|b| !is_vowel(b)) {
+            i += 1;
+            continue;
+        }
+
+        let onset = ROMAJI_ONSETS
+            .iter()
+            .find(|onset| word[i..].starts_with(*onset))
+            .map_or(0, |onset| onset.len());
+        if !bytes.get(i + onset).copied().is_some_and(is_vowel) {
+            return false;
+        }
+        i += onset + 1;
+    }
+
+    true
+}
+
+fn fold_romanization(title: &str) -> String {
+    ROMANIZATION
+        .iter()
+        .fold(title.to_string(), |title, (from, to)| {
+            title.replace(from, to)
+        })
+}
+
+/// A title normalized once, ready to be compared against many others
+struct ComparableTitle {
+    normalized: String,
+    /// Folded romanization, only for titles that read as romanized Japanese
+    romanized: Option<String>,
+}
+
+impl ComparableTitle {
+    fn new(title: &str) -> Self {
+        let normalized = normalize_title(title);
+        let romanized = looks_romanized(&normalized).then(|| fold_romanization(&normalized));
+        Self {
+            normalized,
+            romanized,
+        }
+    }
+
+    fn similarity(&self, other: &Self) -> f64 {
+        let direct = normalized_similarity(&self.normalized, &other.normalized);
+        match (&self.romanized, &other.romanized) {
+            (Some(a), Some(b)) => direct.max(ROMANIZED_WEIGHT * normalized_similarity(a, b)),
+            _ => direct,
+        }
+    }
+}
+
+fn normalized_similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+
+    0.6 * token_set_ratio(a, b) + 0.4 * jaro_winkler(a, b)
+}
+
+/// Similarity of two titles from 0.0 to 1.0, after normalization.
+///
+/// Blends a token set ratio, which ignores word order and extra words, with
+/// Jaro-Winkler, which tolerates typos and rewards a shared prefix. When both
+/// titles read as romanized Japanese, spelling variants such as "Shounen" and
+/// "Shōnen" or "Tsu" and "Tu" are also compared folded onto a single form.
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    ComparableTitle::new(a).similarity(&ComparableTitle::new(b))
+}
+
+/// Best similarity between any title of `a` and any title of `b`
+pub fn best_title_similarity<'a>(
+    a: impl IntoIterator<Item = &'a str>,
+    b: impl IntoIterator<Item = &'a str>,
+) -> f64 {
+    let a: Vec<ComparableTitle> = a.into_iter().map(ComparableTitle::new).collect();
+    let b: Vec<ComparableTitle> = b.into_iter().map(ComparableTitle::new).collect();
+
+    a.iter()
+        .flat_map(|left| b.iter().map(move |right| left.similarity(right)))
+        .fold(0.0, f64::max)
+}
+
+impl Series {
+    /// The main title followed by the alternative titles
+    pub fn all_titles(&self) -> impl Iterator<Item = &str> + Clone {
+        std::iter::once(self.title.as_str()).chain(self.alt_titles.iter().map(String::as_str))
+    }
+
+    /// How well a title matches this series, considering `alt_titles`
+    pub fn title_similarity(&self, title: &str) -> f64 {
+        best_title_similarity(self.all_titles(), [title])
+    }
+}
+
+impl SeriesEntry {
+    /// The normalized title, suitable as a deduplication key
+    pub fn normalized_title(&self) -> String {
+        normalize_title(&self.title)
+    }
+}
+
+/// Token set ratio: compares the shared tokens against each side's full token set
+pub fn token_set_ratio(a: &str, b: &str) -> f64 {
+    let a_tokens: BTreeSet<&str> = a.split_whitespace().collect();
+    let b_tokens: BTreeSet<&str> = b.split_whitespace().collect();
+
+    let join = |tokens: Vec<&str>| tokens.join(" ");
+    let common = join(a_tokens.intersection(&b_tokens).copied().collect());
+    let with_a = join(
+        a_tokens
+            .intersection(&b_tokens)
+            .chain(a_tokens.difference(&b_tokens))
+            .copied()
+            .collect(),
+    );
+    let with_b = join(
+        a_tokens
+            .intersection(&b_tokens)
+            .chain(b_tokens.difference(&a_tokens))
+            .copied()
+            .collect(),
+    );
+
+    let mut best = levenshtein_ratio(&with_a, &with_b);
+    if !common.is_empty() {
+        best = best
+            .max(levenshtein_ratio(&common, &with_a))
+            .max(levenshtein_ratio(&common, &with_b));
+    }
+    best
+}
+
+/// Jaro-Winkler similarity with the standard 0.1 prefix scale
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0usize;
+
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && b[j] == *ca {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_seq = a
+        .iter()
+        .zip(&a_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let b_seq = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let transpositions = a_seq.zip(b_seq).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+fn levenshtein_ratio(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f64 / a.len().max(b.len()) as f64
+}
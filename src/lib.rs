@@ -1,7 +1,20 @@
 pub mod base;
+pub mod export;
 pub mod generate;
+pub mod library;
+pub mod local;
+pub mod native;
+pub mod rpc;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "ts")]
+pub mod typescript;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod error;
 pub mod utils;
+pub mod wire;
 
 pub use error::*;
-pub use utils::*;
\ No newline at end of file
+pub use utils::*;
+pub use wire::*;
\ No newline at end of file
@@ -0,0 +1,5 @@
+pub mod progress;
+pub mod updates;
+
+pub use progress::*;
+pub use updates::*;
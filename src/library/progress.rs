@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    error::{IntoSourceError, SourceError, SourceResult},
+    generate::{Chapter, Series},
+    library::SeriesSnapshot,
+    utils::unix_timestamp,
+    wire::{FORMAT_VERSION, check_format_version, legacy_format_version},
+};
+
+/// Identifies a chapter across sources
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterKey {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    #[serde(alias = "series_id")]
+    pub series_id: String,
+    #[serde(alias = "chapter_id")]
+    pub chapter_id: String,
+}
+
+impl ChapterKey {
+    pub fn new(
+        source_id: impl Into<String>,
+        series_id: impl Into<String>,
+        chapter_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            source_id: source_id.into(),
+            series_id: series_id.into(),
+            chapter_id: chapter_id.into(),
+        }
+    }
+}
+
+impl From<&Chapter> for ChapterKey {
+    fn from(chapter: &Chapter) -> Self {
+        Self::new(&chapter.source_id, &chapter.series_id, &chapter.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    /// Page index (0-indexed)
+    pub page: u32,
+    pub note: Option<String>,
+    #[serde(alias = "created_at")]
+    pub created_at: u64,
+}
+
+/// The user's reading state for one chapter
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterProgress {
+    pub read: bool,
+
+    /// Last page the user had open (0-indexed)
+    #[serde(alias = "last_page_read")]
+    pub last_page_read: u32,
+
+    /// Number of pages, when known from the reader
+    #[serde(alias = "total_pages")]
+    pub total_pages: Option<u32>,
+
+    /// When the chapter was last opened
+    #[serde(alias = "last_read_at")]
+    pub last_read_at: Option<u64>,
+
+    /// When the chapter was first finished
+    #[serde(alias = "completed_at")]
+    pub completed_at: Option<u64>,
+
+    pub bookmarks: Vec<Bookmark>,
+}
+
+/// A series the user added to their library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryEntry {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    #[serde(alias = "series_id")]
+    pub series_id: String,
+    pub title: String,
+    #[serde(alias = "cover_url")]
+    pub cover_url: String,
+    #[serde(alias = "added_at")]
+    pub added_at: u64,
+
+    /// The series as last seen by the update checker
+    #[serde(default)]
+    pub snapshot: Option<SeriesSnapshot>,
+}
+
+/// Tracks the library and reading progress, independent of what sources report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(try_from = "PersistedLibrary", into = "PersistedLibrary")]
+#[cfg_attr(feature = "ts", ts(as = "PersistedLibrary"))]
+pub struct Library {
+    entries: Vec<LibraryEntry>,
+    progress: HashMap<ChapterKey, ChapterProgress>,
+}
+
+impl Library {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a series to the library, updating title and cover if it is already there
+    pub fn add(&mut self, series: &Series) {
+        if let Some(entry) = self.entry_mut(&series.source_id, &series.series_id) {
+            entry.title = series.title.clone();
+            entry.cover_url = series.cover_url.clone();
+            return;
+        }
+
+        self.entries.push(LibraryEntry {
+            source_id: series.source_id.clone(),
+            series_id: series.series_id.clone(),
+            title: series.title.clone(),
+            cover_url: series.cover_url.clone(),
+            added_at: unix_timestamp(),
+            snapshot: Some(SeriesSnapshot::from(series)),
+        });
+    }
+
+    /// Replace the snapshot the update checker compares against
+    pub fn set_snapshot(&mut self, source_id: &str, series_id: &str, snapshot: SeriesSnapshot) {
+        if let Some(entry) = self.entry_mut(source_id, series_id) {
+            entry.title = snapshot.title.clone();
+            entry.cover_url = snapshot.cover_url.clone();
+            entry.snapshot = Some(snapshot);
+        }
+    }
+
+    /// Remove a series from the library, keeping its reading progress
+    pub fn remove(&mut self, source_id: &str, series_id: &str) -> Option<LibraryEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.source_id == source_id && entry.series_id == series_id)?;
+        Some(self.entries.remove(index))
+    }
+
+    pub fn contains(&self, source_id: &str, series_id: &str) -> bool {
+        self.entry(source_id, series_id).is_some()
+    }
+
+    pub fn entry(&self, source_id: &str, series_id: &str) -> Option<&LibraryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.source_id == source_id && entry.series_id == series_id)
+    }
+
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.entries
+    }
+
+    pub fn progress(&self, key: &ChapterKey) -> Option<&ChapterProgress> {
+        self.progress.get(key)
+    }
+
+    pub fn is_read(&self, key: &ChapterKey) -> bool {
+        self.progress.get(key).is_some_and(|progress| progress.read)
+    }
+
+    pub fn mark_read(&mut self, key: ChapterKey) {
+        let progress = self.progress.entry(key).or_default();
+        if !progress.read {
+            progress.read = true;
+            progress.completed_at.get_or_insert_with(unix_timestamp);
+        }
+    }
+
+    pub fn mark_unread(&mut self, key: &ChapterKey) {
+        if let Some(progress) = self.progress.get_mut(key) {
+            progress.read = false;
+            progress.last_page_read = 0;
+            progress.completed_at = None;
+        }
+    }
+
+    /// Mark `chapter_id` and every chapter before it as read.
+    ///
+    /// Chapters are compared by `chapter_number`; when numbers cannot be parsed,
+    /// the order in which the source lists the chapters is used instead.
+    pub fn mark_read_until(&mut self, series: &Series, chapter_id: &str) {
+        let Some(target) = series.chapters.iter().find(|c| c.id == chapter_id) else {
+            return;
+        };
+
+        let keys: Vec<ChapterKey> = match target.chapter_number.trim().parse::<f64>() {
+            Ok(until) => series
+                .chapters
+                .iter()
+                .filter(|chapter| {
+                    chapter.id == chapter_id
+                        || chapter
+                            .chapter_number
+                            .trim()
+                            .parse::<f64>()
+                            .is_ok_and(|number| number <= until)
+                })
+                .map(ChapterKey::from)
+                .collect(),
+            Err(_) => series
+                .chapters
+                .iter()
+                .take_while(|chapter| chapter.id != chapter_id)
+                .chain(std::iter::once(target))
+                .map(ChapterKey::from)
+                .collect(),
+        };
+
+        for key in keys {
+            self.mark_read(key);
+        }
+    }
+
+    /// Record the page the reader is on, marking the chapter read on its last page
+    pub fn set_last_page(&mut self, key: ChapterKey, page: u32, total_pages: Option<u32>) {
+        let progress = self.progress.entry(key).or_default();
+        progress.last_page_read = page;
+        progress.last_read_at = Some(unix_timestamp());
+        if total_pages.is_some() {
+            progress.total_pages = total_pages;
+        }
+
+        let finished = progress
+            .total_pages
+            .is_some_and(|total| total > 0 && page + 1 >= total);
+        if finished && !progress.read {
+            progress.read = true;
+            progress.completed_at.get_or_insert_with(unix_timestamp);
+        }
+    }
+
+    pub fn add_bookmark(&mut self, key: ChapterKey, page: u32, note: Option<String>) {
+        let bookmarks = &mut self.progress.entry(key).or_default().bookmarks;
+        bookmarks.retain(|bookmark| bookmark.page != page);
+        bookmarks.push(Bookmark {
+            page,
+            note,
+            created_at: unix_timestamp(),
+        });
+        bookmarks.sort_by_key(|bookmark| bookmark.page);
+    }
+
+    pub fn remove_bookmark(&mut self, key: &ChapterKey, page: u32) {
+        if let Some(progress) = self.progress.get_mut(key) {
+            progress.bookmarks.retain(|bookmark| bookmark.page != page);
+        }
+    }
+
+    /// All bookmarks of a series, with the chapter they belong to
+    pub fn bookmarks(&self, source_id: &str, series_id: &str) -> Vec<(&ChapterKey, &Bookmark)> {
+        self.progress
+            .iter()
+            .filter(|(key, _)| key.source_id == source_id && key.series_id == series_id)
+            .flat_map(|(key, progress)| progress.bookmarks.iter().map(move |b| (key, b)))
+            .collect()
+    }
+
+    /// Number of chapters of `series` the user has not read
+    pub fn number_unread(&self, series: &Series) -> u64 {
+        series
+            .chapters
+            .iter()
+            .filter(|chapter| !self.is_read(&ChapterKey::from(*chapter)))
+            .count() as u64
+    }
+
+    /// Overwrite the source-provided `Chapter.read` and `Series.number_unread`
+    /// with the library's reading state
+    pub fn apply_to(&self, series: &mut Series) {
+        for chapter in &mut series.chapters {
+            chapter.read = self.is_read(&ChapterKey::from(&*chapter));
+        }
+        series.number_unread = self.number_unread(series);
+    }
+
+    /// The most recently read chapter of a series
+    pub fn last_read(
+        &self,
+        source_id: &str,
+        series_id: &str,
+    ) -> Option<(&ChapterKey, &ChapterProgress)> {
+        self.progress
+            .iter()
+            .filter(|(key, _)| key.source_id == source_id && key.series_id == series_id)
+            .filter(|(_, progress)| progress.last_read_at.is_some())
+            .max_by_key(|(_, progress)| progress.last_read_at)
+    }
+
+    /// Write the library to `path` as JSON, replacing the previous file atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> SourceResult<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_vec(self).map_err(IntoSourceError::into_source_error)?;
+
+        // Write next to the file first so a crash never leaves it half written
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, json).map_err(IntoSourceError::into_source_error)?;
+        std::fs::rename(&temporary, path).map_err(IntoSourceError::into_source_error)
+    }
+
+    /// Load a library written with `save`, or an empty one if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> SourceResult<Self> {
+        match std::fs::read(path) {
+            Ok(json) => serde_json::from_slice(&json).map_err(IntoSourceError::into_source_error),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into_source_error()),
+        }
+    }
+
+    fn entry_mut(&mut self, source_id: &str, series_id: &str) -> Option<&mut LibraryEntry> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.source_id == source_id && entry.series_id == series_id)
+    }
+}
+
+/// On-disk layout: JSON objects cannot use structured keys, so progress is stored as records
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(rename = "Library"))]
+#[serde(rename_all = "camelCase")]
+struct PersistedLibrary {
+    #[serde(default = "legacy_format_version")]
+    format_version: u32,
+    entries: Vec<LibraryEntry>,
+    progress: Vec<ProgressRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+struct ProgressRecord {
+    #[serde(flatten)]
+    key: ChapterKey,
+    #[serde(flatten)]
+    progress: ChapterProgress,
+}
+
+impl TryFrom<PersistedLibrary> for Library {
+    type Error = SourceError;
+
+    fn try_from(persisted: PersistedLibrary) -> SourceResult<Self> {
+        check_format_version("library", persisted.format_version)?;
+        Ok(Self {
+            entries: persisted.entries,
+            progress: persisted
+                .progress
+                .into_iter()
+                .map(|record| (record.key, record.progress))
+                .collect(),
+        })
+    }
+}
+
+impl From<Library> for PersistedLibrary {
+    fn from(library: Library) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            entries: library.entries,
+            progress: library
+                .progress
+                .into_iter()
+                .map(|(key, progress)| ProgressRecord { key, progress })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::{
+    base::{PaginationRequest, SourceInstance},
+    error::{ErrorContext, SourceError, SourceResult},
+    generate::{Chapter, Series, Status},
+    library::{Library, LibraryEntry},
+    utils::unix_timestamp,
+};
+
+/// The parts of a series the update checker compares between runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesSnapshot {
+    pub title: String,
+    pub description: String,
+    pub status: Status,
+    #[serde(alias = "cover_url")]
+    pub cover_url: String,
+    pub author: Vec<String>,
+    pub artist: Vec<String>,
+    pub tags: Vec<String>,
+    pub chapters: Vec<Chapter>,
+    #[serde(alias = "taken_at")]
+    pub taken_at: u64,
+}
+
+impl From<&Series> for SeriesSnapshot {
+    fn from(series: &Series) -> Self {
+        Self {
+            title: series.title.clone(),
+            description: series.description.clone(),
+            status: series.status,
+            cover_url: series.cover_url.clone(),
+            author: series.author.clone(),
+            artist: series.artist.clone(),
+            tags: series.tags.clone(),
+            chapters: series.chapters.clone(),
+            taken_at: unix_timestamp(),
+        }
+    }
+}
+
+/// Metadata fields whose change is reported by the update checker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum MetadataField {
+    Title,
+    Description,
+    Status,
+    Cover,
+    Authors,
+    Artists,
+    Tags,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateKind {
+    NewChapter { chapter: Chapter },
+    ChapterRemoved { chapter: Chapter },
+    MetadataChanged { fields: Vec<MetadataField> },
+}
+
+/// Something that changed on a library series since the last check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateEvent {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    #[serde(alias = "series_id")]
+    pub series_id: String,
+    pub kind: UpdateKind,
+}
+
+/// A series that could not be checked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFailure {
+    #[serde(alias = "source_id")]
+    pub source_id: String,
+    #[serde(alias = "series_id")]
+    pub series_id: String,
+    pub error: ErrorContext,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateReport {
+    pub events: Vec<UpdateEvent>,
+    pub failures: Vec<UpdateFailure>,
+    pub checked: usize,
+
+    /// Series not re-fetched because the latest-updates feed showed no change
+    pub skipped: usize,
+}
+
+/// Compare two snapshots and describe what changed
+pub fn diff_snapshots(
+    source_id: &str,
+    series_id: &str,
+    previous: &SeriesSnapshot,
+    current: &SeriesSnapshot,
+) -> Vec<UpdateEvent> {
+    let event = |kind| UpdateEvent {
+        source_id: source_id.to_string(),
+        series_id: series_id.to_string(),
+        kind,
+    };
+    let previous_ids: HashSet<&str> = previous.chapters.iter().map(|c| c.id.as_str()).collect();
+    let current_ids: HashSet<&str> = current.chapters.iter().map(|c| c.id.as_str()).collect();
+
+    let mut events: Vec<UpdateEvent> = current
+        .chapters
+        .iter()
+        .filter(|chapter| !previous_ids.contains(chapter.id.as_str()))
+        .map(|chapter| {
+            event(UpdateKind::NewChapter {
+                chapter: chapter.clone(),
+            })
+        })
+        .collect();
+
+    events.extend(
+        previous
+            .chapters
+            .iter()
+            .filter(|chapter| !current_ids.contains(chapter.id.as_str()))
+            .map(|chapter| {
+                event(UpdateKind::ChapterRemoved {
+                    chapter: chapter.clone(),
+                })
+            }),
+    );
+
+    let fields: Vec<MetadataField> = [
+        (MetadataField::Title, previous.title != current.title),
+        (
+            MetadataField::Description,
+            previous.description != current.description,
+        ),
+        (MetadataField::Status, previous.status != current.status),
+        (
+            MetadataField::Cover,
+            previous.cover_url != current.cover_url,
+        ),
+        (MetadataField::Authors, previous.author != current.author),
+        (MetadataField::Artists, previous.artist != current.artist),
+        (MetadataField::Tags, previous.tags != current.tags),
+    ]
+    .into_iter()
+    .filter_map(|(field, changed)| changed.then_some(field))
+    .collect();
+
+    if !fields.is_empty() {
+        events.push(event(UpdateKind::MetadataChanged { fields }));
+    }
+
+    events
+}
+
+/// Re-fetches every library series and reports what changed.
+///
+/// Sources are checked concurrently, while the series of a single source are
+/// fetched one after another with `min_interval` between requests. A rate
+/// limited request is retried once after the delay the source asked for, unless
+/// that delay is longer than `max_retry_after`; the series is then reported as
+/// failed.
+///
+/// Sources that provide a latest-updates feed are asked for it first, and only
+/// the series that appear in it since their last snapshot are re-fetched.
+pub struct UpdateChecker<'a> {
+    sources: HashMap<String, &'a SourceInstance>,
+    min_interval: Duration,
+    timeout: Duration,
+    max_retry_after: Duration,
+    max_feed_pages: u32,
+}
+
+impl<'a> UpdateChecker<'a> {
+    pub fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+            min_interval: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(60),
+            max_feed_pages: 5,
+        }
+    }
+
+    /// Builder method to register a source
+    pub fn with_source(mut self, source_id: impl Into<String>, source: &'a SourceInstance) -> Self {
+        self.sources.insert(source_id.into(), source);
+        self
+    }
+
+    /// Builder method to set the pause between two requests to the same source
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Builder method to set how long a single series fetch may take
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Builder method to set the longest rate limit delay worth waiting for before retrying
+    pub fn with_max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// Builder method to limit how far back the latest-updates feed is read.
+    ///
+    /// When the feed does not reach back to the oldest snapshot within this many
+    /// pages, every series of the source is re-fetched instead.
+    pub fn with_max_feed_pages(mut self, max_feed_pages: u32) -> Self {
+        self.max_feed_pages = max_feed_pages;
+        self
+    }
+
+    /// Check every library entry and store the new snapshots in the library
+    pub async fn check(&self, library: &mut Library) -> UpdateReport {
+        let mut by_source: HashMap<&str, Vec<LibraryEntry>> = HashMap::new();
+        for entry in library.entries() {
+            by_source
+                .entry(entry.source_id.as_str())
+                .or_default()
+                .push(entry.clone());
+        }
+
+        let checks = by_source
+            .into_iter()
+            .map(|(source_id, entries)| self.check_source(source_id.to_string(), entries));
+        let mut report = UpdateReport::default();
+        let mut results = Vec::new();
+        for (checked, skipped) in join_all(checks).await {
+            results.extend(checked);
+            report.skipped += skipped.len();
+
+            // The feed showed nothing new up to now, so later checks can start from here
+            for entry in skipped {
+                if let Some(mut snapshot) = entry.snapshot {
+                    snapshot.taken_at = unix_timestamp();
+                    library.set_snapshot(&entry.source_id, &entry.series_id, snapshot);
+                }
+            }
+        }
+
+        for (entry, result) in results {
+            report.checked += 1;
+            match result {
+                Ok(series) => {
+                    let current = SeriesSnapshot::from(&series);
+                    if let Some(previous) = &entry.snapshot {
+                        report.events.extend(diff_snapshots(
+                            &entry.source_id,
+                            &entry.series_id,
+                            previous,
+                            &current,
+                        ));
+                    }
+                    library.set_snapshot(&entry.source_id, &entry.series_id, current);
+                }
+                Err(error) => report.failures.push(UpdateFailure {
+                    source_id: entry.source_id,
+                    series_id: entry.series_id,
+                    error: error.to_context(),
+                }),
+            }
+        }
+
+        report
+    }
+
+    /// Fetch the series of one source, returning the results and the skipped entries
+    async fn check_source(
+        &self,
+        source_id: String,
+        entries: Vec<LibraryEntry>,
+    ) -> (Vec<(LibraryEntry, SourceResult<Series>)>, Vec<LibraryEntry>) {
+        let Some(source) = self.sources.get(&source_id) else {
+            let results = entries
+                .into_iter()
+                .map(|entry| {
+                    let error = SourceError::not_found_with_suggestion(
+                        format!("source '{}'", source_id),
+                        "Install or enable the source",
+                    );
+                    (entry, Err(error))
+                })
+                .collect();
+            return (results, Vec::new());
+        };
+
+        let (entries, skipped) = match self.updated_in_feed(source, &entries).await {
+            Some(updated) => entries
+                .into_iter()
+                .partition(|entry| entry.snapshot.is_none() || updated.contains(&entry.series_id)),
+            None => (entries, Vec::new()),
+        };
+
+        let mut results = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.into_iter().enumerate() {
+            if index > 0 {
+                tokio::time::sleep(self.min_interval).await;
+            }
+
+            let mut result = self.fetch(source, &entry.series_id).await;
+            if let Err(SourceError::RateLimit { retry_after, .. }) = &result
+                && let retry_after = Duration::from_secs(*retry_after)
+                && retry_after <= self.max_retry_after
+            {
+                tokio::time::sleep(retry_after).await;
+                result = self.fetch(source, &entry.series_id).await;
+            }
+            results.push((entry, result));
+        }
+        (results, skipped)
+    }
+
+    /// Series ids the latest-updates feed lists as updated after their snapshot.
+    ///
+    /// Returns `None` when the feed is unavailable, fails, or does not reach back
+    /// far enough, in which case every series has to be fetched.
+    async fn updated_in_feed(
+        &self,
+        source: &SourceInstance,
+        entries: &[LibraryEntry],
+    ) -> Option<HashSet<String>> {
+        let latest_updates = source.latest_updates?;
+        let taken_at: HashMap<&str, u64> = entries
+            .iter()
+            .filter_map(|entry| Some((entry.series_id.as_str(), entry.snapshot.as_ref()?.taken_at)))
+            .collect();
+        let oldest = taken_at.values().copied().min()?;
+
+        let mut updated = HashSet::new();
+        let mut pagination = PaginationRequest::default();
+        for _ in 0..self.max_feed_pages {
+            let page = tokio::time::timeout(self.timeout, latest_updates(pagination.clone()))
+                .await
+                .ok()?
+                .ok()?;
+
+            let mut reached_oldest = page.items.is_empty();
+            for update in &page.items {
+                let series_id = update.entry.series_id.as_str();
+                if taken_at
+                    .get(series_id)
+                    .is_some_and(|taken_at| update.updated_at >= *taken_at)
+                {
+                    updated.insert(series_id.to_string());
+                }
+                reached_oldest |= update.updated_at < oldest;
+            }
+
+            if reached_oldest || !page.has_next_page {
+                return Some(updated);
+            }
+            pagination.page += 1;
+            tokio::time::sleep(self.min_interval).await;
+        }
+
+        None
+    }
+
+    async fn fetch(&self, source: &SourceInstance, series_id: &str) -> SourceResult<Series> {
+        tokio::time::timeout(self.timeout, (source.series)(series_id.to_string()))
+            .await
+            .unwrap_or(Err(SourceError::Timeout {
+                timeout: self.timeout,
+            }))
+    }
+}
+
+impl Default for UpdateChecker<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::{
+    base::{ContentRating, LanguageKey},
+    generate::Status,
+};
+
+/// Name of the sidecar metadata file inside a series folder
+pub const SIDECAR_FILE: &str = "series.json";
+
+/// Series metadata written by the user next to the chapters, every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct LocalSeriesMetadata {
+    pub title: Option<String>,
+    #[serde(alias = "alt_titles")]
+    pub alt_titles: Vec<String>,
+    pub description: Option<String>,
+    pub status: Option<Status>,
+    pub author: Vec<String>,
+    pub artist: Vec<String>,
+    pub tags: Vec<String>,
+    #[serde(alias = "original_language")]
+    pub original_language: Option<LanguageKey>,
+    #[serde(alias = "content_rating")]
+    pub content_rating: Option<ContentRating>,
+    pub hentai: bool,
+    pub year: Option<u32>,
+
+    /// Cover image file name, relative to the series folder
+    pub cover: Option<String>,
+}
+
+/// Chapter and volume numbers from names like "Vol.02 Ch.010.5 - Title".
+///
+/// Numbers after "vol"/"v" are volumes and numbers after "ch"/"c"/"ep" are
+/// chapters; without a chapter prefix the first other number is used.
+pub fn numbers_from_name(name: &str) -> (Option<String>, Option<String>) {
+    let name = name.to_lowercase();
+    let chars: Vec<char> = name.chars().collect();
+
+    let mut chapter = None;
+    let mut volume = None;
+    let mut unlabeled = None;
+    let mut index = 0;
+    while index < chars.len() {
+        if !chars[index].is_ascii_digit() {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < chars.len()
+            && (chars[index].is_ascii_digit()
+                || (chars[index] == '.' && chars.get(index + 1).is_some_and(char::is_ascii_digit)))
+        {
+            index += 1;
+        }
+        let number = trim_leading_zeros(&chars[start..index].iter().collect::<String>());
+
+        let mut word_end = start;
+        while word_end > 0 && matches!(chars[word_end - 1], ' ' | '.' | '_' | '-' | '#') {
+            word_end -= 1;
+        }
+        let mut word_start = word_end;
+        while word_start > 0 && chars[word_start - 1].is_alphabetic() {
+            word_start -= 1;
+        }
+        let word: String = chars[word_start..word_end].iter().collect();
+
+        match word.as_str() {
+            "v" | "vol" | "volume" | "tome" => {
+                volume.get_or_insert(number);
+            }
+            "c" | "ch" | "chap" | "chapter" | "ep" | "episode" => {
+                chapter.get_or_insert(number);
+            }
+            _ => {
+                unlabeled.get_or_insert(number);
+            }
+        }
+    }
+
+    (chapter.or(unlabeled), volume)
+}
+
+fn trim_leading_zeros(number: &str) -> String {
+    let trimmed = number.trim_start_matches('0');
+    if trimmed.is_empty() || trimmed.starts_with('.') {
+        format!("0{}", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Compare file names so that "2.jpg" sorts before "10.jpg"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod metadata;
+pub mod source;
+
+pub use metadata::*;
+pub use source::*;
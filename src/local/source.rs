@@ -0,0 +1,798 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use zip::ZipArchive;
+
+use crate::{
+    base::{
+        ClientFilter, FilterKind, HomeSection, HomeSectionType, LanguageKey, PaginatedResponse,
+        PaginationRequest, SearchRequest, SourceCapabilities, SourceInstance, StatefulSource,
+    },
+    error::{AsyncSourceResult, IntoSourceError, ParseContentType, SourceError, SourceResult},
+    export::{ComicInfo, PageImage, image_extension, sanitize_file_name},
+    generate::{Chapter, Series, SeriesEntry, Status, normalize_title},
+    local::{LocalSeriesMetadata, SIDECAR_FILE, natural_cmp, numbers_from_name},
+    utils::unix_timestamp,
+    wire::FORMAT_VERSION,
+};
+
+/// Source id of the local library
+pub const LOCAL_SOURCE_ID: &str = "local";
+
+/// Minimum title similarity for a series to match a search query
+const SEARCH_THRESHOLD: f64 = 0.6;
+
+/// Number of series in the "Recently added" home section
+const RECENT_COUNT: usize = 20;
+
+/// Upper bound on the buffer reserved for an archive entry, whatever its header claims
+const MAX_ENTRY_PREALLOCATION: u64 = 8 * 1024 * 1024;
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "webp", "avif"];
+const ARCHIVE_EXTENSIONS: [&str; 2] = ["cbz", "zip"];
+const COVER_NAMES: [&str; 2] = ["cover", "folder"];
+
+/// Where the pages of a chapter are stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalChapterKind {
+    /// A CBZ or ZIP archive
+    Archive,
+    /// A folder of images
+    Folder,
+}
+
+/// A chapter found in a series folder
+#[derive(Debug, Clone)]
+pub struct LocalChapter {
+    /// File or folder name, used as chapter id
+    pub id: String,
+    pub path: PathBuf,
+    pub kind: LocalChapterKind,
+    pub modified: u64,
+}
+
+/// Exposes a directory of already downloaded content as a source.
+///
+/// Every folder directly inside the root is a series, and every CBZ/ZIP archive
+/// or folder of images inside a series folder is a chapter. Series metadata is
+/// read from a `series.json` sidecar (see `LocalSeriesMetadata`) and falls back
+/// to the `ComicInfo.xml` of the first chapter, then to the folder name.
+///
+/// Pages are served as `file://` URLs, read back with `read_page`. Archives
+/// are extracted on first read into the cache directory.
+#[derive(Debug, Clone)]
+pub struct LocalSource {
+    root: PathBuf,
+    cache_dir: PathBuf,
+    summaries: Arc<Mutex<HashMap<String, Summary>>>,
+}
+
+/// Series metadata without its chapter list, reused until the folder changes
+#[derive(Debug, Clone)]
+struct Summary {
+    stamp: Stamp,
+    series: Series,
+}
+
+/// Modification times of a series folder and of its sidecar
+type Stamp = (Option<SystemTime>, Option<SystemTime>);
+
+impl LocalSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let root = std::path::absolute(&root).unwrap_or(root);
+        // One cache per library, so two roots never share extracted pages
+        let name = root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("root");
+        let key = format!(
+            "{}-{:08x}",
+            sanitize_file_name(name),
+            crc32fast::hash(root.as_os_str().as_encoded_bytes())
+        );
+        Self {
+            cache_dir: std::env::temp_dir().join("rrmounter-local").join(key),
+            root,
+            summaries: Arc::default(),
+        }
+    }
+
+    /// Builder method to set where archive pages are extracted to
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        let cache_dir = cache_dir.into();
+        self.cache_dir = std::path::absolute(&cache_dir).unwrap_or(cache_dir);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Expose the library as a regular source
+    pub fn into_instance(self) -> SourceResult<SourceInstance> {
+        SourceInstance::bind(Arc::new(self))
+    }
+
+    /// Ids of every series folder that contains at least one chapter
+    pub fn series_ids(&self) -> SourceResult<Vec<String>> {
+        Ok(self
+            .summaries()?
+            .into_iter()
+            .map(|series| series.series_id)
+            .collect())
+    }
+
+    /// Chapters of a series, in reading order
+    pub fn chapters(&self, series_id: &str) -> SourceResult<Vec<LocalChapter>> {
+        let directory = self.series_dir(series_id)?;
+        let mut chapters: Vec<LocalChapter> = read_dir(&directory)?
+            .into_iter()
+            .filter_map(|path| {
+                let kind = chapter_kind(&path)?;
+                Some(LocalChapter {
+                    id: path.file_name()?.to_str()?.to_string(),
+                    modified: modified(&path),
+                    path,
+                    kind,
+                })
+            })
+            .collect();
+
+        chapters.sort_by(|a, b| {
+            let number = |chapter: &LocalChapter| {
+                numbers_from_name(&chapter.id)
+                    .0
+                    .and_then(|number| number.parse::<f64>().ok())
+            };
+            match (number(a), number(b)) {
+                (Some(x), Some(y)) if x != y => x.total_cmp(&y),
+                _ => natural_cmp(&a.id, &b.id),
+            }
+        });
+        Ok(chapters)
+    }
+
+    /// Read a series and its chapters from disk
+    pub fn series(&self, series_id: &str) -> SourceResult<Series> {
+        let mut series = self.summary(series_id)?;
+        let local_chapters = self.chapters(series_id)?;
+
+        let chapter_infos: Vec<Option<ComicInfo>> = local_chapters
+            .iter()
+            .map(|chapter| self.comic_info(chapter))
+            .collect();
+
+        series.chapters = local_chapters
+            .iter()
+            .zip(&chapter_infos)
+            .map(|(chapter, info)| {
+                let stem = match chapter.kind {
+                    LocalChapterKind::Archive => Path::new(&chapter.id)
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .unwrap_or(&chapter.id),
+                    LocalChapterKind::Folder => &chapter.id,
+                };
+                let (number, volume) = numbers_from_name(stem);
+                let info = info.as_ref();
+
+                Chapter {
+                    id: chapter.id.clone(),
+                    series_id: series_id.to_string(),
+                    source_id: LOCAL_SOURCE_ID.to_string(),
+                    title: info
+                        .and_then(|info| info.title.clone())
+                        .unwrap_or_else(|| stem.to_string()),
+                    chapter_number: info
+                        .and_then(|info| info.number.clone())
+                        .or(number)
+                        .unwrap_or_default(),
+                    volume_number: info
+                        .and_then(|info| info.volume.map(|volume| volume.to_string()))
+                        .or(volume)
+                        .unwrap_or_default(),
+                    group_name: info
+                        .and_then(|info| info.translator.clone())
+                        .unwrap_or_default(),
+                    time: chapter.modified,
+                    read: false,
+                }
+            })
+            .collect();
+        series.number_chapters = series.chapters.len() as u64;
+        series.number_unread = series.number_chapters;
+        Ok(series)
+    }
+
+    /// A series as a list entry, without opening its chapters
+    pub fn entry(&self, series_id: &str) -> SourceResult<SeriesEntry> {
+        let series = self.summary(series_id)?;
+        Ok(SeriesEntry::new(
+            LOCAL_SOURCE_ID,
+            series.series_id,
+            series.title,
+            series.cover_url,
+        ))
+    }
+
+    /// Read a page served by this source, refusing files outside the library and its cache
+    pub fn read_page(&self, url: &str) -> SourceResult<PageImage> {
+        let refused = || SourceError::ImageError {
+            url: url.to_string(),
+            src: Some("not a page of the local library".to_string()),
+        };
+        let path = url::Url::parse(url)
+            .ok()
+            .filter(|parsed| parsed.scheme() == "file")
+            .and_then(|parsed| parsed.to_file_path().ok())
+            .ok_or_else(refused)?;
+        let path = path.canonicalize().map_err(|_| refused())?;
+        let allowed = [&self.root, &self.cache_dir]
+            .iter()
+            .filter_map(|directory| directory.canonicalize().ok())
+            .any(|directory| path.starts_with(directory));
+        if !allowed || !has_extension(&path, &IMAGE_EXTENSIONS) {
+            return Err(refused());
+        }
+
+        let data = std::fs::read(&path).map_err(IntoSourceError::into_source_error)?;
+        Ok(PageImage {
+            url: url.to_string(),
+            extension: image_extension(&data, url),
+            data,
+        })
+    }
+
+    /// Series metadata without chapters, cached until the folder or its sidecar changes
+    fn summary(&self, series_id: &str) -> SourceResult<Series> {
+        let directory = self.series_dir(series_id)?;
+        let stamp = (
+            modified_at(&directory),
+            modified_at(&directory.join(SIDECAR_FILE)),
+        );
+        if let Some(summary) = self.lock_summaries().get(series_id)
+            && summary.stamp == stamp
+        {
+            return Ok(summary.series.clone());
+        }
+
+        let series = self.read_summary(series_id, &directory)?;
+        self.lock_summaries().insert(
+            series_id.to_string(),
+            Summary {
+                stamp,
+                series: series.clone(),
+            },
+        );
+        Ok(series)
+    }
+
+    fn read_summary(&self, series_id: &str, directory: &Path) -> SourceResult<Series> {
+        let sidecar = self.sidecar(directory)?;
+        let local_chapters = self.chapters(series_id)?;
+        // Only the first chapter with a ComicInfo.xml is opened
+        let first_info = local_chapters
+            .iter()
+            .find_map(|chapter| self.comic_info(chapter))
+            .unwrap_or_default();
+
+        let or_info = |values: Vec<String>, info: &[String]| {
+            if values.is_empty() {
+                info.to_vec()
+            } else {
+                values
+            }
+        };
+        let number_chapters = local_chapters.len() as u64;
+
+        Ok(Series {
+            format_version: FORMAT_VERSION,
+            source_id: LOCAL_SOURCE_ID.to_string(),
+            series_id: series_id.to_string(),
+            title: self.title(series_id, &sidecar, &first_info),
+            alt_titles: sidecar.alt_titles,
+            description: sidecar
+                .description
+                .or(first_info.summary.clone())
+                .unwrap_or_default(),
+            status: sidecar.status.unwrap_or(Status::Ongoing),
+            cover_url: self.cover_url(directory, sidecar.cover.as_deref(), &local_chapters),
+            chapters: Vec::new(),
+            author: or_info(sidecar.author, &first_info.writer),
+            artist: or_info(sidecar.artist, &first_info.penciller),
+            tags: or_info(sidecar.tags, &first_info.genre),
+            hentai: sidecar.hentai || first_info.is_explicit(),
+            original_language: sidecar
+                .original_language
+                .or_else(|| LanguageKey::from_code(first_info.language_iso.as_deref()?))
+                .unwrap_or(LanguageKey::Multi),
+            content_rating: sidecar.content_rating.or(first_info.content_rating()),
+            year: sidecar.year.or(first_info.year),
+            number_unread: number_chapters,
+            number_chapters,
+        })
+    }
+
+    /// Summaries of every series with at least one chapter, in natural order.
+    ///
+    /// Unreadable series are left out instead of failing the whole listing.
+    fn summaries(&self) -> SourceResult<Vec<Series>> {
+        let mut ids: Vec<String> = read_dir(&self.root)?
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+            .filter(|id| !id.starts_with('.'))
+            .collect();
+        ids.sort_by(|a, b| natural_cmp(a, b));
+
+        // Series folders that were removed are dropped from the cache
+        let present: HashSet<&String> = ids.iter().collect();
+        self.lock_summaries()
+            .retain(|series_id, _| present.contains(series_id));
+
+        Ok(ids
+            .iter()
+            .filter_map(|series_id| self.summary(series_id).ok())
+            .filter(|series| series.number_chapters > 0)
+            .collect())
+    }
+
+    fn lock_summaries(&self) -> std::sync::MutexGuard<'_, HashMap<String, Summary>> {
+        self.summaries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// `file://` URLs of the pages of a chapter, extracting archives on first read
+    pub fn pages(&self, series_id: &str, chapter_id: &str) -> SourceResult<Vec<String>> {
+        let directory = self.series_dir(series_id)?;
+        let path = directory.join(path_component(chapter_id)?);
+
+        let pages = match chapter_kind(&path) {
+            Some(LocalChapterKind::Folder) => images_in(&path)?,
+            Some(LocalChapterKind::Archive) => self.extract(series_id, chapter_id, &path)?,
+            None => {
+                return Err(SourceError::NotFound {
+                    resource: format!("local chapter '{}/{}'", series_id, chapter_id),
+                    suggestion: None,
+                });
+            }
+        };
+
+        pages.iter().map(|page| file_url(page)).collect()
+    }
+
+    fn home_page(&self) -> SourceResult<Vec<HomeSection>> {
+        let entries = self.all_entries()?;
+        let recent = self.recent_entries(&entries);
+
+        Ok(vec![
+            HomeSection::new("recent", "Recently added", HomeSectionType::SingleRowNormal)
+                .with_more_items()
+                .with_entries(recent.into_iter().take(RECENT_COUNT).collect()),
+            HomeSection::new("library", "Library", HomeSectionType::DoubleRow)
+                .with_entries(entries),
+        ])
+    }
+
+    fn view_more(
+        &self,
+        section_id: &str,
+        pagination: PaginationRequest,
+    ) -> SourceResult<PaginatedResponse<SeriesEntry>> {
+        let entries = self.all_entries()?;
+        let entries = match section_id {
+            "recent" => self.recent_entries(&entries),
+            "library" => entries,
+            _ => {
+                return Err(SourceError::NotFound {
+                    resource: format!("home section '{}'", section_id),
+                    suggestion: None,
+                });
+            }
+        };
+        Ok(paginate(entries, &pagination))
+    }
+
+    fn search(&self, request: SearchRequest) -> SourceResult<PaginatedResponse<SeriesEntry>> {
+        let query = normalize_title(&request.query);
+        let filter = ClientFilter::new(request.filters.clone(), &FilterKind::ALL);
+
+        let mut matches = Vec::new();
+        for series in self.summaries()? {
+            let matches_query = query.is_empty()
+                || series
+                    .all_titles()
+                    .any(|title| normalize_title(title).contains(&query))
+                || series.title_similarity(&request.query) >= SEARCH_THRESHOLD;
+            if matches_query && filter.matches(&series) {
+                matches.push(SeriesEntry::new(
+                    LOCAL_SOURCE_ID,
+                    series.series_id,
+                    series.title,
+                    series.cover_url,
+                ));
+            }
+        }
+
+        Ok(paginate(matches, &request.pagination))
+    }
+
+    fn all_entries(&self) -> SourceResult<Vec<SeriesEntry>> {
+        Ok(self
+            .summaries()?
+            .into_iter()
+            .map(|series| {
+                SeriesEntry::new(
+                    LOCAL_SOURCE_ID,
+                    series.series_id,
+                    series.title,
+                    series.cover_url,
+                )
+            })
+            .collect())
+    }
+
+    /// Entries ordered by when their folder last changed, newest first
+    fn recent_entries(&self, entries: &[SeriesEntry]) -> Vec<SeriesEntry> {
+        let mut recent = entries.to_vec();
+        recent.sort_by_key(|entry| std::cmp::Reverse(modified(&self.root.join(&entry.series_id))));
+        recent
+    }
+
+    fn series_dir(&self, series_id: &str) -> SourceResult<PathBuf> {
+        let directory = self.root.join(path_component(series_id)?);
+        if !directory.is_dir() {
+            return Err(SourceError::NotFound {
+                resource: format!("local series '{}'", series_id),
+                suggestion: None,
+            });
+        }
+        Ok(directory)
+    }
+
+    fn sidecar(&self, directory: &Path) -> SourceResult<LocalSeriesMetadata> {
+        match std::fs::read(directory.join(SIDECAR_FILE)) {
+            Ok(json) => serde_json::from_slice(&json).map_err(|error| {
+                SourceError::parse_error(
+                    format!("{}: {}", directory.join(SIDECAR_FILE).display(), error),
+                    ParseContentType::SeriesMetadata,
+                )
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(LocalSeriesMetadata::default())
+            }
+            Err(error) => Err(error.into_source_error()),
+        }
+    }
+
+    fn title(&self, series_id: &str, sidecar: &LocalSeriesMetadata, info: &ComicInfo) -> String {
+        sidecar
+            .title
+            .clone()
+            .or_else(|| Some(info.series.clone()))
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or_else(|| series_id.to_string())
+    }
+
+    fn comic_info(&self, chapter: &LocalChapter) -> Option<ComicInfo> {
+        let xml = match chapter.kind {
+            LocalChapterKind::Folder => {
+                std::fs::read_to_string(chapter.path.join("ComicInfo.xml")).ok()?
+            }
+            LocalChapterKind::Archive => {
+                let mut archive = ZipArchive::new(File::open(&chapter.path).ok()?).ok()?;
+                let mut file = archive.by_name("ComicInfo.xml").ok()?;
+                let mut xml = String::new();
+                file.read_to_string(&mut xml).ok()?;
+                xml
+            }
+        };
+        Some(ComicInfo::parse(&xml))
+    }
+
+    /// The sidecar cover, a `cover.*` image, or the first page of the first chapter
+    fn cover_url(
+        &self,
+        directory: &Path,
+        sidecar_cover: Option<&str>,
+        chapters: &[LocalChapter],
+    ) -> String {
+        let named = sidecar_cover
+            .and_then(|cover| path_component(cover).ok())
+            .map(|cover| directory.join(cover))
+            .filter(|cover| cover.is_file())
+            .or_else(|| {
+                images_in(directory).ok()?.into_iter().find(|image| {
+                    image
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .is_some_and(|stem| COVER_NAMES.contains(&stem.to_lowercase().as_str()))
+                })
+            });
+
+        let cover = named.or_else(|| {
+            let chapter = chapters.first()?;
+            match chapter.kind {
+                LocalChapterKind::Folder => images_in(&chapter.path).ok()?.into_iter().next(),
+                LocalChapterKind::Archive => self.extract_cover(directory, chapter).ok(),
+            }
+        });
+
+        cover
+            .and_then(|cover| file_url(&cover).ok())
+            .unwrap_or_default()
+    }
+
+    /// Extract only the first page of an archive, for use as the series cover
+    fn extract_cover(&self, directory: &Path, chapter: &LocalChapter) -> SourceResult<PathBuf> {
+        let series = directory
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let target = self.cache_dir.join("covers").join(format!(
+            "{}-{}",
+            sanitize_file_name(series),
+            chapter.modified
+        ));
+        if let Some(existing) = images_in(&target)
+            .ok()
+            .and_then(|images| images.into_iter().next())
+        {
+            return Ok(existing);
+        }
+
+        let mut archive = open_archive(&chapter.path)?;
+        let name = archive_images(&mut archive)
+            .into_iter()
+            .next()
+            .ok_or_else(|| empty_archive(&chapter.path))?;
+        let data = read_entry(&mut archive, &name)?;
+
+        std::fs::create_dir_all(&target).map_err(IntoSourceError::into_source_error)?;
+        let path = target.join(format!("cover.{}", image_extension(&data, &name)));
+        std::fs::write(&path, data).map_err(IntoSourceError::into_source_error)?;
+        Ok(path)
+    }
+
+    /// Extract the images of an archive into the cache, reusing a previous extraction
+    fn extract(
+        &self,
+        series_id: &str,
+        chapter_id: &str,
+        path: &Path,
+    ) -> SourceResult<Vec<PathBuf>> {
+        let target = self
+            .cache_dir
+            .join(sanitize_file_name(series_id))
+            .join(format!(
+                "{}-{}",
+                sanitize_file_name(chapter_id),
+                modified(path)
+            ));
+        let complete = target.join(".complete");
+        if complete.is_file() {
+            return images_in(&target);
+        }
+
+        let mut archive = open_archive(path)?;
+        let names = archive_images(&mut archive);
+        if names.is_empty() {
+            return Err(empty_archive(path));
+        }
+
+        std::fs::create_dir_all(&target).map_err(IntoSourceError::into_source_error)?;
+        let width = names.len().to_string().len().max(3);
+        let mut pages = Vec::with_capacity(names.len());
+        for (index, name) in names.iter().enumerate() {
+            let data = read_entry(&mut archive, name)?;
+            let page = target.join(format!(
+                "{:0width$}.{}",
+                index + 1,
+                image_extension(&data, name),
+                width = width
+            ));
+            std::fs::write(&page, data).map_err(IntoSourceError::into_source_error)?;
+            pages.push(page);
+        }
+
+        // Written last so an interrupted extraction is redone on the next read
+        std::fs::write(&complete, unix_timestamp().to_string())
+            .map_err(IntoSourceError::into_source_error)?;
+        Ok(pages)
+    }
+}
+
+impl StatefulSource for LocalSource {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        Box::pin(blocking(move || LocalSource::home_page(&self)))
+    }
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        Box::pin(blocking(move || LocalSource::search(&self, request)))
+    }
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series> {
+        Box::pin(blocking(move || LocalSource::series(&self, &series_id)))
+    }
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>> {
+        Box::pin(blocking(move || {
+            let series_id = series_id.ok_or_else(|| {
+                SourceError::not_found_with_suggestion(
+                    format!("local chapter '{}'", chapter_id),
+                    "Pass the series id, local chapters are looked up inside their series",
+                )
+            })?;
+            self.pages(&series_id, &chapter_id)
+        }))
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            view_more: true,
+            ..SourceCapabilities::default()
+        }
+    }
+
+    fn view_more(
+        self: Arc<Self>,
+        section_id: String,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        Box::pin(blocking(move || {
+            LocalSource::view_more(&self, &section_id, pagination)
+        }))
+    }
+}
+
+/// Run filesystem work on the blocking pool, away from the async runtime
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> SourceResult<T> + Send + 'static,
+) -> SourceResult<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|error| SourceError::Unexpected {
+            message: format!("local source task failed: {}", error),
+            error_code: None,
+        })?
+}
+
+fn paginate(
+    items: Vec<SeriesEntry>,
+    pagination: &PaginationRequest,
+) -> PaginatedResponse<SeriesEntry> {
+    let per_page = pagination.per_page.max(1) as usize;
+    let page = pagination.page.max(1);
+    let total_items = items.len();
+    let total_pages = total_items.div_ceil(per_page).max(1);
+
+    let items = items
+        .into_iter()
+        .skip((page as usize - 1) * per_page)
+        .take(per_page)
+        .collect();
+    PaginatedResponse::with_totals(items, page, total_pages as u32, total_items as u32)
+}
+
+/// Accept an id only if it names a single entry, so ids cannot escape the library root
+fn path_component(id: &str) -> SourceResult<&str> {
+    let mut components = Path::new(id).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(id),
+        _ => Err(SourceError::NotFound {
+            resource: format!("local item '{}'", id),
+            suggestion: None,
+        }),
+    }
+}
+
+fn read_dir(directory: &Path) -> SourceResult<Vec<PathBuf>> {
+    std::fs::read_dir(directory)
+        .map_err(IntoSourceError::into_source_error)?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .map_err(IntoSourceError::into_source_error)
+        })
+        .collect()
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.contains(&extension.to_lowercase().as_str()))
+}
+
+fn chapter_kind(path: &Path) -> Option<LocalChapterKind> {
+    if path.is_file() && has_extension(path, &ARCHIVE_EXTENSIONS) {
+        return Some(LocalChapterKind::Archive);
+    }
+    if path.is_dir() && images_in(path).is_ok_and(|images| !images.is_empty()) {
+        return Some(LocalChapterKind::Folder);
+    }
+    None
+}
+
+/// Image files directly inside a folder, in natural order
+fn images_in(directory: &Path) -> SourceResult<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = read_dir(directory)?
+        .into_iter()
+        .filter(|path| path.is_file() && has_extension(path, &IMAGE_EXTENSIONS))
+        .collect();
+    images.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(images)
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn modified(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn file_url(path: &Path) -> SourceResult<String> {
+    url::Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|_| SourceError::Unexpected {
+            message: format!("{} is not an absolute path", path.display()),
+            error_code: None,
+        })
+}
+
+fn open_archive(path: &Path) -> SourceResult<ZipArchive<File>> {
+    let file = File::open(path).map_err(IntoSourceError::into_source_error)?;
+    ZipArchive::new(file).map_err(IntoSourceError::into_source_error)
+}
+
+/// Names of the image entries of an archive, in natural order
+fn archive_images(archive: &mut ZipArchive<File>) -> Vec<String> {
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| name.ok().map(|name| name.into_owned()))
+        .filter(|name| !name.ends_with('/') && has_extension(Path::new(name), &IMAGE_EXTENSIONS))
+        .filter(|name| !name.starts_with("__MACOSX/"))
+        .collect();
+    names.sort_by(|a, b| natural_cmp(a, b));
+    names
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> SourceResult<Vec<u8>> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(IntoSourceError::into_source_error)?;
+    let mut data = Vec::with_capacity(file.size().min(MAX_ENTRY_PREALLOCATION) as usize);
+    file.read_to_end(&mut data)
+        .map_err(IntoSourceError::into_source_error)?;
+    Ok(data)
+}
+
+fn empty_archive(path: &Path) -> SourceError {
+    SourceError::NotFound {
+        resource: format!("pages in {}", path.display()),
+        suggestion: Some("The archive contains no images".to_string()),
+    }
+}
@@ -0,0 +1,104 @@
+use std::ffi::{CStr, c_char};
+
+use crate::base::{SourceInfo, SourceInstance};
+
+/// Version of `ExtensionDeclaration`, raised whenever its layout changes
+pub const ABI_VERSION: u32 = 2;
+
+/// Version of rrmounter an extension was built against, as a C string
+pub const CRATE_VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("invalid crate version"),
+    };
+
+/// Compiler that built rrmounter, as a C string.
+///
+/// Rust types have no stable layout, so sources can only cross the library
+/// boundary between binaries built by the same compiler.
+pub const RUSTC_VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("RRMOUNTER_RUSTC_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("invalid compiler version"),
+    };
+
+/// Name of the static holding the `ExtensionDeclaration` of a library
+pub const DECLARATION_SYMBOL: &[u8] = b"RRMOUNTER_EXTENSION\0";
+
+/// Entry point of a native extension, exported by `export_sources!`.
+///
+/// Only the version fields are read before the handshake succeeds. They keep
+/// their C layout and position across every ABI version, while `register` is
+/// only called once the library is known to match the host.
+///
+/// `register` uses the C calling convention and returns `false` if the
+/// extension panicked, since unwinding must not cross the library boundary.
+/// `SourceRegistrar` and everything registered in it are still Rust types,
+/// which is why the compiler version has to match.
+#[repr(C)]
+pub struct ExtensionDeclaration {
+    pub abi_version: u32,
+    pub crate_version: *const c_char,
+    pub rustc_version: *const c_char,
+    pub register: extern "C" fn(&mut SourceRegistrar) -> bool,
+}
+
+// The version pointers refer to static strings
+unsafe impl Sync for ExtensionDeclaration {}
+
+/// Collects the sources an extension library provides
+#[derive(Default)]
+pub struct SourceRegistrar {
+    sources: Vec<(SourceInfo, SourceInstance)>,
+}
+
+impl SourceRegistrar {
+    pub fn register(&mut self, info: SourceInfo, source: SourceInstance) {
+        let info = info.with_capabilities_of(&source);
+        self.sources.push((info, source));
+    }
+
+    pub fn into_sources(self) -> Vec<(SourceInfo, SourceInstance)> {
+        self.sources
+    }
+}
+
+/// Export the entry point of a native extension built as a `cdylib`.
+///
+/// The extension links its own copy of rrmounter and of its dependencies,
+/// tokio included. Its futures run on the host's runtime, but the extension's
+/// tokio cannot see that runtime, so `tokio::spawn`, tokio timers and anything
+/// built on them, such as `reqwest`, panic with "there is no reactor running".
+/// Extensions needing them start their own runtime in `register` and hand
+/// results back through channels, e.g. `futures::channel::oneshot`.
+///
+/// ```ignore
+/// rrmounter::export_sources!(register);
+///
+/// fn register(registrar: &mut SourceRegistrar) {
+///     registrar.register(info, source);
+/// }
+/// ```
+#[macro_export]
+macro_rules! export_sources {
+    ($register:expr) => {
+        #[unsafe(no_mangle)]
+        pub static RRMOUNTER_EXTENSION: $crate::native::ExtensionDeclaration =
+            $crate::native::ExtensionDeclaration {
+                abi_version: $crate::native::ABI_VERSION,
+                crate_version: $crate::native::CRATE_VERSION.as_ptr(),
+                rustc_version: $crate::native::RUSTC_VERSION.as_ptr(),
+                register: {
+                    extern "C" fn __rrmounter_register(
+                        registrar: &mut $crate::native::SourceRegistrar,
+                    ) -> bool {
+                        ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+                            ($register)(registrar)
+                        }))
+                        .is_ok()
+                    }
+                    __rrmounter_register
+                },
+            };
+    };
+}
@@ -0,0 +1,129 @@
+use libloading::Library;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    base::{SourceInfo, SourceInstance},
+    error::{SourceError, SourceResult},
+    native::{
+        ABI_VERSION, CRATE_VERSION, DECLARATION_SYMBOL, ExtensionDeclaration, RUSTC_VERSION,
+        SourceRegistrar,
+    },
+};
+
+/// Sources loaded from a native extension library.
+///
+/// The library stays loaded for the rest of the process, since sources and the
+/// values and futures they return keep pointing into its code.
+pub struct NativeExtension {
+    path: PathBuf,
+    sources: Vec<(SourceInfo, SourceInstance)>,
+}
+
+impl NativeExtension {
+    /// Load a library exporting `export_sources!`, checking that it was built
+    /// against the same ABI, rrmounter version and compiler as the host.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and a library exporting
+    /// the entry symbol with another meaning is undefined behavior. Only load
+    /// trusted extensions.
+    pub unsafe fn load(path: impl AsRef<Path>) -> SourceResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let library = unsafe { Library::new(&path) }.map_err(|error| SourceError::Unexpected {
+            message: format!("Could not load {}: {}", path.display(), error),
+            error_code: None,
+        })?;
+
+        let declaration = unsafe { library.get::<*const ExtensionDeclaration>(DECLARATION_SYMBOL) }
+            .map_err(|_| SourceError::Unexpected {
+                message: format!("{} is not an rrmounter extension", path.display()),
+                error_code: None,
+            })?;
+        let declaration: *const ExtensionDeclaration = *declaration;
+
+        let extension = unsafe { Self::from_declaration(&path, declaration) }?;
+        std::mem::forget(library);
+        Ok(extension)
+    }
+
+    /// Run the handshake with a declaration and collect its sources, `path` only
+    /// names the extension in errors.
+    ///
+    /// # Safety
+    ///
+    /// `declaration` must point to a readable `abi_version`, and to a whole
+    /// `ExtensionDeclaration` with valid C strings when that version matches.
+    /// Whatever it points into must stay loaded while its sources are in use.
+    pub unsafe fn from_declaration(
+        path: impl AsRef<Path>,
+        declaration: *const ExtensionDeclaration,
+    ) -> SourceResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        // Nothing past the ABI version is read from a library with another layout
+        let abi_version = unsafe { (&raw const (*declaration).abi_version).read() };
+        if abi_version != ABI_VERSION {
+            return Err(version_mismatch(
+                &path,
+                "abi_version",
+                ABI_VERSION.to_string(),
+                abi_version.to_string(),
+            ));
+        }
+        let declaration = unsafe { &*declaration };
+
+        let crate_version = unsafe { CStr::from_ptr(declaration.crate_version) };
+        if crate_version != CRATE_VERSION {
+            return Err(version_mismatch(
+                &path,
+                "rrmounter_version",
+                CRATE_VERSION.to_string_lossy().into_owned(),
+                crate_version.to_string_lossy().into_owned(),
+            ));
+        }
+        let rustc_version = unsafe { CStr::from_ptr(declaration.rustc_version) };
+        if rustc_version != RUSTC_VERSION {
+            return Err(version_mismatch(
+                &path,
+                "rustc_version",
+                RUSTC_VERSION.to_string_lossy().into_owned(),
+                rustc_version.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let mut registrar = SourceRegistrar::default();
+        if !(declaration.register)(&mut registrar) {
+            return Err(SourceError::Unexpected {
+                message: format!("{} panicked while registering its sources", path.display()),
+                error_code: None,
+            });
+        }
+
+        Ok(Self {
+            path,
+            sources: registrar.into_sources(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn sources(&self) -> &[(SourceInfo, SourceInstance)] {
+        &self.sources
+    }
+
+    pub fn into_sources(self) -> Vec<(SourceInfo, SourceInstance)> {
+        self.sources
+    }
+}
+
+fn version_mismatch(path: &Path, setting: &str, expected: String, current: String) -> SourceError {
+    SourceError::ConfigurationError {
+        setting: format!("{} of {}", setting, path.display()),
+        expected,
+        current,
+    }
+}
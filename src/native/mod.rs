@@ -0,0 +1,7 @@
+pub mod declaration;
+#[cfg(feature = "native")]
+pub mod loader;
+
+pub use declaration::*;
+#[cfg(feature = "native")]
+pub use loader::*;
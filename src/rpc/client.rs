@@ -0,0 +1,427 @@
+use futures::channel::oneshot;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+
+use crate::{
+    base::{
+        HomeSection, PaginatedResponse, PaginationRequest, SearchRequest, SettingType,
+        SourceCapabilities, SourceInfo, SourceInstance, StatefulSource,
+    },
+    error::{AsyncSourceResult, ParseContentType, SourceError, SourceResult},
+    generate::{LatestUpdate, Series, SeriesEntry},
+    rpc::{
+        ChapterParams, MAX_MESSAGE_SIZE, Manifest, ManifestParams, PROTOCOL_VERSION, RpcRequest,
+        RpcResponse, SectionParams, SeriesParams, ViewMoreParams, method,
+    },
+};
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<RpcResponse>>>>;
+
+/// Output and input of an extension, as seen from the host
+pub type RpcTransport = (
+    Box<dyn AsyncRead + Send + Unpin>,
+    Box<dyn AsyncWrite + Send + Unpin>,
+);
+
+/// How a new extension instance is started
+enum Launcher {
+    Process { program: PathBuf, args: Vec<String> },
+    Transport(Box<dyn Fn() -> std::io::Result<RpcTransport> + Send + Sync>),
+}
+
+/// A running extension instance
+struct Connection {
+    stdin: futures::lock::Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+    // Set once the manifest handshake succeeded
+    info: OnceLock<SourceInfo>,
+
+    // Killed when the connection is dropped
+    _child: Option<Child>,
+}
+
+/// Talks to an extension running as a separate process over JSON-RPC on stdio.
+///
+/// Each call has a timeout, and a crashed extension fails only the calls in
+/// flight: the process is started again on the next call, and its manifest
+/// is checked again before any request is sent. Responses that are not valid
+/// JSON-RPC, too large or for unknown ids are ignored.
+pub struct RpcClient {
+    launcher: Launcher,
+    timeout: Duration,
+    next_id: AtomicU64,
+    connection: futures::lock::Mutex<Option<Arc<Connection>>>,
+
+    // Sent again to a restarted extension
+    settings: Mutex<Option<HashMap<String, SettingType>>>,
+
+    // Every operation, bound once and narrowed to the manifest of each `instance` call
+    bound: Mutex<Option<SourceInstance>>,
+}
+
+impl RpcClient {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self::from_launcher(Launcher::Process {
+            program: program.into(),
+            args: Vec::new(),
+        })
+    }
+
+    /// Talk to extensions reached through `connect` instead of a child process,
+    /// e.g. a socket. `connect` is called again whenever the connection is lost.
+    pub fn from_transport(
+        connect: impl Fn() -> std::io::Result<RpcTransport> + Send + Sync + 'static,
+    ) -> Self {
+        Self::from_launcher(Launcher::Transport(Box::new(connect)))
+    }
+
+    fn from_launcher(launcher: Launcher) -> Self {
+        Self {
+            launcher,
+            timeout: Duration::from_secs(30),
+            next_id: AtomicU64::new(1),
+            connection: futures::lock::Mutex::new(None),
+            settings: Mutex::new(None),
+            bound: Mutex::new(None),
+        }
+    }
+
+    /// Builder method to pass arguments to the extension program
+    pub fn with_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        if let Launcher::Process { args: current, .. } = &mut self.launcher {
+            *current = args.into_iter().map(Into::into).collect();
+        }
+        self
+    }
+
+    /// Builder method to set how long a single call may take
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Start the extension if needed and return the manifest it answered with
+    pub async fn manifest(&self) -> SourceResult<SourceInfo> {
+        let connection = self.connect().await?;
+        connection
+            .info
+            .get()
+            .cloned()
+            .ok_or_else(|| unavailable("the extension did not complete its handshake".to_string()))
+    }
+
+    pub async fn settings(&self) -> SourceResult<HashMap<String, SettingType>> {
+        self.call(method::GET_SETTINGS, &Value::Null).await
+    }
+
+    /// Change settings of the extension, which rejects undeclared keys and wrong kinds.
+    ///
+    /// Accepted values are applied again whenever the extension is restarted.
+    pub async fn set_settings(&self, values: HashMap<String, SettingType>) -> SourceResult<()> {
+        let _: Value = self.call(method::SET_SETTINGS, &values).await?;
+        lock(&self.settings)
+            .get_or_insert_with(HashMap::new)
+            .extend(values);
+        Ok(())
+    }
+
+    /// Stop the extension process, it is started again on the next call
+    pub async fn shutdown(&self) {
+        self.connection.lock().await.take();
+    }
+
+    /// Send a request and wait for its response
+    pub async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> SourceResult<R> {
+        let connection = self.connect().await?;
+        self.request(&connection, method, params).await
+    }
+
+    async fn request<P: Serialize, R: DeserializeOwned>(
+        &self,
+        connection: &Connection,
+        method: &str,
+        params: &P,
+    ) -> SourceResult<R> {
+        let params = serde_json::to_value(params).map_err(|error| SourceError::Unexpected {
+            message: format!("Could not encode '{}' parameters: {}", method, error),
+            error_code: None,
+        })?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        lock(&connection.pending).insert(id, sender);
+
+        let mut line =
+            serde_json::to_vec(&RpcRequest::new(id, method, params)).map_err(|error| {
+                SourceError::Unexpected {
+                    message: error.to_string(),
+                    error_code: None,
+                }
+            })?;
+        line.push(b'\n');
+
+        let written = {
+            let mut stdin = connection.stdin.lock().await;
+            match stdin.write_all(&line).await {
+                Ok(()) => stdin.flush().await,
+                Err(error) => Err(error),
+            }
+        };
+        if let Err(error) = written {
+            lock(&connection.pending).remove(&id);
+            connection.alive.store(false, Ordering::Relaxed);
+            return Err(unavailable(format!(
+                "could not write to the extension: {}",
+                error
+            )));
+        }
+
+        let response = match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(unavailable("the extension exited".to_string())),
+            Err(_) => {
+                lock(&connection.pending).remove(&id);
+                return Err(SourceError::Timeout {
+                    timeout: self.timeout,
+                });
+            }
+        };
+
+        if let Some(error) = response.error {
+            return Err(error.into_source_error(method));
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null)).map_err(|error| {
+            SourceError::parse_error(
+                format!(
+                    "Invalid '{}' response from the extension: {}",
+                    method, error
+                ),
+                ParseContentType::Json,
+            )
+        })
+    }
+
+    /// Expose the extension as a regular source, based on the operations its manifest lists.
+    ///
+    /// The client stays usable for settings while the source is in use.
+    pub async fn instance(self: &Arc<Self>) -> SourceResult<(SourceInfo, SourceInstance)> {
+        let info = self.manifest().await?;
+        let source = {
+            let mut bound = lock(&self.bound);
+            match *bound {
+                Some(source) => source,
+                None => *bound.insert(SourceInstance::bind(self.clone())?),
+            }
+        };
+        let capabilities = info.capabilities;
+        Ok((info, source.with_capabilities(capabilities)))
+    }
+
+    async fn connect(&self) -> SourceResult<Arc<Connection>> {
+        let mut connection = self.connection.lock().await;
+        if let Some(existing) = connection.as_ref()
+            && existing.alive.load(Ordering::Relaxed)
+        {
+            return Ok(existing.clone());
+        }
+        // A dead instance is dropped, and killed, before the next one starts
+        connection.take();
+
+        let ((stdout, stdin), child) = self.launch()?;
+        let pending = Pending::default();
+        let alive = Arc::new(AtomicBool::new(true));
+        tokio::spawn(read_responses(stdout, pending.clone(), alive.clone()));
+
+        let started = Connection {
+            stdin: futures::lock::Mutex::new(stdin),
+            pending,
+            alive,
+            info: OnceLock::new(),
+            _child: child,
+        };
+
+        // Every instance, restarted ones included, must speak this protocol version
+        let manifest: Manifest = self
+            .request(
+                &started,
+                method::MANIFEST,
+                &ManifestParams {
+                    protocol_version: PROTOCOL_VERSION,
+                },
+            )
+            .await?;
+        if manifest.protocol_version != PROTOCOL_VERSION {
+            return Err(SourceError::ConfigurationError {
+                setting: "protocol_version".to_string(),
+                expected: PROTOCOL_VERSION.to_string(),
+                current: manifest.protocol_version.to_string(),
+            });
+        }
+        let _ = started.info.set(manifest.info);
+
+        let settings = lock(&self.settings).clone();
+        if let Some(settings) = settings {
+            let _: Value = self
+                .request(&started, method::SET_SETTINGS, &settings)
+                .await?;
+        }
+
+        let started = Arc::new(started);
+        *connection = Some(started.clone());
+        Ok(started)
+    }
+
+    fn launch(&self) -> SourceResult<(RpcTransport, Option<Child>)> {
+        match &self.launcher {
+            Launcher::Process { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::inherit())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|error| {
+                        unavailable(format!("could not start {}: {}", program.display(), error))
+                    })?;
+
+                let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+                    return Err(unavailable("the extension has no stdio".to_string()));
+                };
+                Ok(((Box::new(stdout), Box::new(stdin)), Some(child)))
+            }
+            Launcher::Transport(connect) => {
+                let transport = connect().map_err(|error| {
+                    unavailable(format!("could not connect to the extension: {}", error))
+                })?;
+                Ok((transport, None))
+            }
+        }
+    }
+}
+
+/// Every operation is forwarded, `instance` keeps the ones the manifest lists
+impl StatefulSource for RpcClient {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        Box::pin(async move { self.call(method::HOME_PAGE, &Value::Null).await })
+    }
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        Box::pin(async move { self.call(method::SEARCH, &request).await })
+    }
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series> {
+        Box::pin(async move { self.call(method::SERIES, &SeriesParams { series_id }).await })
+    }
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>> {
+        let params = ChapterParams {
+            series_id,
+            chapter_id,
+        };
+        Box::pin(async move { self.call(method::CHAPTER, &params).await })
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            latest_updates: true,
+            view_more: true,
+            lazy_home_page: true,
+        }
+    }
+
+    fn latest_updates(
+        self: Arc<Self>,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+        Box::pin(async move { self.call(method::LATEST_UPDATES, &pagination).await })
+    }
+
+    fn view_more(
+        self: Arc<Self>,
+        section_id: String,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        let params = ViewMoreParams {
+            section_id,
+            pagination,
+        };
+        Box::pin(async move { self.call(method::VIEW_MORE, &params).await })
+    }
+
+    fn home_sections(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        Box::pin(async move { self.call(method::HOME_SECTIONS, &Value::Null).await })
+    }
+
+    fn home_section(self: Arc<Self>, section_id: String) -> AsyncSourceResult<HomeSection> {
+        let params = SectionParams { section_id };
+        Box::pin(async move { self.call(method::HOME_SECTION, &params).await })
+    }
+}
+
+/// Route each response line to the call waiting for it, until the extension closes stdout
+async fn read_responses(
+    stdout: Box<dyn AsyncRead + Send + Unpin>,
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+) {
+    let mut reader = BufReader::new(stdout);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(MAX_MESSAGE_SIZE as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .await;
+        match read {
+            Ok(0) | Err(_) => break,
+            Ok(_) if line.len() > MAX_MESSAGE_SIZE => break,
+            Ok(_) => {}
+        }
+
+        let Ok(response) = serde_json::from_slice::<RpcResponse>(&line) else {
+            continue;
+        };
+        if let Some(sender) = response.id.and_then(|id| lock(&pending).remove(&id)) {
+            let _ = sender.send(response);
+        }
+    }
+
+    // Dropping the senders fails every call still waiting
+    alive.store(false, Ordering::Relaxed);
+    lock(&pending).clear();
+}
+
+fn unavailable(reason: String) -> SourceError {
+    SourceError::SourceUnavailable {
+        reason: format!("Extension unavailable: {}", reason),
+        estimated_downtime: None,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,7 @@
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub use client::*;
+pub use protocol::*;
+pub use server::*;
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    base::{PaginationRequest, SourceInfo},
+    error::SourceError,
+};
+
+/// Version of the protocol, exchanged in the `manifest` call.
+///
+/// Version 2 carries payloads in the camelCase wire format of `FORMAT_VERSION`.
+/// Hosts refuse extensions still speaking version 1 at the handshake, so the
+/// envelopes carry no `formatVersion` of their own.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Longest line either side accepts, larger messages are treated as a protocol violation
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Method names, one JSON-RPC method per source operation
+pub mod method {
+    pub const MANIFEST: &str = "manifest";
+    pub const HOME_PAGE: &str = "homePage";
+    pub const SEARCH: &str = "search";
+    pub const SERIES: &str = "series";
+    pub const CHAPTER: &str = "chapter";
+    pub const LATEST_UPDATES: &str = "latestUpdates";
+    pub const VIEW_MORE: &str = "viewMore";
+    pub const HOME_SECTIONS: &str = "homeSections";
+    pub const HOME_SECTION: &str = "homeSection";
+    pub const GET_SETTINGS: &str = "getSettings";
+    pub const SET_SETTINGS: &str = "setSettings";
+}
+
+/// Standard JSON-RPC error codes, plus one for errors raised by the source
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+
+    /// `data` holds the serialized `SourceError`
+    pub const SOURCE_ERROR: i64 = -32000;
+}
+
+/// A call from the host to the extension, one JSON object per line on stdin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: u64,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    pub fn new(id: u64, method: impl Into<String>, params: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// The answer to an `RpcRequest`, one JSON object per line on stdout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn success(id: u64, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn failure(id: Option<u64>, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Convert back to the `SourceError` the extension raised
+    pub fn into_source_error(self, method: &str) -> SourceError {
+        if let Some(error) = self
+            .data
+            .and_then(|data| serde_json::from_value::<SourceError>(data).ok())
+        {
+            return error;
+        }
+
+        match self.code {
+            error_code::METHOD_NOT_FOUND => SourceError::UnsupportedOperation {
+                operation: method.to_string(),
+                alternatives: Vec::new(),
+            },
+            _ => SourceError::Unexpected {
+                message: format!("Extension failed on '{}': {}", method, self.message),
+                error_code: Some(self.code.to_string()),
+            },
+        }
+    }
+}
+
+impl From<SourceError> for RpcError {
+    fn from(error: SourceError) -> Self {
+        Self {
+            code: error_code::SOURCE_ERROR,
+            message: error.to_string(),
+            data: serde_json::to_value(&error).ok(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestParams {
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub protocol_version: u32,
+    pub info: SourceInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesParams {
+    pub series_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterParams {
+    pub series_id: Option<String>,
+    pub chapter_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SectionParams {
+    pub section_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ViewMoreParams {
+    pub section_id: String,
+    pub pagination: PaginationRequest,
+}
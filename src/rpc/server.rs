@@ -0,0 +1,252 @@
+use futures::{StreamExt, stream};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+
+use crate::{
+    base::{
+        PaginationRequest, SearchRequest, SettingType, SettingValues, SharedSettings, SourceInfo,
+        SourceInstance,
+    },
+    error::{IntoSourceError, SourceResult},
+    rpc::{
+        ChapterParams, MAX_MESSAGE_SIZE, Manifest, PROTOCOL_VERSION, RpcError, RpcRequest,
+        RpcResponse, SectionParams, SeriesParams, ViewMoreParams, error_code, method,
+    },
+};
+
+/// Number of requests an extension handles at the same time
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Runs a source inside an extension process, answering JSON-RPC requests on stdio.
+///
+/// ```ignore
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() {
+///     let settings = SettingValues::from_defaults(&info.settings).shared();
+///     ExtensionServer::new(info, source).with_settings(settings).run().await.unwrap();
+/// }
+/// ```
+pub struct ExtensionServer {
+    info: SourceInfo,
+    source: SourceInstance,
+    settings: SharedSettings,
+}
+
+impl ExtensionServer {
+    pub fn new(info: SourceInfo, source: SourceInstance) -> Self {
+        let settings = SettingValues::from_defaults(&info.settings).shared();
+        let info = info.with_capabilities_of(&source);
+        Self {
+            info,
+            source,
+            settings,
+        }
+    }
+
+    /// Builder method to share setting values with the source's operations
+    pub fn with_settings(mut self, settings: SharedSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Serve requests from stdin until the host closes it
+    pub async fn run(self) -> SourceResult<()> {
+        self.serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout())
+            .await
+    }
+
+    /// Serve requests from `input`, writing one response line per request to `output`
+    pub async fn serve(
+        &self,
+        input: impl AsyncBufRead + Unpin,
+        output: impl AsyncWrite + Unpin,
+    ) -> SourceResult<()> {
+        let output = futures::lock::Mutex::new(output);
+        let lines = stream::unfold(input, |mut input| async move {
+            let mut line = Vec::new();
+            let read = (&mut input)
+                .take(MAX_MESSAGE_SIZE as u64 + 1)
+                .read_until(b'\n', &mut line)
+                .await;
+            // The rest of an oversized line is dropped, not read as the next request
+            let read = match read {
+                Ok(_) if line.len() > MAX_MESSAGE_SIZE && line.last() != Some(&b'\n') => {
+                    skip_line(&mut input).await.and(read)
+                }
+                read => read,
+            };
+            match read {
+                Ok(0) => None,
+                Ok(_) => Some((Ok(line), input)),
+                Err(error) => Some((Err(error), input)),
+            }
+        });
+
+        let failure = Mutex::new(None);
+        lines
+            .for_each_concurrent(MAX_CONCURRENT_REQUESTS, |line| {
+                let output = &output;
+                let failure = &failure;
+                async move {
+                    let response = match line {
+                        Ok(line) => self.handle_line(&line).await,
+                        Err(error) => {
+                            lock(failure).get_or_insert(error.into_source_error());
+                            return;
+                        }
+                    };
+                    let Some(response) = response else {
+                        return;
+                    };
+
+                    let written = match serde_json::to_vec(&response) {
+                        Ok(mut json) => {
+                            json.push(b'\n');
+                            let mut output = output.lock().await;
+                            match output.write_all(&json).await {
+                                Ok(()) => output.flush().await,
+                                Err(error) => Err(error),
+                            }
+                            .map_err(IntoSourceError::into_source_error)
+                        }
+                        Err(error) => Err(error.into_source_error()),
+                    };
+                    if let Err(error) = written {
+                        lock(failure).get_or_insert(error);
+                    }
+                }
+            })
+            .await;
+
+        failure
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .map_or(Ok(()), Err)
+    }
+
+    async fn handle_line(&self, line: &[u8]) -> Option<RpcResponse> {
+        if line.len() > MAX_MESSAGE_SIZE {
+            let error = RpcError::new(error_code::INVALID_REQUEST, "Message too large");
+            return Some(RpcResponse::failure(None, error));
+        }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+
+        let request: RpcRequest = match serde_json::from_slice(line) {
+            Ok(request) => request,
+            Err(error) => {
+                let error = RpcError::new(error_code::PARSE_ERROR, error.to_string());
+                return Some(RpcResponse::failure(None, error));
+            }
+        };
+
+        Some(match self.dispatch(&request.method, request.params).await {
+            Ok(result) => RpcResponse::success(request.id, result),
+            Err(error) => RpcResponse::failure(Some(request.id), error),
+        })
+    }
+
+    async fn dispatch(&self, name: &str, params: Value) -> Result<Value, RpcError> {
+        match name {
+            method::MANIFEST => respond(Manifest {
+                protocol_version: PROTOCOL_VERSION,
+                info: self.info.clone(),
+            }),
+            method::HOME_PAGE => respond((self.source.home_page)().await?),
+            method::SEARCH => {
+                let request: SearchRequest = decode(params)?;
+                respond((self.source.search)(request).await?)
+            }
+            method::SERIES => {
+                let params: SeriesParams = decode(params)?;
+                respond((self.source.series)(params.series_id).await?)
+            }
+            method::CHAPTER => {
+                let params: ChapterParams = decode(params)?;
+                respond((self.source.chapter)(params.series_id, params.chapter_id).await?)
+            }
+            method::LATEST_UPDATES if self.source.supports_latest_updates() => {
+                let pagination: PaginationRequest = decode(params)?;
+                respond(self.source.fetch_latest_updates(pagination).await?)
+            }
+            method::VIEW_MORE if self.source.supports_view_more() => {
+                let params: ViewMoreParams = decode(params)?;
+                respond(
+                    self.source
+                        .fetch_view_more(params.section_id, params.pagination)
+                        .await?,
+                )
+            }
+            method::HOME_SECTIONS if self.source.supports_lazy_home_page() => {
+                let home_sections = self.source.home_sections.as_ref().ok_or_else(not_found)?;
+                respond(home_sections().await?)
+            }
+            method::HOME_SECTION if self.source.supports_lazy_home_page() => {
+                let params: SectionParams = decode(params)?;
+                let home_section = self.source.home_section.as_ref().ok_or_else(not_found)?;
+                respond(home_section(params.section_id).await?)
+            }
+            method::GET_SETTINGS => respond(
+                self.settings
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .values(),
+            ),
+            method::SET_SETTINGS => {
+                let values: HashMap<String, SettingType> = decode(params)?;
+                self.settings
+                    .write()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .update(&self.info.settings, values)?;
+                Ok(Value::Null)
+            }
+            _ => Err(not_found()),
+        }
+    }
+}
+
+/// Consume input up to and including the next line break
+async fn skip_line(input: &mut (impl AsyncBufRead + Unpin)) -> std::io::Result<()> {
+    loop {
+        let buffer = input.fill_buf().await?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|byte| *byte == b'\n') {
+            Some(end) => {
+                input.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let length = buffer.len();
+                input.consume(length);
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn decode<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|error| RpcError::new(error_code::INVALID_PARAMS, error.to_string()))
+}
+
+fn respond<T: Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result)
+        .map_err(|error| RpcError::new(error_code::INTERNAL_ERROR, error.to_string()))
+}
+
+fn not_found() -> RpcError {
+    RpcError::new(error_code::METHOD_NOT_FOUND, "Method not found")
+}
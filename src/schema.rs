@@ -0,0 +1,32 @@
+use schemars::{SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Value, json};
+
+use crate::wire::root_types;
+
+/// Where the generated schema is committed, relative to the crate root
+pub const SCHEMA_PATH: &str = "schema/rrmounter.schema.json";
+
+/// JSON Schema (draft 2020-12) with a definition for every serializable type
+pub fn json_schema() -> Value {
+    let mut generator = SchemaGenerator::new(SchemaSettings::draft2020_12());
+
+    macro_rules! add {
+        ($type:ty) => {
+            generator.subschema_for::<$type>();
+        };
+    }
+    root_types!(add);
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "rrmounter",
+        "$defs": generator.take_definitions(true),
+    })
+}
+
+/// The schema as committed: pretty-printed with a trailing newline
+pub fn json_schema_string() -> String {
+    let mut schema = serde_json::to_string_pretty(&json_schema()).unwrap_or_default();
+    schema.push('\n');
+    schema
+}
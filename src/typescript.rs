@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use ts_rs::{Config, TS, TypeVisitor};
+
+use crate::wire::root_types;
+
+/// Where the generated declarations are committed, relative to the crate root
+pub const DECLARATIONS_PATH: &str = "bindings/rrmounter.d.ts";
+
+/// TypeScript declarations for every serializable type and the types they use, sorted by name.
+///
+/// 64-bit integers are declared as `number`, matching how they appear in JSON.
+pub fn typescript_declarations() -> String {
+    let mut collector = Collector {
+        config: Config::new().with_large_int("number"),
+        declarations: BTreeMap::new(),
+    };
+
+    macro_rules! visit {
+        ($type:ty) => {
+            collector.visit::<$type>();
+        };
+    }
+    root_types!(visit);
+
+    let mut output = String::from("// Generated from the rrmounter types, do not edit by hand.\n");
+    // serde writes `std::time::Duration` as seconds and nanoseconds
+    collector.declarations.insert(
+        "Duration".to_string(),
+        "export type Duration = { secs: number, nanos: number };".to_string(),
+    );
+    for declaration in collector.declarations.values() {
+        output.push('\n');
+        output.push_str(declaration);
+        output.push('\n');
+    }
+    output
+}
+
+/// Gathers the declaration of each visited type and, recursively, of its dependencies
+struct Collector {
+    config: Config,
+    declarations: BTreeMap<String, String>,
+}
+
+impl TypeVisitor for Collector {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        // Only named types have a declaration, primitives and containers are inlined
+        if T::output_path().is_none() {
+            return;
+        }
+        let name = T::ident(&self.config);
+        if self.declarations.contains_key(&name) {
+            return;
+        }
+
+        let docs = T::docs().unwrap_or_default();
+        let declaration = format!("{}export {}", docs, T::decl(&self.config));
+        self.declarations.insert(name, declaration);
+        T::visit_dependencies(self);
+    }
+}
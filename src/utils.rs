@@ -16,3 +16,11 @@ pub fn create_selectors(selectors: &[(&str, &str)]) -> SourceResult<HashMap<Stri
         })
         .collect()
 }
+
+/// Current time as seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
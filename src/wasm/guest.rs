@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::rpc::RpcError;
+
+/// Name of the import module holding the host functions
+pub const IMPORT_MODULE: &str = "rrmounter";
+
+/// Names of the functions the guest exports
+pub mod export {
+    pub const MEMORY: &str = "memory";
+    pub const ALLOC: &str = "rr_alloc";
+    pub const CALL: &str = "rr_call";
+}
+
+/// Names of the functions the host provides
+pub mod import {
+    pub const HTTP_GET: &str = "http_get";
+    pub const HTTP_REQUEST: &str = "http_request";
+    pub const HTML_SELECT: &str = "html_select";
+    pub const HTML_SELECT_ATTR: &str = "html_select_attr";
+    pub const JSON_GET: &str = "json_get";
+    pub const SETTING: &str = "setting";
+}
+
+/// What `rr_call` returns, either the method's result or an error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct GuestResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct GuestHttpRequest {
+    pub url: String,
+
+    /// Defaults to GET
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Response to a `GuestHttpRequest`, unsuccessful statuses are left to the guest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct GuestHttpResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// An element matched by `html_select`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct HtmlElement {
+    /// Text content with whitespace collapsed
+    pub text: String,
+    pub html: String,
+    pub attributes: HashMap<String, String>,
+}
+
+/// Pack a guest pointer and length into the `i64` returned across the boundary
+pub fn pack(pointer: u32, length: u32) -> i64 {
+    (((pointer as u64) << 32) | length as u64) as i64
+}
+
+/// Split a packed `i64` into a guest pointer and length
+pub fn unpack(packed: i64) -> (u32, u32) {
+    let packed = packed as u64;
+    ((packed >> 32) as u32, packed as u32)
+}
@@ -0,0 +1,742 @@
+use scraper::Html;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use wasmtime::{
+    AsContext, AsContextMut, Caller, Config, Engine, Instance, Linker, Memory, Module,
+    ResourceLimiter, Store, Trap, TypedFunc,
+};
+
+use crate::{
+    base::{
+        HomeSection, HttpClient, PaginatedResponse, PaginationRequest, SearchRequest, SettingType,
+        SettingValues, SharedSettings, SourceCapabilities, SourceInfo, SourceInstance,
+        StatefulSource,
+    },
+    error::{AsyncSourceResult, IntoSourceError, ParseContentType, SourceError, SourceResult},
+    generate::{LatestUpdate, Series, SeriesEntry},
+    rpc::{
+        ChapterParams, Manifest, ManifestParams, PROTOCOL_VERSION, SectionParams, SeriesParams,
+        ViewMoreParams, method,
+    },
+    utils::create_selectors,
+    wasm::{
+        GuestHttpRequest, GuestHttpResponse, GuestResult, HtmlElement, IMPORT_MODULE, export,
+        import, pack, unpack,
+    },
+};
+
+/// Fuel consumed between two chances for the wall-clock timeout to fire
+const FUEL_YIELD_INTERVAL: u64 = 100_000;
+
+/// Limits applied to every call into a module
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Largest linear memory, in bytes
+    pub memory: usize,
+
+    /// Instructions budget, roughly one unit of fuel per WebAssembly instruction
+    pub fuel: u64,
+
+    /// Wall-clock time, including HTTP requests made through the host
+    pub timeout: Duration,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            memory: 64 * 1024 * 1024,
+            fuel: 5_000_000_000,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of one call, owned by its store
+struct HostState {
+    http: HttpClient,
+    settings: SharedSettings,
+    allowed_hosts: Vec<String>,
+    memory_limit: usize,
+    memory_exceeded: bool,
+}
+
+impl ResourceLimiter for HostState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if desired > self.memory_limit {
+            self.memory_exceeded = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(desired <= 10_000)
+    }
+}
+
+/// A source compiled to WebAssembly, running sandboxed inside the host.
+///
+/// Every call gets a fresh instance with its own memory, fuel and time limits,
+/// so a failing call leaves nothing behind for the next one. The module can
+/// only reach the network and settings through the functions of the guest API.
+///
+/// Strings and JSON documents are passed as `(pointer, length)` pairs into the
+/// guest's exported `memory`. Functions returning data give back an `i64`
+/// packing the pointer in the high 32 bits and the length in the low 32 bits,
+/// with `0` meaning "no value". The host allocates returned data through the
+/// guest's `rr_alloc` export, so the guest owns it.
+///
+/// The guest exports:
+/// - `memory`
+/// - `rr_alloc(len: i32) -> i32`
+/// - `rr_call(method_ptr, method_len, params_ptr, params_len: i32) -> i64`, taking
+///   a method name and JSON params from `rpc::method` and returning a `GuestResult`
+///
+/// The host provides, in the `rrmounter` import module:
+/// - `http_get(url_ptr, url_len) -> i64`, the response body as text
+/// - `http_request(request_ptr, request_len) -> i64`, a `GuestHttpRequest` in and
+///   a `GuestHttpResponse` out
+/// - `html_select(html_ptr, html_len, selector_ptr, selector_len) -> i64`, a JSON
+///   array of `HtmlElement`
+/// - `html_select_attr(html_ptr, html_len, selector_ptr, selector_len, attr_ptr, attr_len) -> i64`,
+///   a JSON array of attribute values
+/// - `json_get(json_ptr, json_len, pointer_ptr, pointer_len) -> i64`, the value at a
+///   JSON pointer, with strings returned unquoted
+/// - `setting(key_ptr, key_len) -> i64`, the current value of a setting, with
+///   strings returned unquoted
+///
+/// A failing host function, such as an HTTP error, aborts the call with the
+/// matching `SourceError`.
+///
+/// HTTP requests may only reach the host of the manifest's `base_url`, its
+/// subdomains, and hosts added with `with_allowed_hosts`. Anything else fails
+/// with `SourceError::Forbidden`.
+pub struct WasmSource {
+    engine: Engine,
+    module: Module,
+    linker: Linker<HostState>,
+    http: HttpClient,
+    settings: SharedSettings,
+    limits: WasmLimits,
+    allowed_hosts: RwLock<Vec<String>>,
+
+    // Every operation, bound once and narrowed to the manifest of each `instance` call
+    bound: Mutex<Option<SourceInstance>>,
+}
+
+impl WasmSource {
+    /// Compile a module from its binary or text format
+    pub fn new(module: impl AsRef<[u8]>) -> SourceResult<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|error| invalid_module(&error))?;
+        let module = Module::new(&engine, module).map_err(|error| invalid_module(&error))?;
+        let linker = host_linker(&engine).map_err(|error| invalid_module(&error))?;
+
+        Ok(Self {
+            engine,
+            module,
+            linker,
+            http: HttpClient::default(),
+            settings: SettingValues::default().shared(),
+            limits: WasmLimits::default(),
+            allowed_hosts: RwLock::default(),
+            bound: Mutex::default(),
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> SourceResult<Self> {
+        let module = std::fs::read(path).map_err(IntoSourceError::into_source_error)?;
+        Self::new(module)
+    }
+
+    /// Builder method to share the HTTP client, and its cache, with other sources
+    pub fn with_http_client(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Builder method to share setting values with whatever updates them
+    pub fn with_settings(mut self, settings: SharedSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Builder method to set the limits of every call
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Builder method to let the module reach these hosts, and their subdomains,
+    /// on top of the host of its `base_url`
+    pub fn with_allowed_hosts(
+        mut self,
+        hosts: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let hosts = hosts
+            .into_iter()
+            .map(|host| host.into().to_ascii_lowercase());
+        self.allowed_hosts
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .extend(hosts);
+        self
+    }
+
+    /// Current setting values, read by the module through the `setting` function
+    pub fn settings(&self) -> &SharedSettings {
+        &self.settings
+    }
+
+    /// Read the module's manifest, filling in defaults for settings without a value
+    pub async fn manifest(&self) -> SourceResult<SourceInfo> {
+        let manifest: Manifest = self
+            .call(
+                method::MANIFEST,
+                &ManifestParams {
+                    protocol_version: PROTOCOL_VERSION,
+                },
+            )
+            .await?;
+
+        if manifest.protocol_version != PROTOCOL_VERSION {
+            return Err(SourceError::ConfigurationError {
+                setting: "protocol_version".to_string(),
+                expected: PROTOCOL_VERSION.to_string(),
+                current: manifest.protocol_version.to_string(),
+            });
+        }
+
+        self.settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert_defaults(&manifest.info.settings);
+        let base_host = manifest
+            .info
+            .base_url
+            .as_deref()
+            .and_then(|base_url| url::Url::parse(base_url).ok())
+            .and_then(|base_url| base_url.host_str().map(str::to_ascii_lowercase));
+        if let Some(host) = base_host {
+            let mut allowed_hosts = write(&self.allowed_hosts);
+            if !allowed_hosts.contains(&host) {
+                allowed_hosts.push(host);
+            }
+        }
+        Ok(manifest.info)
+    }
+
+    /// Call a method of the module in a fresh instance
+    pub async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> SourceResult<R> {
+        let params = serde_json::to_vec(params).map_err(|error| SourceError::Unexpected {
+            message: format!("Could not encode '{}' parameters: {}", method, error),
+            error_code: None,
+        })?;
+
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                http: self.http.clone(),
+                settings: self.settings.clone(),
+                allowed_hosts: self
+                    .allowed_hosts
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .clone(),
+                memory_limit: self.limits.memory,
+                memory_exceeded: false,
+            },
+        );
+        store.limiter(|state| state);
+        store
+            .set_fuel(self.limits.fuel)
+            .and_then(|_| store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL)))
+            .map_err(|error| invalid_module(&error))?;
+
+        let run = async {
+            let instance = self
+                .linker
+                .instantiate_async(&mut store, &self.module)
+                .await?;
+            let guest = Guest::of_instance(&instance, &mut store)?;
+            let call: TypedFunc<(i32, i32, i32, i32), i64> =
+                instance.get_typed_func(&mut store, export::CALL)?;
+
+            let (method_pointer, method_length) =
+                unpack(guest.write(&mut store, method.as_bytes()).await?);
+            let (params_pointer, params_length) = unpack(guest.write(&mut store, &params).await?);
+            let output = call
+                .call_async(
+                    &mut store,
+                    (
+                        method_pointer as i32,
+                        method_length as i32,
+                        params_pointer as i32,
+                        params_length as i32,
+                    ),
+                )
+                .await?;
+            guest.read_packed(&store, output)
+        };
+
+        let output = match tokio::time::timeout(self.limits.timeout, run).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(error)) => return Err(self.call_error(method, error, store.data())),
+            Err(_) => {
+                return Err(SourceError::Timeout {
+                    timeout: self.limits.timeout,
+                });
+            }
+        };
+
+        let output: GuestResult = serde_json::from_slice(&output).map_err(|error| {
+            SourceError::parse_error(
+                format!("Invalid '{}' response from the module: {}", method, error),
+                ParseContentType::Json,
+            )
+        })?;
+        if let Some(error) = output.error {
+            return Err(error.into_source_error(method));
+        }
+        serde_json::from_value(output.result.unwrap_or(Value::Null)).map_err(|error| {
+            SourceError::parse_error(
+                format!("Invalid '{}' response from the module: {}", method, error),
+                ParseContentType::Json,
+            )
+        })
+    }
+
+    /// Expose the module as a regular source, based on the operations its manifest lists
+    pub async fn instance(self: &Arc<Self>) -> SourceResult<(SourceInfo, SourceInstance)> {
+        let info = self.manifest().await?;
+        let source = {
+            let mut bound = self
+                .bound
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match *bound {
+                Some(source) => source,
+                None => *bound.insert(SourceInstance::bind(self.clone())?),
+            }
+        };
+        let capabilities = info.capabilities;
+        Ok((info, source.with_capabilities(capabilities)))
+    }
+
+    fn call_error(&self, method: &str, error: wasmtime::Error, state: &HostState) -> SourceError {
+        if let Some(error) = error.downcast_ref::<SourceError>() {
+            return error.clone();
+        }
+        if state.memory_exceeded {
+            return SourceError::Unexpected {
+                message: format!(
+                    "Module exceeded its memory limit of {} bytes on '{}'",
+                    self.limits.memory, method
+                ),
+                error_code: None,
+            };
+        }
+        if let Some(Trap::OutOfFuel) = error.downcast_ref::<Trap>() {
+            return SourceError::Unexpected {
+                message: format!(
+                    "Module ran out of fuel after {} units on '{}'",
+                    self.limits.fuel, method
+                ),
+                error_code: None,
+            };
+        }
+        SourceError::Unexpected {
+            message: format!("Module failed on '{}': {}", method, error),
+            error_code: None,
+        }
+    }
+}
+
+/// Every operation is forwarded, `instance` keeps the ones the manifest lists
+impl StatefulSource for WasmSource {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        Box::pin(async move { self.call(method::HOME_PAGE, &Value::Null).await })
+    }
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        Box::pin(async move { self.call(method::SEARCH, &request).await })
+    }
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series> {
+        Box::pin(async move { self.call(method::SERIES, &SeriesParams { series_id }).await })
+    }
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>> {
+        let params = ChapterParams {
+            series_id,
+            chapter_id,
+        };
+        Box::pin(async move { self.call(method::CHAPTER, &params).await })
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            latest_updates: true,
+            view_more: true,
+            lazy_home_page: true,
+        }
+    }
+
+    fn latest_updates(
+        self: Arc<Self>,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+        Box::pin(async move { self.call(method::LATEST_UPDATES, &pagination).await })
+    }
+
+    fn view_more(
+        self: Arc<Self>,
+        section_id: String,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        let params = ViewMoreParams {
+            section_id,
+            pagination,
+        };
+        Box::pin(async move { self.call(method::VIEW_MORE, &params).await })
+    }
+
+    fn home_sections(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        Box::pin(async move { self.call(method::HOME_SECTIONS, &Value::Null).await })
+    }
+
+    fn home_section(self: Arc<Self>, section_id: String) -> AsyncSourceResult<HomeSection> {
+        let params = SectionParams { section_id };
+        Box::pin(async move { self.call(method::HOME_SECTION, &params).await })
+    }
+}
+
+/// Memory and allocator of an instance, used to move data across the boundary
+struct Guest {
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+impl Guest {
+    fn of_instance(instance: &Instance, store: &mut Store<HostState>) -> wasmtime::Result<Self> {
+        let memory = instance
+            .get_memory(&mut *store, export::MEMORY)
+            .ok_or_else(|| wasmtime::format_err!("module does not export `memory`"))?;
+        let alloc = instance.get_typed_func(&mut *store, export::ALLOC)?;
+        Ok(Self { memory, alloc })
+    }
+
+    fn of_caller(caller: &mut Caller<'_, HostState>) -> wasmtime::Result<Self> {
+        let memory = caller
+            .get_export(export::MEMORY)
+            .and_then(|export| export.into_memory())
+            .ok_or_else(|| wasmtime::format_err!("module does not export `memory`"))?;
+        let alloc = caller
+            .get_export(export::ALLOC)
+            .and_then(|export| export.into_func())
+            .ok_or_else(|| wasmtime::format_err!("module does not export `{}`", export::ALLOC))?
+            .typed(&*caller)?;
+        Ok(Self { memory, alloc })
+    }
+
+    fn read(&self, store: impl AsContext, pointer: i32, length: i32) -> wasmtime::Result<Vec<u8>> {
+        let start = pointer as u32 as usize;
+        let end = start + length as u32 as usize;
+        self.memory
+            .data(&store)
+            .get(start..end)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| wasmtime::format_err!("guest pointer out of bounds"))
+    }
+
+    fn read_string(
+        &self,
+        store: impl AsContext,
+        pointer: i32,
+        length: i32,
+    ) -> wasmtime::Result<String> {
+        String::from_utf8(self.read(store, pointer, length)?)
+            .map_err(|_| wasmtime::format_err!("guest string is not valid UTF-8"))
+    }
+
+    fn read_packed(&self, store: impl AsContext, packed: i64) -> wasmtime::Result<Vec<u8>> {
+        let (pointer, length) = unpack(packed);
+        self.read(store, pointer as i32, length as i32)
+    }
+
+    /// Copy bytes into memory allocated by the guest
+    async fn write(
+        &self,
+        mut store: impl AsContextMut<Data = HostState>,
+        bytes: &[u8],
+    ) -> wasmtime::Result<i64> {
+        let length = i32::try_from(bytes.len())
+            .map_err(|_| wasmtime::format_err!("value too large for the guest"))?;
+        let pointer = self.alloc.call_async(&mut store, length).await?;
+        self.memory
+            .write(&mut store, pointer as u32 as usize, bytes)?;
+        Ok(pack(pointer as u32, length as u32))
+    }
+}
+
+fn host_linker(engine: &Engine) -> wasmtime::Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::HTTP_GET,
+        |mut caller: Caller<'_, HostState>, (url, url_length): (i32, i32)| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let url = guest.read_string(&caller, url, url_length)?;
+                check_host(&caller.data().allowed_hosts, &url).map_err(wasmtime::Error::new)?;
+                let http = caller.data().http.clone();
+                let response = http.get(&url).await.map_err(wasmtime::Error::new)?;
+                guest.write(&mut caller, &response.body).await
+            })
+        },
+    )?;
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::HTTP_REQUEST,
+        |mut caller: Caller<'_, HostState>, (request, request_length): (i32, i32)| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let request: GuestHttpRequest =
+                    serde_json::from_slice(&guest.read(&caller, request, request_length)?)?;
+                let (http, allowed_hosts) = (
+                    caller.data().http.clone(),
+                    caller.data().allowed_hosts.clone(),
+                );
+                let response = http_request(&http, &allowed_hosts, request)
+                    .await
+                    .map_err(wasmtime::Error::new)?;
+                guest
+                    .write(&mut caller, &serde_json::to_vec(&response)?)
+                    .await
+            })
+        },
+    )?;
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::HTML_SELECT,
+        |mut caller: Caller<'_, HostState>,
+         (html, html_length, selector, selector_length): (i32, i32, i32, i32)| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let html = guest.read_string(&caller, html, html_length)?;
+                let selector = guest.read_string(&caller, selector, selector_length)?;
+                let elements = html_select(&html, &selector).map_err(wasmtime::Error::new)?;
+                guest
+                    .write(&mut caller, &serde_json::to_vec(&elements)?)
+                    .await
+            })
+        },
+    )?;
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::HTML_SELECT_ATTR,
+        |mut caller: Caller<'_, HostState>,
+         (html, html_length, selector, selector_length, attribute, attribute_length): (
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+        )| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let html = guest.read_string(&caller, html, html_length)?;
+                let selector = guest.read_string(&caller, selector, selector_length)?;
+                let attribute = guest.read_string(&caller, attribute, attribute_length)?;
+                let values: Vec<String> = html_select(&html, &selector)
+                    .map_err(wasmtime::Error::new)?
+                    .into_iter()
+                    .filter_map(|mut element| element.attributes.remove(&attribute))
+                    .collect();
+                guest
+                    .write(&mut caller, &serde_json::to_vec(&values)?)
+                    .await
+            })
+        },
+    )?;
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::JSON_GET,
+        |mut caller: Caller<'_, HostState>,
+         (json, json_length, pointer, pointer_length): (i32, i32, i32, i32)| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let json: Value =
+                    serde_json::from_slice(&guest.read(&caller, json, json_length)?)?;
+                let pointer = guest.read_string(&caller, pointer, pointer_length)?;
+                match json.pointer(&pointer) {
+                    Some(Value::String(value)) => guest.write(&mut caller, value.as_bytes()).await,
+                    Some(value) => guest.write(&mut caller, &serde_json::to_vec(value)?).await,
+                    None => Ok(0),
+                }
+            })
+        },
+    )?;
+
+    linker.func_wrap_async(
+        IMPORT_MODULE,
+        import::SETTING,
+        |mut caller: Caller<'_, HostState>, (key, key_length): (i32, i32)| {
+            Box::new(async move {
+                let guest = Guest::of_caller(&mut caller)?;
+                let key = guest.read_string(&caller, key, key_length)?;
+                let value = caller
+                    .data()
+                    .settings
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .get(&key)
+                    .cloned();
+                let value = match value {
+                    None => return Ok(0),
+                    Some(SettingType::String(value)) => value.into_bytes(),
+                    Some(SettingType::Boolean(value)) => serde_json::to_vec(&value)?,
+                    Some(SettingType::Number(value)) => serde_json::to_vec(&value)?,
+                    Some(SettingType::LanguageKey(value)) => serde_json::to_vec(&value)?,
+                };
+                guest.write(&mut caller, &value).await
+            })
+        },
+    )?;
+
+    Ok(linker)
+}
+
+async fn http_request(
+    http: &HttpClient,
+    allowed_hosts: &[String],
+    request: GuestHttpRequest,
+) -> SourceResult<GuestHttpResponse> {
+    check_host(allowed_hosts, &request.url)?;
+    let method = request.method.as_deref().unwrap_or("GET");
+    let method =
+        reqwest::Method::from_bytes(method.as_bytes()).map_err(|_| SourceError::Unexpected {
+            message: format!("Invalid HTTP method '{}'", method),
+            error_code: None,
+        })?;
+
+    let mut builder = http.inner().request(method, &request.url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body {
+        builder = builder.body(body);
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(IntoSourceError::into_source_error)?;
+    // Redirects are followed by the shared client, where they end up is checked too
+    check_host(allowed_hosts, response.url().as_str())?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response
+        .text()
+        .await
+        .map_err(IntoSourceError::into_source_error)?;
+
+    Ok(GuestHttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Refuse URLs that are not HTTP(S) or point outside the allowed hosts
+fn check_host(allowed_hosts: &[String], url: &str) -> SourceResult<()> {
+    let parsed = url::Url::parse(url).ok();
+    let host = parsed
+        .as_ref()
+        .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+        .and_then(|parsed| parsed.host_str())
+        .map(str::to_ascii_lowercase);
+    let allowed = host.is_some_and(|host| {
+        allowed_hosts.iter().any(|allowed| {
+            host == *allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    });
+    if allowed {
+        return Ok(());
+    }
+    Err(SourceError::Forbidden {
+        reason: format!("the module may not request {}", url),
+    })
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn html_select(html: &str, selector: &str) -> SourceResult<Vec<HtmlElement>> {
+    let selectors = create_selectors(&[(selector, selector)])?;
+    let document = Html::parse_document(html);
+
+    Ok(document
+        .select(&selectors[selector])
+        .map(|element| HtmlElement {
+            text: element
+                .text()
+                .flat_map(str::split_whitespace)
+                .collect::<Vec<_>>()
+                .join(" "),
+            html: element.html(),
+            attributes: element
+                .value()
+                .attrs()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        })
+        .collect())
+}
+
+fn invalid_module(error: &wasmtime::Error) -> SourceError {
+    SourceError::Unexpected {
+        message: format!("Invalid WebAssembly module: {}", error),
+        error_code: None,
+    }
+}
@@ -0,0 +1,5 @@
+pub mod guest;
+pub mod host;
+
+pub use guest::*;
+pub use host::*;
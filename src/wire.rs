@@ -0,0 +1,93 @@
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::error::{SourceError, SourceResult};
+
+/// Version of the JSON format the serializable types are written in.
+///
+/// Version 2 names every field, variant and value in camelCase. Version 1 used
+/// snake_case struct fields, PascalCase language keys and error variants and
+/// lowercase badge colors; those names are still accepted when reading.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Version of documents written before the version was recorded in them
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+/// Default of the `formatVersion` field of persisted documents
+pub fn legacy_format_version() -> u32 {
+    LEGACY_FORMAT_VERSION
+}
+
+/// Reject a document written in a newer format than this build understands
+pub fn check_format_version(document: &str, version: u32) -> SourceResult<()> {
+    if version > FORMAT_VERSION {
+        return Err(SourceError::ConfigurationError {
+            setting: format!("formatVersion of {}", document),
+            expected: format!("{} or lower", FORMAT_VERSION),
+            current: version.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Read the `formatVersion` field of a payload, rejecting newer formats
+pub fn deserialize_format_version<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    check_format_version("payload", version).map_err(serde::de::Error::custom)?;
+    Ok(version)
+}
+
+/// Write the `formatVersion` field of a payload.
+///
+/// Payloads are always written in the current format, whatever version they were read in.
+pub fn serialize_format_version<S: Serializer>(_: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(FORMAT_VERSION)
+}
+
+/// Call `$visit!` with every type written at the top level of a JSON document.
+///
+/// The JSON Schema and the TypeScript declarations are both generated from
+/// this list, so a type added here shows up in both.
+#[cfg(any(feature = "schema", feature = "ts"))]
+macro_rules! root_types {
+    ($visit:ident) => {
+        $visit!(crate::generate::Series);
+        $visit!(crate::generate::SeriesEntry);
+        $visit!(crate::generate::Chapter);
+        $visit!(crate::generate::LatestUpdate);
+        $visit!(crate::generate::MergedSeries);
+        $visit!(crate::generate::MatchScore);
+        $visit!(crate::base::SearchRequest);
+        $visit!(crate::base::PaginatedResponse<crate::generate::SeriesEntry>);
+        $visit!(crate::base::PaginatedResponse<crate::generate::LatestUpdate>);
+        $visit!(crate::base::HomeSection);
+        $visit!(crate::base::Banner);
+        $visit!(crate::base::Badge);
+        $visit!(crate::base::FilterKind);
+        $visit!(crate::base::Language);
+        $visit!(crate::base::LanguageKey);
+        $visit!(crate::base::ContentRating);
+        $visit!(crate::base::SourceInfo);
+        $visit!(crate::base::SettingValues);
+        $visit!(crate::base::CacheKey);
+        $visit!(crate::base::CachePolicy);
+        $visit!(crate::base::CacheOperation);
+        $visit!(crate::error::SourceError);
+        $visit!(crate::error::ErrorContext);
+        $visit!(crate::library::Library);
+        $visit!(crate::library::UpdateReport);
+        $visit!(crate::export::DownloadTask);
+        $visit!(crate::local::LocalSeriesMetadata);
+        $visit!(crate::rpc::RpcRequest);
+        $visit!(crate::rpc::RpcResponse);
+        $visit!(crate::rpc::Manifest);
+        $visit!(crate::rpc::ManifestParams);
+        $visit!(crate::rpc::SeriesParams);
+        $visit!(crate::rpc::ChapterParams);
+        $visit!(crate::rpc::SectionParams);
+        $visit!(crate::rpc::ViewMoreParams);
+    };
+}
+#[cfg(any(feature = "schema", feature = "ts"))]
+pub(crate) use root_types;
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{Stub, entry, page, series};
+use futures::StreamExt;
+use rrmounter::SourceError;
+use rrmounter::base::{GlobalSearch, SearchRequest, SourceInstance};
+use std::time::Duration;
+
+/// A source answering with a single entry after `delay`
+fn delayed_source(source_id: &'static str, delay: Duration) -> SourceInstance {
+    Stub::default()
+        .with_search(move |request: SearchRequest| {
+            let items = vec![entry(&series(source_id, "1", &request.query))];
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                Ok(page(items, 1, false))
+            })
+        })
+        .bind()
+}
+
+fn broken_source() -> SourceInstance {
+    Stub::default()
+        .with_search(|_| Box::pin(async { Err(SourceError::network("offline", true)) }))
+        .bind()
+}
+
+#[tokio::test]
+async fn slow_sources_time_out_on_their_own() {
+    let slow = delayed_source("slow", Duration::from_secs(30));
+    let fast = delayed_source("fast", Duration::ZERO);
+    let search = GlobalSearch::new(Duration::from_millis(100))
+        .with_source("slow", &slow)
+        .with_source("fast", &fast);
+
+    let results = search
+        .collect(SearchRequest::simple("one piece".to_string()))
+        .await;
+
+    assert_eq!(results.get("fast").unwrap().items[0].title, "one piece");
+    assert_eq!(results.errors.len(), 1);
+    let (source_id, context) = &results.errors[0];
+    assert_eq!(source_id, "slow");
+    assert!(matches!(
+        context.error,
+        SourceError::Timeout { timeout } if timeout == Duration::from_millis(100)
+    ));
+}
+
+#[tokio::test]
+async fn failing_sources_do_not_hide_the_others() {
+    let broken = broken_source();
+    let working = delayed_source("working", Duration::ZERO);
+    let search = GlobalSearch::new(Duration::from_secs(5))
+        .with_source("broken", &broken)
+        .with_source("working", &working);
+
+    let results = search
+        .collect(SearchRequest::simple("naruto".to_string()))
+        .await;
+
+    assert_eq!(results.item_count(), 1);
+    assert!(results.get("broken").is_none());
+    assert_eq!(results.errors[0].0, "broken");
+    assert!(matches!(
+        results.errors[0].1.error,
+        SourceError::NetworkError { .. }
+    ));
+}
+
+#[tokio::test]
+async fn streams_follow_completion_and_groups_registration() {
+    let late = delayed_source("late", Duration::from_millis(100));
+    let early = delayed_source("early", Duration::ZERO);
+    let broken = broken_source();
+    let search = GlobalSearch::new(Duration::from_secs(5))
+        .with_source("late", &late)
+        .with_source("broken", &broken)
+        .with_source("early", &early);
+    let request = SearchRequest::simple("bleach".to_string());
+
+    let streamed: Vec<String> = search
+        .stream(request.clone())
+        .map(|result| result.source_id)
+        .collect()
+        .await;
+    assert_eq!(streamed.last().unwrap(), "late");
+
+    let results = search.collect(request).await;
+    let grouped: Vec<&str> = results.groups.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(grouped, ["late", "early"]);
+}
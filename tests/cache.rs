@@ -0,0 +1,141 @@
+mod common;
+
+use common::{Stub, series, temp_path};
+use rrmounter::base::{CacheKey, CachePolicy, CachedSource, SourceInstance};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A source serving every series and chapter, counting how often it is asked
+fn counting_source(source_id: &'static str) -> (SourceInstance, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let series_calls = calls.clone();
+    let chapter_calls = calls.clone();
+    let source = Stub::default()
+        .with_series(move |series_id: String| {
+            series_calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(series(source_id, &series_id, "Title")) })
+        })
+        .with_chapter(move |series_id: Option<String>, chapter_id: String| {
+            chapter_calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(vec![format!("{:?}/{}", series_id, chapter_id)]) })
+        })
+        .bind();
+
+    (source, calls)
+}
+
+#[test]
+fn keys_keep_arguments_apart() {
+    assert_ne!(
+        CacheKey::chapter("s", None, "1"),
+        CacheKey::chapter("s", Some(""), "1")
+    );
+    assert_ne!(
+        CacheKey::chapter("s", Some("a/b"), "c"),
+        CacheKey::chapter("s", Some("a"), "b/c")
+    );
+    assert_ne!(
+        CacheKey::series("s", "1"),
+        CacheKey::chapter("s", None, "1")
+    );
+}
+
+#[tokio::test]
+async fn chapters_without_a_series_are_cached_separately() {
+    let (source, calls) = counting_source("test");
+    let cache = CachedSource::new("test", &source, CachePolicy::default());
+
+    let without = cache.chapter(None, "1".to_string()).await.unwrap();
+    let empty = cache
+        .chapter(Some(String::new()), "1".to_string())
+        .await
+        .unwrap();
+
+    assert_ne!(without.value, empty.value);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn hits_are_served_without_calling_the_source() {
+    let (source, calls) = counting_source("test");
+    let cache = CachedSource::new("test", &source, CachePolicy::default());
+
+    cache.series("1".to_string()).await.unwrap();
+    let cached = cache.series("1".to_string()).await.unwrap();
+
+    assert!(!cached.stale);
+    assert_eq!(cached.value.series_id, "1");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn the_size_limit_evicts_the_least_recently_used_entry() {
+    let (source, calls) = counting_source("test");
+
+    let measure = CachedSource::new("test", &source, CachePolicy::default());
+    measure.series("1".to_string()).await.unwrap();
+    let entry_size = measure.size();
+
+    let policy = CachePolicy {
+        max_bytes: entry_size * 2 + entry_size / 2,
+        ..CachePolicy::default()
+    };
+    let cache = CachedSource::new("test", &source, policy);
+    for id in ["1", "2", "1", "3"] {
+        cache.series(id.to_string()).await.unwrap();
+    }
+    assert_eq!(cache.len(), 2);
+    assert!(cache.size() <= entry_size * 2 + entry_size / 2);
+
+    calls.store(0, Ordering::SeqCst);
+    cache.series("1".to_string()).await.unwrap();
+    cache.series("3".to_string()).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    cache.series("2".to_string()).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn invalidating_a_series_drops_its_chapters() {
+    let (source, calls) = counting_source("test");
+    let cache = CachedSource::new("test", &source, CachePolicy::default());
+
+    cache.series("a".to_string()).await.unwrap();
+    cache
+        .chapter(Some("a".to_string()), "1".to_string())
+        .await
+        .unwrap();
+    cache
+        .chapter(Some("a/b".to_string()), "1".to_string())
+        .await
+        .unwrap();
+
+    cache.invalidate_series("a");
+
+    assert_eq!(cache.len(), 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn saving_keeps_entries_of_other_sources() {
+    let path = temp_path("shared-cache.json");
+    let (first, _) = counting_source("first");
+    let (second, _) = counting_source("second");
+
+    let cache = CachedSource::new("first", &first, CachePolicy::default());
+    cache.series("1".to_string()).await.unwrap();
+    cache.save(&path).unwrap();
+
+    let cache = CachedSource::new("second", &second, CachePolicy::default());
+    cache.series("2".to_string()).await.unwrap();
+    cache.save(&path).unwrap();
+
+    for (source_id, source) in [("first", &first), ("second", &second)] {
+        let cache = CachedSource::new(source_id, source, CachePolicy::default());
+        cache.load(&path).unwrap();
+        assert_eq!(cache.len(), 1, "{} lost its entries", source_id);
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{Reply, Stub, chapter, png, series, serve, temp_path};
+use rrmounter::base::{ContentRating, LanguageKey};
+use rrmounter::export::{ComicInfo, PageImage, export_chapter_cbz, write_cbz};
+use rrmounter::generate::Series;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+fn one_piece() -> Series {
+    let mut series = series("test", "1", "One Piece");
+    series.description = "Pirates & <treasure>".to_string();
+    series.author = vec!["Eiichiro Oda".to_string()];
+    series.tags = vec!["Action".to_string(), "Adventure".to_string()];
+    series.content_rating = Some(ContentRating::Everyone);
+    series.year = Some(1997);
+    series
+}
+
+fn page(url: &str) -> PageImage {
+    PageImage {
+        url: url.to_string(),
+        data: vec![0x89, b'P', b'N', b'G', 1, 2, 3],
+        extension: "png",
+    }
+}
+
+#[test]
+fn manga_follows_the_original_language() {
+    let mut series = one_piece();
+    let chapter = chapter(&series, "1", "1");
+
+    let manga = |series: &Series| ComicInfo::from_chapter(series, &chapter).manga;
+    assert_eq!(manga(&series), Some("YesAndRightToLeft"));
+
+    series.original_language = LanguageKey::Korean;
+    assert_eq!(manga(&series), Some("Yes"));
+
+    series.original_language = LanguageKey::English;
+    assert_eq!(manga(&series), Some("Unknown"));
+}
+
+#[test]
+fn xml_round_trips() {
+    let series = one_piece();
+    let mut chapter = chapter(&series, "1", "1");
+    chapter.volume_number = "1".to_string();
+    chapter.group_name = "Group".to_string();
+
+    let info = ComicInfo::from_chapter(&series, &chapter)
+        .with_page_count(20)
+        .with_web("https://example.com/1");
+    let xml = info.to_xml();
+
+    assert!(xml.contains("<Summary>Pirates &amp; &lt;treasure&gt;</Summary>"));
+    assert!(xml.contains("<Manga>YesAndRightToLeft</Manga>"));
+    assert!(xml.contains("<AgeRating>Everyone</AgeRating>"));
+
+    let parsed = ComicInfo::parse(&xml);
+    assert_eq!(parsed.series, "One Piece");
+    assert_eq!(parsed.summary.as_deref(), Some("Pirates & <treasure>"));
+    assert_eq!(parsed.number.as_deref(), Some("1"));
+    assert_eq!(parsed.volume, Some(1));
+    assert_eq!(parsed.year, Some(1997));
+    assert_eq!(parsed.writer, ["Eiichiro Oda"]);
+    assert_eq!(parsed.genre, ["Action", "Adventure"]);
+    assert_eq!(parsed.translator.as_deref(), Some("Group"));
+    assert_eq!(parsed.page_count, Some(20));
+    assert_eq!(parsed.language_iso.as_deref(), Some("ja"));
+    assert_eq!(parsed.manga, Some("YesAndRightToLeft"));
+    assert_eq!(parsed.content_rating(), Some(ContentRating::Everyone));
+}
+
+#[test]
+fn cbz_holds_the_metadata_and_ordered_pages() {
+    let series = one_piece();
+    let info = ComicInfo::from_chapter(&series, &chapter(&series, "1", "1")).with_page_count(2);
+    let pages = [
+        page("https://example.com/1.png"),
+        page("https://example.com/2.png"),
+    ];
+
+    let cbz = write_cbz(Cursor::new(Vec::new()), &pages, &info).unwrap();
+    let mut archive = zip::ZipArchive::new(Cursor::new(cbz.into_inner())).unwrap();
+
+    let names: Vec<String> = (0..archive.len())
+        .map(|index| archive.by_index(index).unwrap().name().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["ComicInfo.xml", "001.png", "002.png"]);
+
+    let mut xml = String::new();
+    archive
+        .by_name("ComicInfo.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    assert_eq!(xml, info.to_xml());
+
+    let mut data = Vec::new();
+    let mut first = archive.by_name("001.png").unwrap();
+    assert_eq!(first.compression(), zip::CompressionMethod::Stored);
+    first.read_to_end(&mut data).unwrap();
+    assert_eq!(data, pages[0].data);
+}
+
+#[test]
+fn attributes_and_self_closing_elements_are_read() {
+    let xml = r#"<?xml version="1.0"?>
+<ComicInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <SeriesGroup>Shonen Jump</SeriesGroup>
+  <Series xml:lang="en">One Piece</Series>
+  <Title/>
+  <Summary lang="en" >Pirates &amp; treasure</Summary>
+  <Web />
+  <Writer>Eiichiro Oda</Writer >
+  <Manga/>
+  <AgeRating>Teen</AgeRating>
+</ComicInfo>"#;
+
+    let parsed = ComicInfo::parse(xml);
+
+    assert_eq!(parsed.series, "One Piece");
+    assert_eq!(parsed.title, None);
+    assert_eq!(parsed.summary.as_deref(), Some("Pirates & treasure"));
+    assert_eq!(parsed.web, None);
+    assert_eq!(parsed.writer, ["Eiichiro Oda"]);
+    assert_eq!(parsed.manga, None);
+    assert_eq!(parsed.content_rating(), Some(ContentRating::Mature));
+}
+
+#[tokio::test]
+async fn failed_exports_keep_the_previous_archive() {
+    let broken = Arc::new(AtomicBool::new(false));
+    let failing = broken.clone();
+    let (base_url, _) = serve(move |path, _| match path {
+        "/2.png" if failing.load(Ordering::SeqCst) => Reply::status("404 Not Found"),
+        _ => Reply::ok(png(800, 1200)),
+    })
+    .await;
+    let urls = vec![format!("{}/1.png", base_url), format!("{}/2.png", base_url)];
+    let source = Stub::default()
+        .with_chapter(move |_, _| {
+            let urls = urls.clone();
+            Box::pin(async move { Ok(urls) })
+        })
+        .bind();
+    let series = one_piece();
+    let chapter = chapter(&series, "1", "1");
+    let directory = temp_path("cbz-export");
+    let client = reqwest::Client::new();
+
+    let path = export_chapter_cbz(&source, &client, &series, &chapter, &directory)
+        .await
+        .unwrap();
+    let exported = std::fs::read(&path).unwrap();
+    let files = || std::fs::read_dir(&directory).unwrap().count();
+    assert_eq!(files(), 1);
+
+    broken.store(true, Ordering::SeqCst);
+    let result = export_chapter_cbz(&source, &client, &series, &chapter, &directory).await;
+
+    assert!(result.is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), exported);
+    assert_eq!(files(), 1);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,310 @@
+// Shared by several test crates, each of which only uses part of it
+#![allow(dead_code)]
+
+use rrmounter::FORMAT_VERSION;
+use rrmounter::base::{
+    HomeSection, LanguageKey, PaginatedResponse, PaginationRequest, SearchRequest,
+    SourceCapabilities, SourceInstance, StatefulSource,
+};
+use rrmounter::error::{AsyncSourceResult, SourceError, SourceResult};
+use rrmounter::generate::{Chapter, LatestUpdate, Series, SeriesEntry, Status};
+use std::sync::Arc;
+
+pub fn series(source_id: &str, series_id: &str, title: &str) -> Series {
+    Series {
+        format_version: FORMAT_VERSION,
+        source_id: source_id.to_string(),
+        series_id: series_id.to_string(),
+        title: title.to_string(),
+        alt_titles: Vec::new(),
+        description: String::new(),
+        status: Status::Ongoing,
+        cover_url: String::new(),
+        chapters: Vec::new(),
+        author: Vec::new(),
+        artist: Vec::new(),
+        tags: Vec::new(),
+        hentai: false,
+        original_language: LanguageKey::Japanese,
+        content_rating: None,
+        year: None,
+        number_unread: 0,
+        number_chapters: 0,
+    }
+}
+
+pub fn chapter(series: &Series, id: &str, number: &str) -> Chapter {
+    Chapter {
+        id: id.to_string(),
+        series_id: series.series_id.clone(),
+        source_id: series.source_id.clone(),
+        title: format!("Chapter {}", number),
+        chapter_number: number.to_string(),
+        volume_number: String::new(),
+        group_name: String::new(),
+        time: 0,
+        read: false,
+    }
+}
+
+pub fn entry(series: &Series) -> SeriesEntry {
+    SeriesEntry::new(
+        &series.source_id,
+        &series.series_id,
+        &series.title,
+        &series.cover_url,
+    )
+}
+
+pub fn page<T>(items: Vec<T>, current_page: u32, has_next_page: bool) -> PaginatedResponse<T> {
+    PaginatedResponse {
+        format_version: FORMAT_VERSION,
+        items,
+        current_page,
+        total_pages: None,
+        has_next_page,
+        total_items: None,
+    }
+}
+
+/// A source whose operations all fail, to be completed with the ones a test needs
+pub fn failing_source() -> SourceInstance {
+    fn unsupported<T>(operation: &str) -> SourceResult<T> {
+        Err(SourceError::UnsupportedOperation {
+            operation: operation.to_string(),
+            alternatives: Vec::new(),
+        })
+    }
+    SourceInstance::new(
+        || Box::pin(async { unsupported("home_page") }),
+        |_| Box::pin(async { unsupported("search") }),
+        |_| Box::pin(async { unsupported("series") }),
+        |_, _| Box::pin(async { unsupported("chapter") }),
+    )
+}
+
+type Operation<A, T> = Box<dyn Fn(A) -> AsyncSourceResult<T> + Send + Sync>;
+
+/// Series and chapter ids of a chapter request
+type ChapterRequest = (Option<String>, String);
+
+/// A bound source answering from closures, so a test can capture state.
+/// Operations without a closure fail like those of `failing_source`.
+#[derive(Default)]
+pub struct Stub {
+    search: Option<Operation<SearchRequest, PaginatedResponse<SeriesEntry>>>,
+    series: Option<Operation<String, Series>>,
+    chapter: Option<Operation<ChapterRequest, Vec<String>>>,
+    latest_updates: Option<Operation<PaginationRequest, PaginatedResponse<LatestUpdate>>>,
+}
+
+impl Stub {
+    pub fn with_search(
+        mut self,
+        search: impl Fn(SearchRequest) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.search = Some(Box::new(search));
+        self
+    }
+
+    pub fn with_series(
+        mut self,
+        series: impl Fn(String) -> AsyncSourceResult<Series> + Send + Sync + 'static,
+    ) -> Self {
+        self.series = Some(Box::new(series));
+        self
+    }
+
+    pub fn with_chapter(
+        mut self,
+        chapter: impl Fn(Option<String>, String) -> AsyncSourceResult<Vec<String>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.chapter = Some(Box::new(move |(series_id, chapter_id)| {
+            chapter(series_id, chapter_id)
+        }));
+        self
+    }
+
+    pub fn with_latest_updates(
+        mut self,
+        latest_updates: impl Fn(PaginationRequest) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.latest_updates = Some(Box::new(latest_updates));
+        self
+    }
+
+    pub fn bind(self) -> SourceInstance {
+        SourceInstance::bind(Arc::new(self)).unwrap()
+    }
+}
+
+impl StatefulSource for Stub {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        (failing_source().home_page)()
+    }
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        match &self.search {
+            Some(search) => search(request),
+            None => (failing_source().search)(request),
+        }
+    }
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series> {
+        match &self.series {
+            Some(series) => series(series_id),
+            None => (failing_source().series)(series_id),
+        }
+    }
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>> {
+        match &self.chapter {
+            Some(chapter) => chapter((series_id, chapter_id)),
+            None => (failing_source().chapter)(series_id, chapter_id),
+        }
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            latest_updates: self.latest_updates.is_some(),
+            ..SourceCapabilities::default()
+        }
+    }
+
+    fn latest_updates(
+        self: Arc<Self>,
+        pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<LatestUpdate>> {
+        match &self.latest_updates {
+            Some(latest_updates) => latest_updates(pagination),
+            None => failing_source().fetch_latest_updates(pagination),
+        }
+    }
+}
+
+/// A fresh path under the system temp directory, unique to this test process
+pub fn temp_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("rrmounter-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+/// A response of the test server
+pub struct Reply {
+    pub status: &'static str,
+    pub headers: String,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            headers: String::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: &'static str) -> Self {
+        Self {
+            status,
+            headers: String::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push_str(&format!("{}: {}\r\n", name, value));
+        self
+    }
+}
+
+/// Path and headers of every request the test server received
+pub type Requests = std::sync::Arc<std::sync::Mutex<Vec<(String, Vec<(String, String)>)>>>;
+
+/// Serve HTTP/1.1 on a local port, answering each request with `respond(path, headers)`
+pub async fn serve(
+    respond: impl Fn(&str, &[(String, String)]) -> Reply + Send + 'static,
+) -> (String, Requests) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+
+    let seen = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            let request = String::from_utf8_lossy(&request).into_owned();
+            let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+            let headers: Vec<(String, String)> = request
+                .lines()
+                .skip(1)
+                .filter_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    Some((name.trim().to_lowercase(), value.trim().to_string()))
+                })
+                .collect();
+            seen.lock().unwrap().push((path.clone(), headers.clone()));
+
+            let reply = respond(&path, &headers);
+            let head = format!(
+                "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                reply.status,
+                reply.headers,
+                reply.body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&reply.body).await.unwrap();
+        }
+    });
+
+    (base_url, requests)
+}
+
+/// How many requests the test server received for `path`
+pub fn requests_for(requests: &Requests, path: &str) -> usize {
+    requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(seen, _)| seen == path)
+        .count()
+}
+
+/// The smallest PNG header `image_dimensions` accepts
+pub fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 13];
+    data.extend_from_slice(b"IHDR");
+    data.extend_from_slice(&width.to_be_bytes());
+    data.extend_from_slice(&height.to_be_bytes());
+    data
+}
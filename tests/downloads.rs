@@ -0,0 +1,157 @@
+mod common;
+
+use common::{Reply, Stub, chapter, png, requests_for, series, serve, temp_path};
+use futures::StreamExt;
+use rrmounter::base::SourceInstance;
+use rrmounter::export::{DownloadEvent, DownloadManager, DownloadState};
+use rrmounter::library::ChapterKey;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A source whose chapter "1" has two pages served from `base_url`
+fn source(base_url: &str) -> SourceInstance {
+    let urls = vec![format!("{}/1.png", base_url), format!("{}/2.png", base_url)];
+    Stub::default()
+        .with_chapter(move |_, _| {
+            let urls = urls.clone();
+            Box::pin(async move { Ok(urls) })
+        })
+        .bind()
+}
+
+async fn run(manager: &DownloadManager<'_>) -> Vec<DownloadEvent> {
+    manager.run().collect().await
+}
+
+#[tokio::test]
+async fn completed_pages_carry_a_checksum() {
+    let (base_url, _) = serve(|_, _| Reply::ok(png(800, 1200))).await;
+    let source = source(&base_url);
+    let root = temp_path("downloads-checksum");
+    let series = series("test", "1", "Title");
+    let chapter = chapter(&series, "1", "1");
+
+    let manager = DownloadManager::open(&root, reqwest::Client::new())
+        .unwrap()
+        .with_source("test", &source);
+    manager.enqueue(&series, &chapter).unwrap();
+    let events = run(&manager).await;
+
+    assert!(matches!(
+        events.last(),
+        Some(DownloadEvent::Completed { .. })
+    ));
+    let task = manager.task(&ChapterKey::from(&chapter)).unwrap();
+    let expected = crc32fast::hash(&png(800, 1200));
+    assert!(
+        task.pages
+            .iter()
+            .all(|page| page.as_ref().unwrap().checksum == Some(expected))
+    );
+
+    // Page progress is in the saved queue once the chapter is done
+    let reopened = DownloadManager::open(&root, reqwest::Client::new()).unwrap();
+    assert_eq!(reopened.tasks()[0].saved_pages(), 2);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn damaged_and_non_image_pages_are_fetched_again() {
+    let serve_html = Arc::new(AtomicBool::new(true));
+    let broken = serve_html.clone();
+    let (base_url, requests) = serve(move |path, _| {
+        if path == "/2.png" && broken.load(Ordering::SeqCst) {
+            return Reply::ok("<html>Please log in</html>");
+        }
+        Reply::ok(png(800, 1200))
+    })
+    .await;
+    let source = source(&base_url);
+    let root = temp_path("downloads-damaged");
+    let series = series("test", "1", "Title");
+    let chapter = chapter(&series, "1", "1");
+    let key = ChapterKey::from(&chapter);
+
+    let manager = DownloadManager::open(&root, reqwest::Client::new())
+        .unwrap()
+        .with_source("test", &source)
+        .with_page_concurrency(1)
+        .with_max_retries(0);
+    manager.enqueue(&series, &chapter).unwrap();
+
+    // An HTML page served with 200 is not accepted as an image
+    let events = run(&manager).await;
+    assert!(matches!(events.last(), Some(DownloadEvent::Failed { .. })));
+    assert_eq!(manager.task(&key).unwrap().saved_pages(), 1);
+
+    // Same size, different bytes: only the checksum tells the page apart
+    let first_page = manager.chapter_directory(&key).unwrap().join("001.png");
+    let mut data = std::fs::read(&first_page).unwrap();
+    data[20] ^= 0xFF;
+    std::fs::write(&first_page, data).unwrap();
+
+    serve_html.store(false, Ordering::SeqCst);
+    manager.retry_failed().unwrap();
+    let events = run(&manager).await;
+
+    assert!(matches!(
+        events.last(),
+        Some(DownloadEvent::Completed { .. })
+    ));
+    assert!(matches!(
+        manager.task(&key).unwrap().state,
+        DownloadState::Completed { .. }
+    ));
+    assert_eq!(requests_for(&requests, "/1.png"), 2);
+    assert_eq!(requests_for(&requests, "/2.png"), 2);
+    assert_eq!(std::fs::read(&first_page).unwrap(), png(800, 1200));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn chapters_sharing_a_number_get_their_own_folder() {
+    let (base_url, _) = serve(|_, _| Reply::ok(png(800, 1200))).await;
+    let source = source(&base_url);
+    let root = temp_path("downloads-folders");
+    let namesake = series("test", "2", "Title");
+    let series = series("test", "1", "Title");
+    let mut first = chapter(&series, "1-a", "1");
+    first.group_name = "Group A".to_string();
+    let mut second = chapter(&series, "1-b", "1");
+    second.group_name = "Group B".to_string();
+    let third = chapter(&namesake, "1-a", "1");
+
+    let manager = DownloadManager::open(&root, reqwest::Client::new())
+        .unwrap()
+        .with_source("test", &source);
+    for (series, chapter) in [(&series, &first), (&series, &second), (&namesake, &third)] {
+        assert!(manager.enqueue(series, chapter).unwrap());
+    }
+    let events = run(&manager).await;
+
+    let completed = events
+        .iter()
+        .filter(|event| matches!(event, DownloadEvent::Completed { .. }))
+        .count();
+    assert_eq!(completed, 3);
+    let mut directories: Vec<_> = [&first, &second, &third]
+        .into_iter()
+        .map(|chapter| {
+            manager
+                .chapter_directory(&ChapterKey::from(chapter))
+                .unwrap()
+        })
+        .collect();
+    assert!(
+        directories
+            .iter()
+            .all(|directory| directory.join("002.png").is_file())
+    );
+    directories.sort();
+    directories.dedup();
+    assert_eq!(directories.len(), 3);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::{Reply, Stub, chapter, png, series, serve, temp_path};
+use rrmounter::export::{EpubChapter, EpubOptions, PageImage, export_series_epub, write_epub};
+use rrmounter::generate::Series;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use zip::{CompressionMethod, ZipArchive};
+
+fn one_piece() -> Series {
+    let mut series = series("test", "op", "One Piece");
+    series.author = vec!["Eiichiro Oda".to_string()];
+    series
+}
+
+fn image(extension: &'static str) -> PageImage {
+    PageImage {
+        url: format!("https://example.com/page.{}", extension),
+        data: png(800, 1200),
+        extension,
+    }
+}
+
+fn epub_chapter(series: &Series, id: &str, pages: Vec<PageImage>) -> EpubChapter {
+    EpubChapter {
+        chapter: chapter(series, id, id),
+        pages,
+    }
+}
+
+/// Write a book and open it again as a ZIP archive
+fn book(series: &Series, chapters: &[EpubChapter]) -> ZipArchive<Cursor<Vec<u8>>> {
+    let written = write_epub(
+        Cursor::new(Vec::new()),
+        series,
+        Some(&image("png")),
+        chapters,
+        &EpubOptions::default().with_right_to_left(true),
+    )
+    .unwrap();
+    ZipArchive::new(Cursor::new(written.into_inner())).unwrap()
+}
+
+fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+/// Every value of `attribute` in `xml`, in document order
+fn attributes(xml: &str, attribute: &str) -> Vec<String> {
+    let marker = format!("{}=\"", attribute);
+    xml.split(&marker)
+        .skip(1)
+        .map(|rest| rest[..rest.find('"').unwrap()].to_string())
+        .collect()
+}
+
+#[test]
+fn the_mimetype_comes_first_uncompressed() {
+    let series = one_piece();
+    let mut archive = book(&series, &[epub_chapter(&series, "1", vec![image("png")])]);
+
+    let mut first = archive.by_index(0).unwrap();
+    assert_eq!(first.name().unwrap(), "mimetype");
+    assert_eq!(first.compression(), CompressionMethod::Stored);
+    let mut mimetype = String::new();
+    first.read_to_string(&mut mimetype).unwrap();
+    assert_eq!(mimetype, "application/epub+zip");
+}
+
+#[test]
+fn the_manifest_lists_every_page() {
+    let series = one_piece();
+    let chapters = [
+        epub_chapter(&series, "1", vec![image("png"), image("jpg")]),
+        epub_chapter(&series, "2", vec![image("webp")]),
+    ];
+    let mut archive = book(&series, &chapters);
+    let opf = read(&mut archive, "OEBPS/content.opf");
+
+    let listed = attributes(&opf, "href");
+    let stored: Vec<String> = archive
+        .file_names()
+        .map(|name| name.unwrap().into_owned())
+        .filter_map(|name| name.strip_prefix("OEBPS/").map(str::to_string))
+        .filter(|name| name != "content.opf")
+        .collect();
+
+    // Cover plus three pages, each an image and a page document, plus the nav
+    assert_eq!(listed.len(), 9);
+    for name in &stored {
+        assert!(listed.contains(name), "{} is not in the manifest", name);
+    }
+    assert!(listed.contains(&"images/c002_p001.webp".to_string()));
+}
+
+#[test]
+fn the_spine_follows_chapter_order() {
+    let series = one_piece();
+    let chapters = [
+        epub_chapter(&series, "10", vec![image("png"), image("png")]),
+        epub_chapter(&series, "2", vec![image("png")]),
+    ];
+    let mut archive = book(&series, &chapters);
+    let opf = read(&mut archive, "OEBPS/content.opf");
+    let spine = &opf[opf.find("<spine").unwrap()..];
+
+    assert!(spine.starts_with("<spine page-progression-direction=\"rtl\">"));
+    assert_eq!(
+        attributes(spine, "idref"),
+        [
+            "page-cover",
+            "page-c001_p001",
+            "page-c001_p002",
+            "page-c002_p001"
+        ]
+    );
+
+    let nav = read(&mut archive, "OEBPS/nav.xhtml");
+    assert!(nav.find("Ch. 10").unwrap() < nav.find("Ch. 2").unwrap());
+}
+
+#[test]
+fn avif_pages_fall_back_to_text() {
+    let series = one_piece();
+    let mut archive = book(
+        &series,
+        &[epub_chapter(
+            &series,
+            "1",
+            vec![image("avif"), image("png")],
+        )],
+    );
+
+    let avif = read(&mut archive, "OEBPS/pages/c001_p001.xhtml");
+    assert!(avif.contains("<object data=\"../images/c001_p001.avif\" type=\"image/avif\"><p>"));
+    assert!(!avif.contains("<img"));
+
+    let png = read(&mut archive, "OEBPS/pages/c001_p002.xhtml");
+    assert!(png.contains("<img src=\"../images/c001_p002.png\""));
+}
+
+#[test]
+fn identifiers_follow_the_selected_chapters() {
+    let series = one_piece();
+    let identifier = |ids: &[&str]| {
+        let chapters: Vec<EpubChapter> = ids
+            .iter()
+            .map(|id| epub_chapter(&series, id, vec![image("png")]))
+            .collect();
+        let opf = read(&mut book(&series, &chapters), "OEBPS/content.opf");
+        let start = opf.find("<dc:identifier id=\"book-id\">").unwrap();
+        let end = opf.find("</dc:identifier>").unwrap();
+        opf[start..end].to_string()
+    };
+
+    assert!(identifier(&["1"]).contains("urn:rrmounter:test:op:"));
+    assert_eq!(identifier(&["1", "2"]), identifier(&["1", "2"]));
+    assert_ne!(identifier(&["1", "2"]), identifier(&["1"]));
+    assert_ne!(identifier(&["1", "2"]), identifier(&["2", "1"]));
+}
+
+#[tokio::test]
+async fn failed_exports_keep_the_previous_book() {
+    let broken = Arc::new(AtomicBool::new(false));
+    let failing = broken.clone();
+    let (base_url, _) = serve(move |path, _| match path {
+        "/2.png" if failing.load(Ordering::SeqCst) => Reply::status("404 Not Found"),
+        _ => Reply::ok(png(800, 1200)),
+    })
+    .await;
+    let urls = vec![format!("{}/1.png", base_url), format!("{}/2.png", base_url)];
+    let source = Stub::default()
+        .with_chapter(move |_, _| {
+            let urls = urls.clone();
+            Box::pin(async move { Ok(urls) })
+        })
+        .bind();
+    let series = one_piece();
+    let chapters = [chapter(&series, "1", "1"), chapter(&series, "2", "2")];
+    let directory = temp_path("epub-export");
+    let client = reqwest::Client::new();
+    let options = EpubOptions::default();
+    let export = || export_series_epub(&source, &client, &series, &chapters, &directory, &options);
+
+    let path = export().await.unwrap();
+    let exported = std::fs::read(&path).unwrap();
+    let files = || std::fs::read_dir(&directory).unwrap().count();
+    assert_eq!(files(), 1);
+
+    broken.store(true, Ordering::SeqCst);
+    assert!(export().await.is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), exported);
+    assert_eq!(files(), 1);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
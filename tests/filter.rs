@@ -0,0 +1,230 @@
+mod common;
+
+use common::{Stub, entry, page, series};
+use rrmounter::SourceError;
+use rrmounter::base::{
+    ClientFilter, ContentRating, FilterKind, LanguageKey, SearchFilters, SearchRequest,
+    SourceInstance, search_with_fallback,
+};
+use rrmounter::generate::Series;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Ten series per page over three pages; even ids are tagged "action", id 3 fails to load
+fn catalog() -> Arc<HashMap<String, Series>> {
+    let catalog = (0..30)
+        .map(|id| {
+            let mut series = series("test", &id.to_string(), &format!("Series {}", id));
+            if id % 2 == 0 {
+                series.tags.push("Action".to_string());
+            }
+            series.content_rating = Some(if id % 5 == 0 {
+                ContentRating::Adult
+            } else {
+                ContentRating::Everyone
+            });
+            (series.series_id.clone(), series)
+        })
+        .collect();
+    Arc::new(catalog)
+}
+
+fn catalog_source() -> SourceInstance {
+    catalog_source_failing(|id| id == "3")
+}
+
+/// `catalog_source`, failing to load the details of the series `fails` picks
+fn catalog_source_failing(fails: fn(&str) -> bool) -> SourceInstance {
+    let catalog = catalog();
+    let pages = catalog.clone();
+
+    Stub::default()
+        .with_search(move |request: SearchRequest| {
+            let page_number = request.pagination.page;
+            let items = (0..10)
+                .map(|i| ((page_number - 1) * 10 + i).to_string())
+                .filter_map(|id| pages.get(&id).map(entry))
+                .collect();
+            Box::pin(async move { Ok(page(items, page_number, page_number < 3)) })
+        })
+        .with_series(move |series_id: String| {
+            let series = catalog.get(&series_id).cloned();
+            Box::pin(async move {
+                match series {
+                    Some(series) if !fails(&series.series_id) => Ok(series),
+                    _ => Err(SourceError::network("offline", true)),
+                }
+            })
+        })
+        .bind()
+}
+
+fn request(filters: SearchFilters) -> SearchRequest {
+    let mut request = SearchRequest::simple(String::new());
+    request.filters = filters;
+    request.pagination.per_page = 10;
+    request
+}
+
+fn ids(items: &[rrmounter::generate::SeriesEntry]) -> Vec<&str> {
+    items.iter().map(|entry| entry.series_id.as_str()).collect()
+}
+
+#[tokio::test]
+async fn inactive_filters_leave_the_page_alone() {
+    let source = catalog_source();
+    let filter = ClientFilter::new(SearchFilters::default(), &FilterKind::ALL);
+    let response = (source.search)(request(SearchFilters::default()))
+        .await
+        .unwrap();
+
+    assert!(!filter.is_active());
+    let filtered = filter.apply(&source, response.clone()).await.unwrap();
+    assert_eq!(filtered.items.len(), response.items.len());
+}
+
+#[tokio::test]
+async fn failed_details_are_dropped() {
+    let source = catalog_source();
+    let filters = SearchFilters {
+        content_rating: Some(ContentRating::Everyone),
+        ..SearchFilters::default()
+    };
+    let filter = ClientFilter::new(filters.clone(), &[FilterKind::ContentRating]);
+    let response = (source.search)(request(filters)).await.unwrap();
+
+    let filtered = filter.apply(&source, response).await.unwrap();
+
+    // 0 and 5 are adult, 3 could not be checked
+    assert_eq!(ids(&filtered.items), ["1", "2", "4", "6", "7", "8", "9"]);
+    assert_eq!(filtered.total_items, None);
+}
+
+#[tokio::test]
+async fn a_page_that_cannot_be_checked_fails() {
+    let mut source = catalog_source();
+    source.series = |_| Box::pin(async { Err(SourceError::network("offline", true)) });
+    let filters = SearchFilters {
+        genres: Some(vec!["action".to_string()]),
+        ..SearchFilters::default()
+    };
+    let filter = ClientFilter::new(filters.clone(), &[FilterKind::Genres]);
+    let response = (source.search)(request(filters)).await.unwrap();
+
+    let result = filter.apply(&source, response).await;
+
+    assert!(matches!(result, Err(SourceError::NetworkError { .. })));
+}
+
+#[tokio::test]
+async fn shrunk_pages_are_refilled_from_the_next_ones() {
+    let source = catalog_source();
+    let filters = SearchFilters {
+        genres: Some(vec!["action".to_string()]),
+        ..SearchFilters::default()
+    };
+
+    let response = search_with_fallback(&source, request(filters), &[FilterKind::Genres])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        ids(&response.items),
+        ["0", "2", "4", "6", "8", "10", "12", "14", "16", "18"]
+    );
+    assert_eq!(response.current_page, 2);
+    assert!(response.has_next_page);
+}
+
+#[tokio::test]
+async fn supported_filters_are_left_to_the_source() {
+    let source = catalog_source();
+    let filters = SearchFilters {
+        genres: Some(vec!["action".to_string()]),
+        ..SearchFilters::default()
+    };
+
+    let response = search_with_fallback(&source, request(filters), &[FilterKind::Status])
+        .await
+        .unwrap();
+
+    assert_eq!(response.items.len(), 10);
+    assert_eq!(response.current_page, 1);
+}
+
+#[tokio::test]
+async fn refilled_pages_are_cut_to_per_page() {
+    let source = catalog_source();
+    let filters = SearchFilters {
+        content_rating: Some(ContentRating::Everyone),
+        ..SearchFilters::default()
+    };
+
+    let response = search_with_fallback(&source, request(filters), &[FilterKind::ContentRating])
+        .await
+        .unwrap();
+
+    // Page 1 keeps 7 entries and page 2 adds 8 more
+    assert_eq!(
+        ids(&response.items),
+        ["1", "2", "4", "6", "7", "8", "9", "11", "12", "13"]
+    );
+    assert_eq!(response.current_page, 2);
+}
+
+#[tokio::test]
+async fn unchecked_refill_pages_keep_the_partial_results() {
+    let source = catalog_source_failing(|id| id.len() > 1);
+    let filters = SearchFilters {
+        genres: Some(vec!["action".to_string()]),
+        ..SearchFilters::default()
+    };
+
+    let response = search_with_fallback(&source, request(filters), &[FilterKind::Genres])
+        .await
+        .unwrap();
+
+    // No detail of page 2 loads, so the next search starts over from there
+    assert_eq!(ids(&response.items), ["0", "2", "4", "6", "8"]);
+    assert_eq!(response.current_page, 1);
+    assert!(response.has_next_page);
+}
+
+#[tokio::test]
+async fn listed_languages_are_checked_without_details() {
+    let details = Arc::new(AtomicUsize::new(0));
+    let counter = details.clone();
+    let source = Stub::default()
+        .with_search(|request: SearchRequest| {
+            let items = (0..4)
+                .map(|id| {
+                    let language = match id {
+                        0 | 1 => vec![LanguageKey::Japanese, LanguageKey::English],
+                        2 => vec![LanguageKey::Korean],
+                        _ => Vec::new(),
+                    };
+                    entry(&series("test", &id.to_string(), "Title")).with_languages(language)
+                })
+                .collect();
+            let page_number = request.pagination.page;
+            Box::pin(async move { Ok(page(items, page_number, false)) })
+        })
+        .with_series(move |series_id: String| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(series("test", &series_id, "Title")) })
+        })
+        .bind();
+    let filters = SearchFilters {
+        language: Some(LanguageKey::English),
+        ..SearchFilters::default()
+    };
+
+    let response = search_with_fallback(&source, request(filters), &[FilterKind::Language])
+        .await
+        .unwrap();
+
+    // Only entry 3 lists no language, and its series is in Japanese
+    assert_eq!(ids(&response.items), ["0", "1"]);
+    assert_eq!(details.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{entry, failing_source, series};
+use futures::StreamExt;
+use rrmounter::SourceError;
+use rrmounter::base::{HomePageEvent, HomeSection, HomeSectionType, load_home_page};
+
+fn filled(id: &str) -> HomeSection {
+    HomeSection::new(id, id, HomeSectionType::SingleRowNormal)
+        .with_entries(vec![entry(&series("test", id, "Title"))])
+}
+
+fn skeleton(id: &str) -> HomeSection {
+    HomeSection::skeleton(id, id, HomeSectionType::SingleRowNormal)
+}
+
+fn describe(events: Vec<HomePageEvent>) -> Vec<String> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            HomePageEvent::Skeletons(sections) => format!("skeletons:{}", sections.len()),
+            HomePageEvent::Loaded(section) => format!("loaded:{}", section.id),
+            HomePageEvent::SectionFailed { section_id, .. } => format!("failed:{}", section_id),
+            HomePageEvent::Failed(_) => "failed".to_string(),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn lazy_sources_resolve_every_skeleton() {
+    let mut source = failing_source().with_lazy_home_page(
+        || Box::pin(async { Ok(vec![skeleton("a"), filled("b")]) }),
+        |section_id| Box::pin(async move { Ok(filled(&section_id)) }),
+    );
+    source.home_page = || Box::pin(async { Ok(vec![filled("eager")]) });
+
+    let events = load_home_page(&source).collect::<Vec<_>>().await;
+
+    assert_eq!(describe(events), ["skeletons:2", "loaded:a"]);
+}
+
+#[tokio::test]
+async fn half_configured_sources_use_the_home_page() {
+    let mut source = failing_source().with_lazy_home_page(
+        || Box::pin(async { Ok(vec![skeleton("a")]) }),
+        |section_id| Box::pin(async move { Ok(filled(&section_id)) }),
+    );
+    source.home_section = None;
+    source.home_page = || Box::pin(async { Ok(vec![filled("eager")]) });
+
+    let events = load_home_page(&source).collect::<Vec<_>>().await;
+
+    match &events[..] {
+        [HomePageEvent::Skeletons(sections)] => assert_eq!(sections[0].id, "eager"),
+        other => panic!("unexpected events {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn empty_sections_are_not_skeletons() {
+    let source = failing_source().with_lazy_home_page(
+        || {
+            let empty = HomeSection::new("history", "History", HomeSectionType::SingleRowNormal);
+            Box::pin(async move { Ok(vec![empty, skeleton("a")]) })
+        },
+        |section_id| Box::pin(async move { Ok(filled(&section_id)) }),
+    );
+
+    let events = load_home_page(&source).collect::<Vec<_>>().await;
+
+    assert_eq!(describe(events), ["skeletons:2", "loaded:a"]);
+}
+
+#[tokio::test]
+async fn failed_sections_do_not_stop_the_others() {
+    let source = failing_source().with_lazy_home_page(
+        || Box::pin(async { Ok(vec![skeleton("broken"), skeleton("a")]) }),
+        |section_id| {
+            Box::pin(async move {
+                match section_id.as_str() {
+                    "broken" => Err(SourceError::network("offline", true)),
+                    _ => Ok(filled(&section_id)),
+                }
+            })
+        },
+    );
+
+    let mut events = describe(load_home_page(&source).collect::<Vec<_>>().await);
+    events[1..].sort();
+
+    assert_eq!(events, ["skeletons:2", "failed:broken", "loaded:a"]);
+}
+
+#[tokio::test]
+async fn home_pages_load_on_other_tasks() {
+    let source = failing_source().with_lazy_home_page(
+        || Box::pin(async { Ok(vec![skeleton("a")]) }),
+        |section_id| Box::pin(async move { Ok(filled(&section_id)) }),
+    );
+    let events = load_home_page(&source).collect::<Vec<_>>();
+
+    let events = tokio::spawn(events).await.unwrap();
+
+    assert_eq!(describe(events), ["skeletons:1", "loaded:a"]);
+}
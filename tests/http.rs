@@ -0,0 +1,91 @@
+mod common;
+
+use common::{Reply, Requests, serve};
+use rrmounter::SourceError;
+use rrmounter::base::HttpClient;
+
+/// 200 with an ETag, then 304 whenever the client revalidates
+fn revalidating(path: &str, headers: &[(String, String)]) -> Reply {
+    match if_none_match(headers) {
+        Some(_) => Reply::status("304 Not Modified"),
+        None => Reply::ok(path).with_header("ETag", "\"v1\""),
+    }
+}
+
+fn if_none_match(headers: &[(String, String)]) -> Option<&str> {
+    headers
+        .iter()
+        .find(|(name, _)| name == "if-none-match")
+        .map(|(_, value)| value.as_str())
+}
+
+fn revalidated(requests: &Requests, path: &str) -> bool {
+    let requests = requests.lock().unwrap();
+    let (last_path, headers) = requests.last().unwrap();
+    assert_eq!(last_path, path);
+    if_none_match(headers).is_some()
+}
+
+#[tokio::test]
+async fn not_modified_reuses_the_stored_body() {
+    let (base_url, requests) = serve(revalidating).await;
+    let client = HttpClient::default();
+    let url = format!("{}/a", base_url);
+
+    let first = client.get(&url).await.unwrap();
+    let second = client.get(&url).await.unwrap();
+
+    assert!(!first.from_cache);
+    assert!(second.from_cache);
+    assert_eq!(second.status, 304);
+    assert_eq!(second.text(), "/a");
+    assert!(revalidated(&requests, "/a"));
+}
+
+#[tokio::test]
+async fn rate_limits_fail_unless_stale_bodies_are_enabled() {
+    let rate_limited = |path: &str, headers: &[(String, String)]| match if_none_match(headers) {
+        Some(_) => Reply::status("429 Too Many Requests").with_header("Retry-After", "7"),
+        None => Reply::ok(path).with_header("ETag", "\"v1\""),
+    };
+    let (base_url, _) = serve(rate_limited).await;
+    let url = format!("{}/a", base_url);
+
+    let client = HttpClient::default();
+    client.get(&url).await.unwrap();
+    let error = client.get(&url).await.unwrap_err();
+    assert!(matches!(
+        error,
+        SourceError::RateLimit { retry_after: 7, .. }
+    ));
+
+    let client = HttpClient::default().with_serve_stale_on_rate_limit(true);
+    client.get(&url).await.unwrap();
+    let stale = client.get(&url).await.unwrap();
+    assert!(stale.from_cache);
+    assert_eq!(stale.text(), "/a");
+}
+
+#[tokio::test]
+async fn the_least_recently_used_body_is_evicted() {
+    let (base_url, requests) = serve(revalidating).await;
+    let client = HttpClient::default().with_max_entries(2);
+    let get = |path: &str| {
+        let url = format!("{}{}", base_url, path);
+        let client = client.clone();
+        async move { client.get(&url).await.unwrap() }
+    };
+
+    get("/a").await;
+    get("/b").await;
+    // Using "/a" again makes "/b" the least recently used entry
+    get("/a").await;
+    get("/c").await;
+
+    get("/a").await;
+    assert!(revalidated(&requests, "/a"));
+    get("/c").await;
+    assert!(revalidated(&requests, "/c"));
+    get("/b").await;
+    assert!(!revalidated(&requests, "/b"));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{chapter, series, temp_path};
+use rrmounter::generate::Series;
+use rrmounter::library::{ChapterKey, Library};
+
+/// A series whose chapters are listed newest first, like most sources do
+fn numbered(numbers: &[&str]) -> Series {
+    let mut series = series("test", "1", "Title");
+    series.chapters = numbers
+        .iter()
+        .rev()
+        .map(|number| chapter(&series, &format!("c{}", number), number))
+        .collect();
+    series
+}
+
+fn key(chapter_id: &str) -> ChapterKey {
+    ChapterKey::new("test", "1", chapter_id)
+}
+
+fn read_ids(library: &Library, series: &Series) -> Vec<String> {
+    let mut ids: Vec<String> = series
+        .chapters
+        .iter()
+        .filter(|chapter| library.is_read(&ChapterKey::from(*chapter)))
+        .map(|chapter| chapter.id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn chapters_up_to_a_number_are_marked_read() {
+    let series = numbered(&["1", "2", "2.5", "3", "10"]);
+    let mut library = Library::new();
+
+    library.mark_read_until(&series, "c2.5");
+
+    assert_eq!(read_ids(&library, &series), ["c1", "c2", "c2.5"]);
+    assert_eq!(library.number_unread(&series), 2);
+}
+
+#[test]
+fn unnumbered_chapters_are_marked_read_in_listing_order() {
+    let mut series = numbered(&["1", "2", "3"]);
+    series.chapters.reverse();
+    for (chapter, number) in series
+        .chapters
+        .iter_mut()
+        .zip(["Prologue", "Extra", "Finale"])
+    {
+        chapter.chapter_number = number.to_string();
+    }
+    let mut library = Library::new();
+
+    library.mark_read_until(&series, "c2");
+    assert_eq!(read_ids(&library, &series), ["c1", "c2"]);
+
+    library.mark_read_until(&series, "missing");
+    assert_eq!(library.number_unread(&series), 1);
+}
+
+#[test]
+fn the_last_page_finishes_a_chapter() {
+    let mut library = Library::new();
+
+    library.set_last_page(key("c1"), 3, Some(10));
+    let progress = library.progress(&key("c1")).unwrap();
+    assert!(!progress.read && progress.completed_at.is_none());
+    assert_eq!(progress.last_page_read, 3);
+    assert!(progress.last_read_at.is_some());
+
+    // The page count is kept when the reader no longer reports it
+    library.set_last_page(key("c1"), 9, None);
+    let progress = library.progress(&key("c1")).unwrap();
+    assert_eq!(progress.total_pages, Some(10));
+    assert!(progress.read && progress.completed_at.is_some());
+
+    // Without a page count the chapter is never finished by reading
+    library.set_last_page(key("c2"), 50, None);
+    assert!(!library.is_read(&key("c2")));
+}
+
+#[test]
+fn unread_chapters_lose_their_completion() {
+    let mut library = Library::new();
+    library.set_last_page(key("c1"), 9, Some(10));
+
+    library.mark_unread(&key("c1"));
+
+    let progress = library.progress(&key("c1")).unwrap();
+    assert!(!progress.read);
+    assert_eq!(progress.last_page_read, 0);
+    assert_eq!(progress.completed_at, None);
+}
+
+#[test]
+fn library_state_overrides_the_source() {
+    let mut series = numbered(&["1", "2", "3"]);
+    series.chapters[0].read = true;
+    series.number_unread = 0;
+    let mut library = Library::new();
+    library.mark_read(key("c1"));
+
+    library.apply_to(&mut series);
+
+    let read: Vec<bool> = series.chapters.iter().map(|chapter| chapter.read).collect();
+    assert_eq!(read, [false, false, true]);
+    assert_eq!(series.number_unread, 2);
+}
+
+#[test]
+fn bookmarks_are_kept_per_page_and_series() {
+    let mut library = Library::new();
+    library.add_bookmark(key("c1"), 4, None);
+    library.add_bookmark(key("c1"), 2, Some("first".to_string()));
+    library.add_bookmark(key("c1"), 4, Some("replaced".to_string()));
+    library.add_bookmark(key("c2"), 0, None);
+    library.add_bookmark(ChapterKey::new("test", "2", "c1"), 1, None);
+
+    let mut bookmarks: Vec<(String, u32, Option<String>)> = library
+        .bookmarks("test", "1")
+        .into_iter()
+        .map(|(key, bookmark)| (key.chapter_id.clone(), bookmark.page, bookmark.note.clone()))
+        .collect();
+    bookmarks.sort();
+    assert_eq!(
+        bookmarks,
+        [
+            ("c1".to_string(), 2, Some("first".to_string())),
+            ("c1".to_string(), 4, Some("replaced".to_string())),
+            ("c2".to_string(), 0, None),
+        ]
+    );
+
+    library.remove_bookmark(&key("c1"), 2);
+    assert_eq!(library.bookmarks("test", "1").len(), 2);
+}
+
+#[test]
+fn the_latest_opened_chapter_is_last_read() {
+    let library: Library = serde_json::from_value(serde_json::json!({
+        "formatVersion": 2,
+        "entries": [],
+        "progress": [
+            { "sourceId": "test", "seriesId": "1", "chapterId": "c1", "read": true,
+              "lastPageRead": 0, "lastReadAt": 100, "bookmarks": [] },
+            { "sourceId": "test", "seriesId": "1", "chapterId": "c2", "read": false,
+              "lastPageRead": 4, "lastReadAt": 300, "bookmarks": [] },
+            { "sourceId": "test", "seriesId": "1", "chapterId": "c3", "read": true,
+              "lastPageRead": 0, "bookmarks": [] },
+            { "sourceId": "test", "seriesId": "2", "chapterId": "c1", "read": false,
+              "lastPageRead": 1, "lastReadAt": 900, "bookmarks": [] }
+        ]
+    }))
+    .unwrap();
+
+    let (key, progress) = library.last_read("test", "1").unwrap();
+    assert_eq!(key.chapter_id, "c2");
+    assert_eq!(progress.last_page_read, 4);
+    assert!(library.last_read("test", "3").is_none());
+}
+
+#[test]
+fn saves_replace_the_file_without_leftovers() {
+    let directory = temp_path("library");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("library.json");
+    std::fs::write(&path, "previous").unwrap();
+    let mut library = Library::new();
+    library.add(&numbered(&["1"]));
+    library.mark_read(key("c1"));
+
+    library.save(&path).unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&directory).unwrap().collect();
+    assert_eq!(files.len(), 1);
+    let loaded = Library::load(&path).unwrap();
+    assert!(loaded.contains("test", "1") && loaded.is_read(&key("c1")));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
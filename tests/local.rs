@@ -0,0 +1,155 @@
+mod common;
+
+use common::{png, temp_path};
+use rrmounter::base::{SearchRequest, SourceInstance};
+use rrmounter::export::download_page;
+use rrmounter::local::LocalSource;
+use std::io::Write;
+use std::path::Path;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+/// A library with a folder chapter, an archive chapter and a series without chapters
+fn library(name: &str) -> std::path::PathBuf {
+    let root = temp_path(name);
+    let folder = root.join("One Piece").join("Chapter 1");
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("2.png"), png(800, 1200)).unwrap();
+    std::fs::write(folder.join("10.png"), png(800, 1200)).unwrap();
+    std::fs::write(
+        root.join("One Piece").join("series.json"),
+        r#"{"title": "One Piece", "altTitles": ["ワンピース"], "tags": ["Action"]}"#,
+    )
+    .unwrap();
+
+    let berserk = root.join("Berserk");
+    std::fs::create_dir_all(&berserk).unwrap();
+    let mut archive = ZipWriter::new(std::fs::File::create(berserk.join("Vol 1.cbz")).unwrap());
+    for name in ["001.png", "002.png"] {
+        archive
+            .start_file(name, SimpleFileOptions::default())
+            .unwrap();
+        archive.write_all(&png(600, 900)).unwrap();
+    }
+    archive.finish().unwrap();
+
+    std::fs::create_dir_all(root.join("Empty")).unwrap();
+    root
+}
+
+fn local(root: &Path) -> LocalSource {
+    LocalSource::new(root).with_cache_dir(root.join(".cache"))
+}
+
+fn instance(root: &Path) -> SourceInstance {
+    local(root).into_instance().unwrap()
+}
+
+#[tokio::test]
+async fn series_and_pages_are_read_from_disk() {
+    let root = library("local-series");
+    let source = instance(&root);
+
+    let series = (source.series)("One Piece".to_string()).await.unwrap();
+    assert_eq!(series.alt_titles, ["ワンピース"]);
+    assert_eq!(series.number_chapters, 1);
+    assert_eq!(series.chapters[0].chapter_number, "1");
+
+    let pages = (source.chapter)(Some("One Piece".to_string()), "Chapter 1".to_string())
+        .await
+        .unwrap();
+    assert!(pages[0].ends_with("/2.png") && pages[1].ends_with("/10.png"));
+
+    let pages = (source.chapter)(Some("Berserk".to_string()), "Vol 1.cbz".to_string())
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 2);
+    assert!(pages.iter().all(|page| page.contains("/.cache/")));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn search_and_home_skip_series_without_chapters() {
+    let root = library("local-search");
+    let local = local(&root);
+    assert_eq!(local.series_ids().unwrap(), ["Berserk", "One Piece"]);
+    let source = local.into_instance().unwrap();
+
+    let results = (source.search)(SearchRequest::simple("one piece".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(results.items.len(), 1);
+    assert_eq!(results.items[0].series_id, "One Piece");
+
+    let home = (source.home_page)().await.unwrap();
+    let library: Vec<&str> = home[1]
+        .entries
+        .iter()
+        .map(|entry| entry.series_id.as_str())
+        .collect();
+    assert_eq!(library, ["Berserk", "One Piece"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn summaries_follow_sidecar_changes() {
+    let root = library("local-sidecar");
+    let source = instance(&root);
+    let search = |query: &str| (source.search)(SearchRequest::simple(query.to_string()));
+    assert_eq!(search("berserk").await.unwrap().items.len(), 1);
+
+    std::fs::write(
+        root.join("Berserk").join("series.json"),
+        r#"{"title": "Berserk Deluxe"}"#,
+    )
+    .unwrap();
+
+    let results = search("berserk deluxe").await.unwrap();
+    assert_eq!(results.items[0].title, "Berserk Deluxe");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn pages_are_read_only_from_the_library() {
+    let root = library("local-read");
+    let local = local(&root);
+    let outside = temp_path("local-outside.png");
+    std::fs::write(&outside, png(10, 10)).unwrap();
+    let outside_url = url::Url::from_file_path(&outside).unwrap().to_string();
+
+    let pages = local.pages("Berserk", "Vol 1.cbz").unwrap();
+    let page = local.read_page(&pages[0]).unwrap();
+    assert_eq!(page.data, png(600, 900));
+    assert_eq!(page.extension, "png");
+
+    assert!(local.read_page(&outside_url).is_err());
+    let root_url = url::Url::from_file_path(&root).unwrap();
+    let escaped = format!(
+        "{}/../{}",
+        root_url,
+        outside.file_name().unwrap().to_str().unwrap()
+    );
+    assert!(local.read_page(&escaped).is_err());
+
+    // The shared download path never touches the filesystem
+    let client = reqwest::Client::new();
+    assert!(download_page(&client, &pages[0]).await.is_err());
+    assert!(download_page(&client, &outside_url).await.is_err());
+
+    std::fs::remove_file(outside).unwrap();
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn each_root_gets_its_own_cache() {
+    let first = LocalSource::new("/library/a/manga");
+    let second = LocalSource::new("/library/b/manga");
+
+    assert_ne!(first.cache_dir(), second.cache_dir());
+    assert_eq!(
+        first.cache_dir(),
+        LocalSource::new("/library/a/manga").cache_dir()
+    );
+}
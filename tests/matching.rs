@@ -0,0 +1,131 @@
+mod common;
+
+use common::{chapter, entry, series};
+use rrmounter::base::ContentRating;
+use rrmounter::generate::{
+    DEFAULT_MATCH_THRESHOLD, Series, Sourced, Status, best_match, merge_series, score_match,
+};
+
+fn titan(source_id: &str, title: &str) -> Series {
+    let mut titan = series(source_id, "aot", title);
+    titan.author = vec!["Hajime Isayama".to_string()];
+    titan.artist = vec!["Hajime Isayama".to_string()];
+    titan.number_chapters = 139;
+    titan
+}
+
+#[test]
+fn missing_fields_do_not_count_against_a_match() {
+    let score = score_match(
+        &entry(&series("a", "1", "One Piece")),
+        &titan("b", "One Piece"),
+    );
+
+    assert_eq!(score.title, 1.0);
+    assert_eq!((score.people, score.chapters), (None, None));
+    assert_eq!(score.total, 1.0);
+}
+
+#[test]
+fn alternative_titles_match_main_titles() {
+    let mut japanese = titan("a", "Shingeki no Kyojin");
+    japanese.alt_titles = vec!["Attack on Titan".to_string()];
+    let english = titan("b", "Attack on Titan");
+
+    let score = score_match(&japanese, &english);
+    assert_eq!(score.title, 1.0);
+    assert!(score.is_match());
+
+    // Entries carry no alternative titles, so only the main title can match
+    let score = score_match(&entry(&japanese), &entry(&english));
+    assert!(score.title < 1.0);
+}
+
+#[test]
+fn differing_people_and_chapters_lower_the_total() {
+    let original = titan("a", "Attack on Titan");
+    let mut spinoff = titan("b", "Attack on Titan");
+    spinoff.author = vec!["Ryo Suzukaze".to_string()];
+    spinoff.artist = vec!["Satoshi Shiki".to_string()];
+    spinoff.number_chapters = 70;
+
+    let score = score_match(&original, &spinoff);
+
+    assert_eq!(score.people, Some(0.0));
+    assert!(score.chapters.unwrap() < 0.55);
+    assert!(score.total < DEFAULT_MATCH_THRESHOLD);
+    assert!(!score.is_match());
+}
+
+#[test]
+fn the_threshold_is_inclusive() {
+    let target = titan("a", "Attack on Titan");
+    let mut candidate = titan("b", "Attack on Titan");
+    candidate.number_chapters = 100;
+    let candidates = [candidate];
+    let total = score_match(&target, &candidates[0]).total;
+
+    assert!(best_match(&target, &candidates, total).is_some());
+    assert!(best_match(&target, &candidates, total + f64::EPSILON).is_none());
+    assert!(best_match(&target, &[] as &[Series], 0.0).is_none());
+}
+
+#[test]
+fn the_best_candidate_wins() {
+    let target = titan("a", "Attack on Titan");
+    let mut near = titan("b", "Attack on Titan");
+    near.series_id = "near".to_string();
+    near.number_chapters = 120;
+    let mut exact = titan("c", "Attack on Titan");
+    exact.series_id = "exact".to_string();
+    let unrelated = series("d", "op", "One Piece");
+    let candidates = [near, unrelated, exact];
+
+    let (found, score) = best_match(&target, &candidates, DEFAULT_MATCH_THRESHOLD).unwrap();
+
+    assert_eq!(found.series_id, "exact");
+    assert_eq!(score.total, 1.0);
+}
+
+#[test]
+fn merges_prefer_the_primary_and_fill_its_gaps() {
+    let mut primary = titan("a", "Attack on Titan");
+    primary.status = Status::Completed;
+    primary.year = Some(2009);
+    primary.chapters = vec![chapter(&primary, "1", "1")];
+    primary.tags = vec!["Action".to_string()];
+
+    let mut other = titan("b", "Shingeki no Kyojin");
+    other.alt_titles = vec!["attack on titan".to_string()];
+    other.status = Status::Ongoing;
+    other.year = Some(2010);
+    other.description = "Humanity fights titans".to_string();
+    other.cover_url = "https://example.com/aot.jpg".to_string();
+    other.content_rating = Some(ContentRating::Mature);
+    other.author = vec!["HAJIME ISAYAMA".to_string()];
+    other.tags = vec!["action".to_string(), "Drama".to_string()];
+    other.chapters = vec![chapter(&other, "1", "1"), chapter(&other, "2", "2")];
+
+    let merged = merge_series(&primary, &[other]);
+
+    // Conflicting scalars keep the primary value
+    assert_eq!(merged.title.value, "Attack on Titan");
+    assert_eq!(merged.status.value, Status::Completed);
+    assert_eq!(merged.year.unwrap().value, 2009);
+
+    // Values the primary lacks come from the other source
+    assert_eq!(merged.description.from.source_id, "b");
+    assert_eq!(merged.cover_url.value, "https://example.com/aot.jpg");
+    assert_eq!(merged.content_rating.unwrap().value, ContentRating::Mature);
+
+    // The longest chapter list wins and lists are unioned case-insensitively
+    assert_eq!(merged.chapters.from.source_id, "b");
+    assert_eq!(merged.chapters.value.len(), 2);
+    let values = |list: &[Sourced<String>]| -> Vec<String> {
+        list.iter().map(|value| value.value.clone()).collect()
+    };
+    assert_eq!(values(&merged.author), ["Hajime Isayama"]);
+    assert_eq!(values(&merged.tags), ["Action", "Drama"]);
+    assert_eq!(values(&merged.alt_titles), ["Shingeki no Kyojin"]);
+    assert_eq!(merged.sources.len(), 2);
+}
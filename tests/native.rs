@@ -0,0 +1,75 @@
+mod common;
+
+use common::failing_source;
+use rrmounter::SourceError;
+use rrmounter::base::{ContentRating, SourceInfo};
+use rrmounter::native::{
+    ABI_VERSION, CRATE_VERSION, ExtensionDeclaration, NativeExtension, RUSTC_VERSION,
+    SourceRegistrar,
+};
+
+// The entry point a `cdylib` would export, with a register function that panics
+rrmounter::export_sources!(|_: &mut SourceRegistrar| panic!("broken extension"));
+
+extern "C" fn register(registrar: &mut SourceRegistrar) -> bool {
+    let info = SourceInfo::new("test", "Test", "1.0.0", ContentRating::Everyone);
+    registrar.register(info, failing_source());
+    true
+}
+
+fn declaration() -> ExtensionDeclaration {
+    ExtensionDeclaration {
+        abi_version: ABI_VERSION,
+        crate_version: CRATE_VERSION.as_ptr(),
+        rustc_version: RUSTC_VERSION.as_ptr(),
+        register,
+    }
+}
+
+fn load(declaration: &ExtensionDeclaration) -> Result<NativeExtension, SourceError> {
+    unsafe { NativeExtension::from_declaration("extension.so", declaration) }
+}
+
+fn mismatched_setting(declaration: &ExtensionDeclaration) -> String {
+    match load(declaration) {
+        Err(SourceError::ConfigurationError { setting, .. }) => setting,
+        Err(error) => panic!("unexpected error {:?}", error),
+        Ok(_) => panic!("the handshake accepted a mismatched extension"),
+    }
+}
+
+#[test]
+fn matching_extensions_register_their_sources() {
+    let extension = load(&declaration()).unwrap();
+
+    assert_eq!(extension.sources().len(), 1);
+    assert_eq!(extension.sources()[0].0.id, "test");
+}
+
+#[test]
+fn mismatched_versions_are_configuration_errors() {
+    let abi = ExtensionDeclaration {
+        abi_version: ABI_VERSION + 1,
+        ..declaration()
+    };
+    assert!(mismatched_setting(&abi).starts_with("abi_version"));
+
+    let crate_version = ExtensionDeclaration {
+        crate_version: c"0.0.1".as_ptr(),
+        ..declaration()
+    };
+    assert!(mismatched_setting(&crate_version).starts_with("rrmounter_version"));
+
+    let rustc_version = ExtensionDeclaration {
+        rustc_version: c"rustc 1.0.0 (a59807b 2015-05-15)".as_ptr(),
+        ..declaration()
+    };
+    assert!(mismatched_setting(&rustc_version).starts_with("rustc_version"));
+}
+
+#[test]
+fn panics_while_registering_are_reported() {
+    let loaded = load(&RRMOUNTER_EXTENSION);
+
+    assert!(matches!(loaded, Err(SourceError::Unexpected { .. })));
+}
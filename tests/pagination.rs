@@ -0,0 +1,161 @@
+use futures::StreamExt;
+use rrmounter::base::{CancelToken, PaginatedResponse, PaginationRequest, Paginator};
+use rrmounter::generate::SeriesEntry;
+use rrmounter::{FORMAT_VERSION, SourceError};
+use std::cell::RefCell;
+
+fn entry(id: u32) -> SeriesEntry {
+    SeriesEntry::new("test", id.to_string(), format!("Series {}", id), "")
+}
+
+fn page(current_page: u32, ids: &[u32], has_next_page: bool) -> PaginatedResponse<SeriesEntry> {
+    PaginatedResponse {
+        format_version: FORMAT_VERSION,
+        items: ids.iter().copied().map(entry).collect(),
+        current_page,
+        total_pages: None,
+        has_next_page,
+        total_items: None,
+    }
+}
+
+fn ids(entries: Vec<Result<SeriesEntry, SourceError>>) -> Vec<String> {
+    entries
+        .into_iter()
+        .map(|entry| entry.unwrap().series_id)
+        .collect()
+}
+
+#[tokio::test]
+async fn walks_pages_until_the_last_one() {
+    let requested = RefCell::new(Vec::new());
+    let fetch = |pagination: PaginationRequest| {
+        requested.borrow_mut().push(pagination.page);
+        let response = match pagination.page {
+            1 => page(1, &[1, 2], true),
+            2 => page(2, &[3, 4], true),
+            _ => page(3, &[5], false),
+        };
+        async move { Ok(response) }
+    };
+
+    let items = Paginator::new(fetch)
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids(items), ["1", "2", "3", "4", "5"]);
+    assert_eq!(*requested.borrow(), [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn advances_from_the_requested_page() {
+    let requested = RefCell::new(Vec::new());
+    // Always reports page 1, whatever was asked for
+    let fetch = |pagination: PaginationRequest| {
+        requested.borrow_mut().push(pagination.page);
+        let page_number = pagination.page;
+        async move { Ok(page(1, &[page_number], page_number < 3)) }
+    };
+
+    let items = Paginator::new(fetch)
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids(items), ["1", "2", "3"]);
+    assert_eq!(*requested.borrow(), [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn stops_when_a_page_adds_no_new_items() {
+    let calls = RefCell::new(0);
+    // Repeats the same page forever while claiming there is more
+    let fetch = |_: PaginationRequest| {
+        *calls.borrow_mut() += 1;
+        async { Ok(page(1, &[1, 2], true)) }
+    };
+
+    let items = Paginator::new(fetch)
+        .dedup_by_series_id()
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids(items), ["1", "2"]);
+    assert_eq!(*calls.borrow(), 2);
+}
+
+#[tokio::test]
+async fn dedup_skips_items_seen_on_earlier_pages() {
+    let fetch = |pagination: PaginationRequest| {
+        let response = match pagination.page {
+            1 => page(1, &[1, 2], true),
+            _ => page(2, &[2, 3], false),
+        };
+        async move { Ok(response) }
+    };
+
+    let items = Paginator::new(fetch)
+        .dedup_by_series_id()
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids(items), ["1", "2", "3"]);
+}
+
+#[tokio::test]
+async fn limits_stop_the_stream() {
+    let fetch = |pagination: PaginationRequest| {
+        let first = pagination.page * 10;
+        async move { Ok(page(pagination.page, &[first, first + 1], true)) }
+    };
+
+    let by_pages = Paginator::new(fetch)
+        .max_pages(2)
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(ids(by_pages), ["10", "11", "20", "21"]);
+
+    let by_items = Paginator::new(fetch)
+        .max_items(3)
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(ids(by_items), ["10", "11", "20"]);
+}
+
+#[tokio::test]
+async fn cancelling_ends_the_stream() {
+    let token = CancelToken::new();
+    let fetch = |pagination: PaginationRequest| {
+        let first = pagination.page * 10;
+        async move { Ok(page(pagination.page, &[first, first + 1], true)) }
+    };
+
+    let mut stream = Box::pin(Paginator::new(fetch).cancel_on(token.clone()).into_stream());
+    assert_eq!(stream.next().await.unwrap().unwrap().series_id, "10");
+    token.cancel();
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn a_failed_page_ends_the_stream() {
+    let fetch = |pagination: PaginationRequest| async move {
+        match pagination.page {
+            1 => Ok(page(1, &[1], true)),
+            _ => Err(SourceError::network("offline", true)),
+        }
+    };
+
+    let items = Paginator::new(fetch)
+        .into_stream()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(items.len(), 2);
+    assert!(items[0].is_ok());
+    assert!(matches!(items[1], Err(SourceError::NetworkError { .. })));
+}
@@ -0,0 +1,179 @@
+use rrmounter::SourceError;
+use rrmounter::base::{ContentRating, LanguageKey, SearchRequest};
+use rrmounter::generate::Status;
+
+fn suggestions(input: &str) -> Vec<String> {
+    match SearchRequest::parse(input) {
+        Err(SourceError::InvalidQuery { suggestions, .. }) => suggestions,
+        other => panic!("expected InvalidQuery for {:?}, got {:?}", input, other),
+    }
+}
+
+#[test]
+fn parses_every_term() {
+    let request = SearchRequest::parse(
+        r#"one piece author:"Eiichiro Oda" artist:oda genre:action -genre:romance status:ongoing rating:safe year:1997..2000 lang:ja"#,
+    )
+    .unwrap();
+    let filters = &request.filters;
+
+    assert_eq!(request.query, "one piece");
+    assert_eq!(filters.authors, Some(vec!["Eiichiro Oda".to_string()]));
+    assert_eq!(filters.artists, Some(vec!["oda".to_string()]));
+    assert_eq!(filters.genres, Some(vec!["action".to_string()]));
+    assert_eq!(filters.excluded_genres, Some(vec!["romance".to_string()]));
+    assert!(matches!(filters.status, Some(Status::Ongoing)));
+    assert!(matches!(
+        filters.content_rating,
+        Some(ContentRating::Everyone)
+    ));
+    assert_eq!(filters.year_range, Some((Some(1997), Some(2000))));
+    assert_eq!(filters.language, Some(LanguageKey::Japanese));
+}
+
+#[test]
+fn quoted_phrases_and_registered_keys() {
+    let input = r#""the end" SORT:latest GENRE:Drama"#;
+    let request = SearchRequest::parse_with_custom_filters(input, &["sort"]).unwrap();
+
+    assert_eq!(request.query, "the end");
+    assert_eq!(request.filters.custom_filters["sort"], "latest");
+    assert_eq!(request.filters.genres, Some(vec!["Drama".to_string()]));
+}
+
+#[test]
+fn unknown_keys_stay_in_the_query() {
+    let request = SearchRequest::parse("Re:Zero sort:latest -x:y genre:drama").unwrap();
+
+    assert_eq!(request.query, "Re:Zero sort:latest -x:y");
+    assert!(request.filters.custom_filters.is_empty());
+    assert_eq!(request.filters.genres, Some(vec!["drama".to_string()]));
+}
+
+#[test]
+fn single_year_and_open_ranges() {
+    let single = SearchRequest::parse("year:2010").unwrap();
+    assert_eq!(single.filters.year_range, Some((Some(2010), Some(2010))));
+
+    let open_start = SearchRequest::parse("year:..2020").unwrap();
+    assert_eq!(open_start.filters.year_range, Some((None, Some(2020))));
+
+    let open_end = SearchRequest::parse("year:2010..").unwrap();
+    assert_eq!(open_end.filters.year_range, Some((Some(2010), None)));
+
+    assert!(suggestions("year:..").contains(&"year:2010..2020".to_string()));
+}
+
+#[test]
+fn invalid_terms_suggest_fixes() {
+    assert_eq!(suggestions("year:2020..2010"), ["year:2010..2020"]);
+    assert_eq!(suggestions("-author:oda"), ["-genre:oda"]);
+    assert_eq!(suggestions("author:"), [r#"author:"value""#]);
+    assert!(suggestions(r#"author:"oda"#)[0].ends_with('"'));
+    assert!(suggestions("status:done").contains(&"status:completed".to_string()));
+    assert!(suggestions(":x").contains(&"genre:".to_string()));
+}
+
+#[test]
+fn every_accepted_rating_is_suggested() {
+    let suggested = suggestions("rating:unknown");
+
+    for rating in ["everyone", "mature", "adult", "multi"] {
+        let term = format!("rating:{}", rating);
+        assert!(SearchRequest::parse(&term).is_ok());
+        assert!(suggested.contains(&term), "{} is not suggested", term);
+    }
+}
+
+#[test]
+fn shared_language_codes_are_ambiguous() {
+    for code in ["pt", "es", "zh"] {
+        assert_eq!(LanguageKey::from_code(code), None);
+        assert_eq!(LanguageKey::matching(code).len(), 2);
+
+        // Each suggestion resolves to exactly one of the candidates
+        for suggestion in suggestions(&format!("lang:{}", code)) {
+            let request = SearchRequest::parse(&suggestion).unwrap();
+            assert!(LanguageKey::matching(code).contains(&request.filters.language.unwrap()));
+        }
+    }
+
+    assert_eq!(
+        LanguageKey::from_code("br"),
+        Some(LanguageKey::PortugueseBr)
+    );
+    assert_eq!(
+        LanguageKey::from_code("portugueseBr"),
+        Some(LanguageKey::PortugueseBr)
+    );
+    assert_eq!(
+        LanguageKey::from_code("Spanish (Latin America)"),
+        Some(LanguageKey::SpanishLatam)
+    );
+    assert_eq!(LanguageKey::from_code("my"), Some(LanguageKey::Burmese));
+}
+
+#[test]
+fn unknown_languages_suggest_by_initial() {
+    let suggested = suggestions("lang:klingon");
+
+    assert!(suggested.contains(&"lang:ko".to_string()));
+    assert!(suggested.iter().all(|s| SearchRequest::parse(s).is_ok()));
+}
+
+/// The variant declared after `key`. New variants break this match, so they
+/// have to be added here and the walk below checks them against `ALL`.
+fn next_variant(key: LanguageKey) -> Option<LanguageKey> {
+    use LanguageKey::*;
+    match key {
+        Multi => Some(Arabic),
+        Arabic => Some(Bengali),
+        Bengali => Some(Bulgarian),
+        Bulgarian => Some(Burmese),
+        Burmese => Some(Catalan),
+        Catalan => Some(ChineseSimp),
+        ChineseSimp => Some(ChineseTrad),
+        ChineseTrad => Some(Czech),
+        Czech => Some(Danish),
+        Danish => Some(Dutch),
+        Dutch => Some(English),
+        English => Some(Filipino),
+        Filipino => Some(Finnish),
+        Finnish => Some(French),
+        French => Some(German),
+        German => Some(Greek),
+        Greek => Some(Hebrew),
+        Hebrew => Some(Hindi),
+        Hindi => Some(Hungarian),
+        Hungarian => Some(Indonesian),
+        Indonesian => Some(Italian),
+        Italian => Some(Japanese),
+        Japanese => Some(Korean),
+        Korean => Some(Lithuanian),
+        Lithuanian => Some(Malay),
+        Malay => Some(Mongolian),
+        Mongolian => Some(Persian),
+        Persian => Some(Polish),
+        Polish => Some(PortugueseBr),
+        PortugueseBr => Some(PortuguesePt),
+        PortuguesePt => Some(Romanian),
+        Romanian => Some(Russian),
+        Russian => Some(SerboCroatian),
+        SerboCroatian => Some(SpanishEs),
+        SpanishEs => Some(SpanishLatam),
+        SpanishLatam => Some(Swedish),
+        Swedish => Some(Thai),
+        Thai => Some(Turkish),
+        Turkish => Some(Ukrainian),
+        Ukrainian => Some(Vietnamese),
+        Vietnamese => None,
+    }
+}
+
+#[test]
+fn all_lists_every_language() {
+    let declared: Vec<LanguageKey> =
+        std::iter::successors(Some(LanguageKey::Multi), |key| next_variant(*key)).collect();
+
+    assert_eq!(declared, LanguageKey::ALL);
+}
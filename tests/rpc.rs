@@ -0,0 +1,173 @@
+mod common;
+
+use common::{failing_source, series};
+use rrmounter::SourceError;
+use rrmounter::base::{ContentRating, SourceInfo};
+use rrmounter::rpc::{
+    ExtensionServer, MAX_MESSAGE_SIZE, RpcClient, RpcRequest, RpcResponse, RpcTransport,
+    error_code, method,
+};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::task::{AbortHandle, LocalSet};
+
+fn info() -> SourceInfo {
+    SourceInfo::new("test", "Test", "1.0.0", ContentRating::Everyone)
+}
+
+fn server() -> ExtensionServer {
+    let mut source = failing_source();
+    source.series =
+        |series_id: String| Box::pin(async move { Ok(series("test", &series_id, "Title")) });
+    ExtensionServer::new(info(), source)
+}
+
+/// Run `server` on one end of an in-memory pipe, returning the other end.
+///
+/// Source futures are not `Send`, so the server gets a thread of its own.
+fn spawn_server(server: ExtensionServer) -> (DuplexStream, AbortHandle) {
+    let (host, extension) = tokio::io::duplex(64 * 1024);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let local = LocalSet::new();
+        let task = local.spawn_local(async move {
+            let (input, output) = tokio::io::split(extension);
+            let _ = server.serve(BufReader::new(input), output).await;
+        });
+        sender.send(task.abort_handle()).unwrap();
+        let _ = local.block_on(&runtime, task);
+    });
+    (host, receiver.recv().unwrap())
+}
+
+/// An extension that still speaks protocol version 1
+fn spawn_outdated() -> DuplexStream {
+    let (host, extension) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        let (input, mut output) = tokio::io::split(extension);
+        let mut lines = BufReader::new(input).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: RpcRequest = serde_json::from_str(&line).unwrap();
+            let manifest = json!({ "protocolVersion": 1, "info": info() });
+            let response = RpcResponse::success(request.id, manifest);
+            let mut line = serde_json::to_vec(&response).unwrap();
+            line.push(b'\n');
+            output.write_all(&line).await.unwrap();
+        }
+    });
+    host
+}
+
+fn transport(stream: DuplexStream) -> RpcTransport {
+    let (output, input) = tokio::io::split(stream);
+    (Box::new(output), Box::new(input))
+}
+
+async fn responses(host: DuplexStream, requests: Vec<u8>, count: usize) -> Vec<RpcResponse> {
+    let (output, mut input) = tokio::io::split(host);
+    tokio::spawn(async move { input.write_all(&requests).await.unwrap() });
+
+    let mut lines = BufReader::new(output).lines();
+    let mut responses = Vec::new();
+    while responses.len() < count {
+        let line = lines.next_line().await.unwrap().unwrap();
+        responses.push(serde_json::from_str(&line).unwrap());
+    }
+    responses
+}
+
+fn request_line(id: u64, method: &str, params: Value) -> Vec<u8> {
+    let mut line = serde_json::to_vec(&RpcRequest::new(id, method, params)).unwrap();
+    line.push(b'\n');
+    line
+}
+
+#[tokio::test]
+async fn oversized_lines_are_rejected_and_skipped() {
+    let (host, _) = spawn_server(server());
+
+    // Past the limit, the rest of the line would parse as a request of its own
+    let mut requests = b"\"".to_vec();
+    requests.resize(MAX_MESSAGE_SIZE + 1, b'a');
+    requests.extend(request_line(1, method::SERIES, json!({ "seriesId": "1" })));
+    requests.extend(request_line(2, method::SERIES, json!({ "seriesId": "2" })));
+
+    let responses = responses(host, requests, 2).await;
+
+    let ids: Vec<Option<u64>> = responses.iter().map(|response| response.id).collect();
+    assert!(ids.contains(&None) && ids.contains(&Some(2)));
+    let oversized = responses.iter().find(|response| response.id.is_none());
+    assert_eq!(
+        oversized.unwrap().error.as_ref().unwrap().code,
+        error_code::INVALID_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn unknown_methods_are_reported() {
+    let (host, _) = spawn_server(server());
+
+    let responses = responses(host, request_line(1, "unknown", Value::Null), 1).await;
+    assert_eq!(
+        responses[0].error.as_ref().unwrap().code,
+        error_code::METHOD_NOT_FOUND
+    );
+
+    let client = RpcClient::from_transport(|| Ok(transport(spawn_server(server()).0)));
+    let error = client.call::<_, Value>("unknown", &Value::Null).await;
+    assert!(matches!(
+        error,
+        Err(SourceError::UnsupportedOperation { .. })
+    ));
+}
+
+#[tokio::test]
+async fn restarted_extensions_repeat_the_handshake() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let running: Arc<Mutex<Vec<AbortHandle>>> = Arc::default();
+    let (count, tasks) = (connections.clone(), running.clone());
+    let client = RpcClient::from_transport(move || {
+        let stream = match count.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => {
+                let (stream, task) = spawn_server(server());
+                tasks.lock().unwrap().push(task);
+                stream
+            }
+            _ => spawn_outdated(),
+        };
+        Ok(transport(stream))
+    })
+    .with_timeout(Duration::from_secs(5));
+    let crash = || {
+        for task in running.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    };
+    let params = json!({ "seriesId": "1" });
+    let fetch = || client.call::<_, Value>(method::SERIES, &params);
+
+    assert_eq!(client.manifest().await.unwrap().id, "test");
+    assert!(fetch().await.is_ok());
+
+    // A restart reaches an up to date extension again
+    crash();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(fetch().await.is_ok());
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // The next one reports an outdated protocol before any request is sent
+    crash();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(matches!(
+        fetch().await,
+        Err(SourceError::ConfigurationError { .. })
+    ));
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,24 @@
+use rrmounter::schema::{SCHEMA_PATH, json_schema_string};
+use std::path::Path;
+
+/// Fails when the committed schema no longer matches the Rust types.
+///
+/// Run with `UPDATE_SCHEMA=1` to regenerate it.
+#[test]
+fn committed_schema_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_PATH);
+    let generated = json_schema_string();
+
+    if std::env::var_os("UPDATE_SCHEMA").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &generated).unwrap();
+        return;
+    }
+
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "{} is out of date, run `UPDATE_SCHEMA=1 cargo test --features schema --test schema`",
+        SCHEMA_PATH
+    );
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use common::{failing_source, page, series};
+use rrmounter::SourceError;
+use rrmounter::base::{
+    HomeSection, PaginatedResponse, PaginationRequest, SearchRequest, SourceCapabilities,
+    SourceInstance, StatefulSource,
+};
+use rrmounter::error::AsyncSourceResult;
+use rrmounter::generate::{Series, SeriesEntry};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the series requests it serves
+#[derive(Default)]
+struct Counter {
+    requests: AtomicUsize,
+    view_more: bool,
+}
+
+impl StatefulSource for Counter {
+    fn home_page(self: Arc<Self>) -> AsyncSourceResult<Vec<HomeSection>> {
+        (failing_source().home_page)()
+    }
+
+    fn search(
+        self: Arc<Self>,
+        request: SearchRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        (failing_source().search)(request)
+    }
+
+    fn series(self: Arc<Self>, series_id: String) -> AsyncSourceResult<Series> {
+        let count = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        Box::pin(async move { Ok(series("test", &series_id, &count.to_string())) })
+    }
+
+    fn chapter(
+        self: Arc<Self>,
+        series_id: Option<String>,
+        chapter_id: String,
+    ) -> AsyncSourceResult<Vec<String>> {
+        (failing_source().chapter)(series_id, chapter_id)
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            view_more: self.view_more,
+            ..SourceCapabilities::default()
+        }
+    }
+
+    fn view_more(
+        self: Arc<Self>,
+        section_id: String,
+        _pagination: PaginationRequest,
+    ) -> AsyncSourceResult<PaginatedResponse<SeriesEntry>> {
+        let entries = vec![common::entry(&series("test", &section_id, "Title"))];
+        Box::pin(async move { Ok(page(entries, 1, false)) })
+    }
+}
+
+#[tokio::test]
+async fn bound_sources_share_their_state() {
+    let counter = Arc::new(Counter::default());
+    let source = SourceInstance::bind(counter.clone()).unwrap();
+    let copy = source;
+
+    assert_eq!((source.series)("1".to_string()).await.unwrap().title, "1");
+    assert_eq!((copy.series)("1".to_string()).await.unwrap().title, "2");
+    assert_eq!(counter.requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn capabilities_decide_the_optional_operations() {
+    let plain = SourceInstance::bind(Arc::new(Counter::default())).unwrap();
+    assert!(!plain.supports_view_more());
+    assert!(!plain.supports_latest_updates() && !plain.supports_lazy_home_page());
+    assert!(matches!(
+        plain
+            .fetch_view_more("popular".to_string(), PaginationRequest::default())
+            .await,
+        Err(SourceError::UnsupportedOperation { .. })
+    ));
+
+    let expandable = SourceInstance::bind(Arc::new(Counter {
+        view_more: true,
+        ..Counter::default()
+    }))
+    .unwrap();
+    assert!(expandable.supports_view_more());
+    let more = expandable
+        .fetch_view_more("popular".to_string(), PaginationRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(more.items[0].series_id, "popular");
+}
+
+#[tokio::test]
+async fn latest_updates_are_unsupported_without_a_feed() {
+    let source = failing_source();
+
+    assert!(!source.supports_latest_updates());
+    match source
+        .fetch_latest_updates(PaginationRequest::default())
+        .await
+    {
+        Err(SourceError::UnsupportedOperation {
+            operation,
+            alternatives,
+        }) => {
+            assert_eq!(operation, "latest_updates");
+            assert_eq!(alternatives, ["series"]);
+        }
+        other => panic!("expected UnsupportedOperation, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn latest_updates_receive_the_requested_page() {
+    let source = failing_source().with_latest_updates(|pagination: PaginationRequest| {
+        Box::pin(async move { Ok(page(Vec::new(), pagination.page, pagination.per_page > 20)) })
+    });
+
+    let pagination = PaginationRequest {
+        page: 3,
+        per_page: 50,
+    };
+    let feed = source.fetch_latest_updates(pagination).await.unwrap();
+
+    assert!(source.supports_latest_updates());
+    assert_eq!(feed.current_page, 3);
+    assert!(feed.has_next_page);
+}
+
+#[tokio::test]
+async fn view_more_is_unsupported_without_expandable_sections() {
+    let source = failing_source();
+
+    assert!(!source.supports_view_more());
+    match source
+        .fetch_view_more("popular".to_string(), PaginationRequest::default())
+        .await
+    {
+        Err(SourceError::UnsupportedOperation {
+            operation,
+            alternatives,
+        }) => {
+            assert_eq!(operation, "view_more");
+            assert_eq!(alternatives, ["home_page", "search"]);
+        }
+        other => panic!("expected UnsupportedOperation, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn view_more_receives_the_section_and_page() {
+    let source = failing_source().with_view_more(|section_id, pagination: PaginationRequest| {
+        let items = vec![common::entry(&series("test", &section_id, "Title"))];
+        Box::pin(async move { Ok(page(items, pagination.page, pagination.per_page > 20)) })
+    });
+
+    let pagination = PaginationRequest {
+        page: 2,
+        per_page: 40,
+    };
+    let more = source
+        .fetch_view_more("popular".to_string(), pagination)
+        .await
+        .unwrap();
+
+    assert!(source.supports_view_more());
+    assert_eq!(more.items[0].series_id, "popular");
+    assert_eq!(more.current_page, 2);
+    assert!(more.has_next_page);
+}
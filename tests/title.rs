@@ -0,0 +1,81 @@
+use rrmounter::generate::{best_title_similarity, normalize_title, title_similarity};
+
+#[test]
+fn folds_width_case_punctuation_and_noise_suffixes() {
+    assert_eq!(normalize_title("ＯＮＥ ＰＩＥＣＥ"), "one piece");
+    assert_eq!(normalize_title("One-Piece!! (Official)"), "one piece");
+    assert_eq!(
+        normalize_title("Solo Leveling [Colored] (Digital)"),
+        "solo leveling"
+    );
+    assert_eq!(normalize_title("ｶﾞﾝﾀﾞﾑ"), "ガンダム");
+}
+
+#[test]
+fn strips_latin_greek_and_cyrillic_diacritics() {
+    assert_eq!(normalize_title("Shōnen Café"), "shonen cafe");
+    assert_eq!(normalize_title("Ἀθῆναι"), "αθηναι");
+    assert_eq!(normalize_title("Ёлка"), "елка");
+}
+
+#[test]
+fn keeps_dakuten_and_handakuten() {
+    assert_eq!(normalize_title("ガ"), "ガ");
+    assert_ne!(normalize_title("バカ"), normalize_title("ハカ"));
+    assert_ne!(normalize_title("ガ"), normalize_title("カ"));
+    assert_ne!(normalize_title("パン"), normalize_title("ハン"));
+    // Decomposed input recomposes to the same title
+    assert_eq!(normalize_title("\u{30AB}\u{3099}"), normalize_title("ガ"));
+
+    assert!(title_similarity("バカ", "ハカ") < 1.0);
+}
+
+#[test]
+fn romanization_variants_match() {
+    for (a, b) in [
+        ("Shounen Ashibe", "Shōnen Ashibe"),
+        ("Toukyou Manji", "Tokyo Manji"),
+        ("Shingeki no Kyojin", "Shingeki no Kyoujin"),
+        ("Ohkami Kakushi", "Okami Kakushi"),
+        ("Tsubasa", "Tubasa"),
+        ("Chihayafuru", "Tihayafuru"),
+    ] {
+        let similarity = title_similarity(a, b);
+        assert!(similarity >= 0.95, "{} / {}: {}", a, b, similarity);
+    }
+}
+
+#[test]
+fn english_titles_are_not_folded() {
+    assert_eq!(normalize_title("School House"), "school house");
+    assert_eq!(normalize_title("Chicken Soup"), "chicken soup");
+
+    for (a, b) in [
+        ("School", "Schol"),
+        ("Chicken", "Tiken"),
+        ("The Good Doctor", "The God Doctor"),
+        ("Tower of God", "Tower of Gd"),
+    ] {
+        let folded_match = title_similarity(a, b);
+        assert!(folded_match < 0.95, "{} / {}: {}", a, b, folded_match);
+    }
+
+    // Words that also read as romaji only match folded at a discount
+    assert!(title_similarity("House", "Hose") < 1.0);
+}
+
+#[test]
+fn exact_titles_beat_folded_ones() {
+    assert_eq!(title_similarity("Shōnen", "shonen"), 1.0);
+    assert!(title_similarity("Shounen", "Shonen") < 1.0);
+}
+
+#[test]
+fn best_similarity_considers_every_pair() {
+    let titles = ["Shingeki no Kyojin", "Attack on Titan"];
+
+    assert_eq!(best_title_similarity(titles, ["attack on titan"]), 1.0);
+    assert!(best_title_similarity(titles, ["Shingeki no Kyoujin"]) >= 0.95);
+    assert_eq!(best_title_similarity(titles, []), 0.0);
+    assert!(best_title_similarity(titles, ["Vinland Saga"]) < 0.7);
+}
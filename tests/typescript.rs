@@ -0,0 +1,24 @@
+use rrmounter::typescript::{DECLARATIONS_PATH, typescript_declarations};
+use std::path::Path;
+
+/// Fails when the committed declarations no longer match the Rust types.
+///
+/// Run with `UPDATE_BINDINGS=1` to regenerate them.
+#[test]
+fn committed_declarations_are_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DECLARATIONS_PATH);
+    let generated = typescript_declarations();
+
+    if std::env::var_os("UPDATE_BINDINGS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &generated).unwrap();
+        return;
+    }
+
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "{} is out of date, run `UPDATE_BINDINGS=1 cargo test --features ts --test typescript`",
+        DECLARATIONS_PATH
+    );
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{Stub, chapter, entry, page, series};
+use rrmounter::SourceError;
+use rrmounter::base::PaginationRequest;
+use rrmounter::generate::{LatestUpdate, Series};
+use rrmounter::library::{
+    Library, MetadataField, SeriesSnapshot, UpdateChecker, UpdateKind, diff_snapshots,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn with_chapters(ids: &[&str]) -> Series {
+    let mut series = series("test", "1", "Title");
+    series.chapters = ids.iter().map(|id| chapter(&series, id, id)).collect();
+    series
+}
+
+fn library(ids: &[&str], taken_at: u64) -> Library {
+    let mut library = Library::new();
+    for id in ids {
+        let series = series("test", id, "Title");
+        library.add(&series);
+        let mut snapshot = SeriesSnapshot::from(&series);
+        snapshot.taken_at = taken_at;
+        library.set_snapshot("test", id, snapshot);
+    }
+    library
+}
+
+fn checker(source: &rrmounter::base::SourceInstance) -> UpdateChecker<'_> {
+    UpdateChecker::new()
+        .with_source("test", source)
+        .with_min_interval(Duration::ZERO)
+}
+
+#[test]
+fn diff_reports_chapters_and_metadata() {
+    let previous = SeriesSnapshot::from(&with_chapters(&["1", "2", "3"]));
+    let mut current = with_chapters(&["2", "3", "4", "5"]);
+    current.title = "New title".to_string();
+    current.tags.push("Action".to_string());
+    let current = SeriesSnapshot::from(&current);
+
+    let events = diff_snapshots("test", "1", &previous, &current);
+    let kinds: Vec<String> = events
+        .iter()
+        .map(|event| match &event.kind {
+            UpdateKind::NewChapter { chapter } => format!("+{}", chapter.id),
+            UpdateKind::ChapterRemoved { chapter } => format!("-{}", chapter.id),
+            UpdateKind::MetadataChanged { fields } => {
+                assert_eq!(fields, &[MetadataField::Title, MetadataField::Tags]);
+                "metadata".to_string()
+            }
+        })
+        .collect();
+
+    assert_eq!(kinds, ["+4", "+5", "-1", "metadata"]);
+}
+
+#[test]
+fn diff_of_long_chapter_lists() {
+    let ids: Vec<String> = (0..20_000).map(|id| id.to_string()).collect();
+    let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+    let previous = SeriesSnapshot::from(&with_chapters(&ids[..19_999]));
+    let current = SeriesSnapshot::from(&with_chapters(&ids[1..]));
+
+    let events = diff_snapshots("test", "1", &previous, &current);
+
+    assert_eq!(events.len(), 2);
+}
+
+#[tokio::test]
+async fn long_rate_limits_are_reported_instead_of_waited_for() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let series_calls = calls.clone();
+    let source = Stub::default()
+        .with_series(move |_| {
+            series_calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Err(SourceError::rate_limit(3600)) })
+        })
+        .bind();
+    let mut library = library(&["1", "2"], 0);
+
+    let report = tokio::time::timeout(Duration::from_secs(5), checker(&source).check(&mut library))
+        .await
+        .expect("the checker waited for the rate limit");
+
+    assert_eq!(report.failures.len(), 2);
+    assert!(matches!(
+        report.failures[0].error.error,
+        SourceError::RateLimit {
+            retry_after: 3600,
+            ..
+        }
+    ));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn short_rate_limits_are_retried() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let series_calls = calls.clone();
+    let source = Stub::default()
+        .with_series(move |series_id: String| {
+            let first = series_calls.fetch_add(1, Ordering::SeqCst) == 0;
+            Box::pin(async move {
+                match first {
+                    true => Err(SourceError::rate_limit(0)),
+                    false => Ok(series("test", &series_id, "Title")),
+                }
+            })
+        })
+        .bind();
+    let mut library = library(&["1"], 0);
+
+    let report = checker(&source).check(&mut library).await;
+
+    assert!(report.failures.is_empty());
+    assert_eq!(report.checked, 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn feed_pages_advance_from_the_requested_page() {
+    let requested = Arc::new(Mutex::new(Vec::new()));
+    let feed_requests = requested.clone();
+    // Always reports page 1, whatever was asked for
+    let source = Stub::default()
+        .with_latest_updates(move |pagination: PaginationRequest| {
+            feed_requests.lock().unwrap().push(pagination.page);
+            let (series_id, updated_at) = match pagination.page {
+                1 => ("1", 300),
+                2 => ("2", 200),
+                _ => ("3", 50),
+            };
+            let update = LatestUpdate {
+                entry: entry(&series("test", series_id, "Title")),
+                chapters: Vec::new(),
+                updated_at,
+            };
+            Box::pin(async move { Ok(page(vec![update], 1, true)) })
+        })
+        .with_series(|series_id: String| {
+            Box::pin(async move { Ok(series("test", &series_id, "Title")) })
+        })
+        .bind();
+    let mut library = library(&["1", "2", "3"], 100);
+
+    let report = checker(&source).check(&mut library).await;
+
+    assert_eq!(*requested.lock().unwrap(), [1, 2, 3]);
+    assert_eq!(report.checked, 2);
+    assert_eq!(report.skipped, 1);
+}
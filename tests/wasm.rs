@@ -0,0 +1,181 @@
+use rrmounter::SourceError;
+use rrmounter::base::{SearchRequest, SettingType};
+use rrmounter::wasm::{WasmLimits, WasmSource};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const SAMPLE_SOURCE: &str = include_str!("wasm/sample_source.wat");
+const LEGACY_SOURCE: &str = include_str!("wasm/legacy_source.wat");
+
+const CHAPTER_HTML: &str = r#"<html><body>
+    <img class="logo" src="/logo.png">
+    <img class="page" src="/1.jpg">
+    <img class="page" src="/2.jpg">
+</body></html>"#;
+
+/// The sample module, allowed to reach the test server
+fn sample() -> WasmSource {
+    WasmSource::new(SAMPLE_SOURCE)
+        .unwrap()
+        .with_allowed_hosts(["127.0.0.1"])
+}
+
+/// Serve `CHAPTER_HTML` on `/chapter` and 404 on anything else
+async fn serve_chapter() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&request[..read]);
+
+            let response = if request.starts_with("GET /chapter ") {
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    CHAPTER_HTML.len(),
+                    CHAPTER_HTML
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn sample_source_works_as_a_source() {
+    let base_url = serve_chapter().await;
+    let wasm = Arc::new(sample());
+    let (info, source) = wasm.instance().await.unwrap();
+
+    assert_eq!(info.id, "sample");
+    assert!(!source.supports_latest_updates());
+    assert_eq!(
+        wasm.settings().read().unwrap().get_string("pageSelector"),
+        Some("img.page")
+    );
+
+    assert!((source.home_page)().await.unwrap().is_empty());
+    let results = (source.search)(SearchRequest::simple("anything".to_string()))
+        .await
+        .unwrap();
+    assert!(results.items.is_empty());
+
+    let pages = (source.chapter)(None, format!("{}/chapter", base_url))
+        .await
+        .unwrap();
+    assert_eq!(pages, vec!["/1.jpg", "/2.jpg"]);
+
+    let mut values = HashMap::new();
+    values.insert(
+        "pageSelector".to_string(),
+        SettingType::String("img".to_string()),
+    );
+    wasm.settings()
+        .write()
+        .unwrap()
+        .update(&info.settings, values)
+        .unwrap();
+    let pages = (source.chapter)(None, format!("{}/chapter", base_url))
+        .await
+        .unwrap();
+    assert_eq!(pages.len(), 3);
+}
+
+#[tokio::test]
+async fn errors_reach_the_caller() {
+    let base_url = serve_chapter().await;
+    let wasm = Arc::new(sample());
+    let (_, source) = wasm.instance().await.unwrap();
+
+    let missing = (source.chapter)(None, format!("{}/missing", base_url)).await;
+    assert!(matches!(missing, Err(SourceError::NotFound { .. })));
+
+    let unsupported = (source.series)("1".to_string()).await;
+    assert!(matches!(
+        unsupported,
+        Err(SourceError::UnsupportedOperation { .. })
+    ));
+}
+
+#[tokio::test]
+async fn calls_are_limited() {
+    let limits = WasmLimits {
+        memory: 4 * 1024 * 1024,
+        fuel: 10_000_000,
+        timeout: Duration::from_secs(5),
+    };
+    let wasm = WasmSource::new(SAMPLE_SOURCE).unwrap().with_limits(limits);
+
+    let spin = wasm.call::<_, Value>("spin", &Value::Null).await;
+    assert!(
+        matches!(&spin, Err(SourceError::Unexpected { message, .. }) if message.contains("out of fuel"))
+    );
+
+    let grow = wasm.call::<_, Value>("grow", &Value::Null).await;
+    assert!(
+        matches!(&grow, Err(SourceError::Unexpected { message, .. }) if message.contains("memory limit"))
+    );
+
+    // A failed call leaves nothing behind
+    assert!(wasm.manifest().await.is_ok());
+
+    let wasm = wasm.with_limits(WasmLimits {
+        fuel: u64::MAX,
+        timeout: Duration::from_millis(200),
+        ..limits
+    });
+    let started = Instant::now();
+    let spin = wasm.call::<_, Value>("spin", &Value::Null).await;
+    assert!(matches!(spin, Err(SourceError::Timeout { .. })));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn requests_stay_on_allowed_hosts() {
+    let base_url = serve_chapter().await;
+    let (_, source) = Arc::new(sample()).instance().await.unwrap();
+
+    let other_host = base_url.replace("127.0.0.1", "localhost");
+    let forbidden = (source.chapter)(None, format!("{}/chapter", other_host)).await;
+    assert!(matches!(forbidden, Err(SourceError::Forbidden { .. })));
+
+    let file = (source.chapter)(None, "file:///etc/passwd".to_string()).await;
+    assert!(matches!(file, Err(SourceError::Forbidden { .. })));
+
+    // Without an allowlist, nothing is reachable
+    let wasm = Arc::new(WasmSource::new(SAMPLE_SOURCE).unwrap());
+    let (_, source) = wasm.instance().await.unwrap();
+    let unlisted = (source.chapter)(None, format!("{}/chapter", base_url)).await;
+    assert!(matches!(unlisted, Err(SourceError::Forbidden { .. })));
+}
+
+#[tokio::test]
+async fn protocol_version_1_modules_are_rejected() {
+    let wasm = WasmSource::new(LEGACY_SOURCE).unwrap();
+
+    let manifest = wasm.manifest().await;
+    assert!(matches!(
+        manifest,
+        Err(SourceError::ConfigurationError { expected, current, .. })
+            if expected == "2" && current == "1"
+    ));
+}
+
+#[test]
+fn invalid_modules_are_rejected() {
+    assert!(WasmSource::new("(module (func").is_err());
+}
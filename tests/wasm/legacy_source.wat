@@ -0,0 +1,21 @@
+;; A source built against protocol version 1, used by tests/wasm.rs.
+;;
+;; Its manifest uses the version 1 wire format (snake_case fields, PascalCase
+;; language keys), and the host must refuse it.
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+
+  ;; manifest
+  (data (i32.const 0) "{\"result\":{\"protocolVersion\":1,\"info\":{\"id\":\"legacy\",\"name\":\"Legacy\",\"version\":\"0.9.0\",\"languages\":[\"English\"],\"content_rating\":\"everyone\",\"settings\":[]}}}")
+
+  (func (export "rr_alloc") (param $length i32) (result i32)
+    (local $pointer i32)
+    (local.set $pointer (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $length)))
+    (local.get $pointer))
+
+  ;; Every method answers with the manifest
+  (func (export "rr_call") (param i32 i32 i32 i32) (result i64)
+    (i64.const 155))
+)
@@ -0,0 +1,124 @@
+;; A minimal source written by hand against the guest API, used by tests/wasm.rs.
+;;
+;; `chapter` fetches the chapter URL through the host, selects the page images
+;; with the `pageSelector` setting and returns their `src` attributes. `spin`
+;; and `grow` never finish, to exercise the time and memory limits.
+(module
+  (import "rrmounter" "http_get" (func $http_get (param i32 i32) (result i64)))
+  (import "rrmounter" "html_select_attr" (func $html_select_attr (param i32 i32 i32 i32 i32 i32) (result i64)))
+  (import "rrmounter" "json_get" (func $json_get (param i32 i32 i32 i32) (result i64)))
+  (import "rrmounter" "setting" (func $setting (param i32 i32) (result i64)))
+
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+
+  ;; method_manifest
+  (data (i32.const 0) "manifest")
+  ;; method_home_page
+  (data (i32.const 16) "homePage")
+  ;; method_search
+  (data (i32.const 32) "search")
+  ;; method_chapter
+  (data (i32.const 48) "chapter")
+  ;; method_spin
+  (data (i32.const 64) "spin")
+  ;; method_grow
+  (data (i32.const 80) "grow")
+  ;; chapter_id_pointer
+  (data (i32.const 96) "/chapterId")
+  ;; page_selector_key
+  (data (i32.const 112) "pageSelector")
+  ;; src_attribute
+  (data (i32.const 128) "src")
+  ;; result_prefix
+  (data (i32.const 144) "{\"result\":")
+  ;; manifest
+  (data (i32.const 160) "{\"result\":{\"protocolVersion\":2,\"info\":{\"id\":\"sample\",\"name\":\"Sample\",\"version\":\"1.0.0\",\"languages\":[],\"contentRating\":\"everyone\",\"settings\":[{\"key\":\"pageSelector\",\"name\":\"Page selector\",\"description\":\"CSS selector of page images\",\"defaultType\":{\"type\":\"string\",\"value\":\"img.page\"},\"required\":false}]}}}")
+  ;; home_page
+  (data (i32.const 464) "{\"result\":[]}")
+  ;; search
+  (data (i32.const 480) "{\"result\":{\"items\":[],\"currentPage\":1,\"totalPages\":null,\"hasNextPage\":false,\"totalItems\":null}}")
+  ;; not_found
+  (data (i32.const 592) "{\"error\":{\"code\":-32601,\"message\":\"Method not found\"}}")
+
+  ;; Bump allocator, memory is never freed since every call gets a fresh instance
+  (func $alloc (export "rr_alloc") (param $length i32) (result i32)
+    (local $pointer i32)
+    (local.set $pointer (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $length)))
+    (block $done
+      (loop $grow
+        (br_if $done (i32.le_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536))))
+        (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1)) (then unreachable))
+        (br $grow)))
+    (local.get $pointer))
+
+  (func $pack (param $pointer i32) (param $length i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $pointer)) (i64.const 32))
+      (i64.extend_i32_u (local.get $length))))
+
+  (func $pointer (param $packed i64) (result i32)
+    (i32.wrap_i64 (i64.shr_u (local.get $packed) (i64.const 32))))
+
+  (func $length (param $packed i64) (result i32)
+    (i32.wrap_i64 (local.get $packed)))
+
+  (func $equals (param $a i32) (param $a_length i32) (param $b i32) (param $b_length i32) (result i32)
+    (local $index i32)
+    (if (i32.ne (local.get $a_length) (local.get $b_length)) (then (return (i32.const 0))))
+    (block $done
+      (loop $compare
+        (br_if $done (i32.ge_u (local.get $index) (local.get $a_length)))
+        (if (i32.ne
+              (i32.load8_u (i32.add (local.get $a) (local.get $index)))
+              (i32.load8_u (i32.add (local.get $b) (local.get $index))))
+          (then (return (i32.const 0))))
+        (local.set $index (i32.add (local.get $index) (i32.const 1)))
+        (br $compare)))
+    (i32.const 1))
+
+  ;; Wrap a JSON value as {"result":<value>}
+  (func $result (param $value i64) (result i64)
+    (local $output i32)
+    (local $length i32)
+    (local.set $length (i32.add (call $length (local.get $value)) (i32.const 11)))
+    (local.set $output (call $alloc (local.get $length)))
+    (memory.copy (local.get $output) (i32.const 144) (i32.const 10))
+    (memory.copy
+      (i32.add (local.get $output) (i32.const 10))
+      (call $pointer (local.get $value))
+      (call $length (local.get $value)))
+    (i32.store8
+      (i32.sub (i32.add (local.get $output) (local.get $length)) (i32.const 1))
+      (i32.const 125))
+    (call $pack (local.get $output) (local.get $length)))
+
+  (func $chapter (param $params i32) (param $params_length i32) (result i64)
+    (local $url i64)
+    (local $html i64)
+    (local $selector i64)
+    (local.set $url (call $json_get (local.get $params) (local.get $params_length) (i32.const 96) (i32.const 10)))
+    (local.set $html (call $http_get (call $pointer (local.get $url)) (call $length (local.get $url))))
+    (local.set $selector (call $setting (i32.const 112) (i32.const 12)))
+    (call $result
+      (call $html_select_attr
+        (call $pointer (local.get $html)) (call $length (local.get $html))
+        (call $pointer (local.get $selector)) (call $length (local.get $selector))
+        (i32.const 128) (i32.const 3))))
+
+  (func (export "rr_call") (param $method i32) (param $method_length i32) (param $params i32) (param $params_length i32) (result i64)
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 0) (i32.const 8))
+      (then (return (call $pack (i32.const 160) (i32.const 302)))))
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 16) (i32.const 8))
+      (then (return (call $pack (i32.const 464) (i32.const 13)))))
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 32) (i32.const 6))
+      (then (return (call $pack (i32.const 480) (i32.const 95)))))
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 48) (i32.const 7))
+      (then (return (call $chapter (local.get $params) (local.get $params_length)))))
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 64) (i32.const 4))
+      (then (loop $forever (br $forever))))
+    (if (call $equals (local.get $method) (local.get $method_length) (i32.const 80) (i32.const 4))
+      (then (loop $forever (drop (call $alloc (i32.const 65536))) (br $forever))))
+    (call $pack (i32.const 592) (i32.const 54)))
+)
@@ -0,0 +1,233 @@
+mod common;
+
+use common::{entry, series, temp_path};
+use rrmounter::base::{
+    BadgeColor, Banner, BannerLink, GenreChip, HomeSection, HomeSectionItem, LanguageKey,
+    PaginatedResponse, RankedEntry, SearchRequest,
+};
+use rrmounter::export::{DownloadManager, DownloadState};
+use rrmounter::generate::{LatestUpdate, Series};
+use rrmounter::library::{ChapterKey, Library};
+use rrmounter::{FORMAT_VERSION, LEGACY_FORMAT_VERSION, SourceError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+// Hand-written documents in format version 1: snake_case fields, PascalCase
+// languages and errors and lowercase badge colors. The baseline types used
+// those names, but fields such as `content_rating`, badges and languages, the
+// library and the download queue only existed in development builds from
+// before the switch to camelCase, never in a release.
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/v1")
+        .join(name)
+}
+
+fn read_fixture(name: &str) -> String {
+    std::fs::read_to_string(fixture(name)).unwrap()
+}
+
+#[test]
+fn version_1_libraries_are_read() {
+    let library = Library::load(fixture("library.json")).unwrap();
+
+    let entry = library.entry("mangadex", "op").unwrap();
+    assert_eq!(entry.title, "One Piece");
+    assert_eq!(entry.snapshot.as_ref().unwrap().chapters.len(), 2);
+
+    let first = ChapterKey::new("mangadex", "op", "1");
+    assert!(library.is_read(&first));
+    let bookmarks = library.bookmarks("mangadex", "op");
+    assert_eq!(bookmarks.len(), 1);
+    assert_eq!(bookmarks[0].1.page, 3);
+    assert_eq!(bookmarks[0].1.note.as_deref(), Some("Luffy"));
+
+    let second = library
+        .progress(&ChapterKey::new("mangadex", "op", "2"))
+        .unwrap();
+    assert_eq!(second.last_page_read, 5);
+    assert_eq!(second.total_pages, Some(20));
+}
+
+#[test]
+fn version_1_download_queues_are_read() {
+    let root = temp_path("wire-downloads");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::copy(fixture("downloads.json"), root.join("downloads.json")).unwrap();
+
+    let downloads = DownloadManager::open(&root, reqwest::Client::new()).unwrap();
+    let task = downloads
+        .task(&ChapterKey::new("mangadex", "op", "2"))
+        .unwrap();
+    assert_eq!(task.chapter_label, "Vol. 1 Ch. 2");
+    match task.state {
+        DownloadState::Failed { error } => {
+            assert!(matches!(error.error, SourceError::NotFound { .. }));
+            assert_eq!(error.suggested_actions, ["Try another group"]);
+        }
+        state => panic!("unexpected state {:?}", state),
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn version_1_payloads_are_read() {
+    let error: SourceError = serde_json::from_str(&read_fixture("error.json")).unwrap();
+    assert!(matches!(
+        error,
+        SourceError::RateLimit {
+            retry_after: 30,
+            ..
+        }
+    ));
+
+    let series: Series = serde_json::from_str(&read_fixture("series.json")).unwrap();
+    assert_eq!(series.original_language, LanguageKey::Japanese);
+    assert_eq!(series.alt_titles, ["ワンピース"]);
+    assert_eq!(series.chapters[1].chapter_number, "2");
+
+    let section: HomeSection = serde_json::from_str(&read_fixture("home_section.json")).unwrap();
+    let entry = &section.entries[0];
+    assert!(matches!(entry.badges[0].badge_type, BadgeColor::Danger));
+    assert_eq!(
+        entry.languages,
+        [LanguageKey::Japanese, LanguageKey::English]
+    );
+}
+
+/// Serialize `value`, read it back and check the second serialization matches the first
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> Value {
+    let json = serde_json::to_value(value).unwrap();
+    let read: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&read).unwrap(), json);
+    json
+}
+
+fn banner(id: &str, link: BannerLink) -> Banner {
+    Banner {
+        id: id.to_string(),
+        image_url: format!("https://example.com/{}.jpg", id),
+        title: Some("Title".to_string()),
+        subtitle: None,
+        link,
+    }
+}
+
+#[test]
+fn banner_links_round_trip() {
+    let mut search = SearchRequest::simple("one piece".to_string());
+    search.filters.language = Some(LanguageKey::Japanese);
+    let links = [
+        BannerLink::Series {
+            series_id: "op".to_string(),
+        },
+        BannerLink::Section {
+            section_id: "popular".to_string(),
+        },
+        BannerLink::Search { request: search },
+        BannerLink::Url {
+            url: "https://example.com".to_string(),
+        },
+    ];
+
+    let json: Vec<Value> = links.iter().map(round_trip).collect();
+
+    assert_eq!(json[0], json!({ "type": "series", "seriesId": "op" }));
+    assert_eq!(
+        json[1],
+        json!({ "type": "section", "sectionId": "popular" })
+    );
+    assert_eq!(json[2]["type"], "search");
+    assert_eq!(json[2]["request"]["filters"]["language"], "japanese");
+    assert_eq!(
+        json[3],
+        json!({ "type": "url", "url": "https://example.com" })
+    );
+}
+
+#[test]
+fn ranked_entries_round_trip() {
+    let one_piece = entry(&series("test", "op", "One Piece"));
+    let climbing = RankedEntry {
+        position: 1,
+        entry: one_piece.clone(),
+        change: Some(3),
+    };
+    let falling = RankedEntry {
+        position: 2,
+        entry: one_piece.clone(),
+        change: Some(-1),
+    };
+    let new = RankedEntry {
+        position: 3,
+        entry: one_piece,
+        change: None,
+    };
+
+    assert_eq!(round_trip(&climbing)["change"], 3);
+    assert_eq!(round_trip(&falling)["change"], -1);
+    let json = round_trip(&new);
+    assert_eq!(json["change"], Value::Null);
+    assert_eq!(json["entry"]["seriesId"], "op");
+}
+
+#[test]
+fn home_section_items_round_trip() {
+    let one_piece = entry(&series("test", "op", "One Piece"));
+    let items = [
+        HomeSectionItem::Genre(GenreChip {
+            id: "action".to_string(),
+            title: "Action".to_string(),
+            image_url: None,
+        }),
+        HomeSectionItem::ChapterUpdate(LatestUpdate {
+            entry: one_piece.clone(),
+            chapters: Vec::new(),
+            updated_at: 1_700_000_000,
+        }),
+        HomeSectionItem::Banner(banner(
+            "event",
+            BannerLink::Url {
+                url: "https://example.com".to_string(),
+            },
+        )),
+        HomeSectionItem::Ranked(RankedEntry {
+            position: 1,
+            entry: one_piece,
+            change: Some(-2),
+        }),
+    ];
+
+    let kinds: Vec<Value> = items
+        .iter()
+        .map(|item| round_trip(item)["kind"].clone())
+        .collect();
+    assert_eq!(kinds, ["genre", "chapterUpdate", "banner", "ranked"]);
+
+    let json = round_trip(&items[2]);
+    assert_eq!(json["imageUrl"], "https://example.com/event.jpg");
+    assert_eq!(json["link"]["type"], "url");
+}
+
+#[test]
+fn payloads_carry_their_format_version() {
+    let legacy: Series = serde_json::from_str(&read_fixture("series.json")).unwrap();
+    assert_eq!(legacy.format_version, LEGACY_FORMAT_VERSION);
+
+    // Whatever they were read in, payloads are written in the current format
+    let json = round_trip(&legacy);
+    assert_eq!(json["formatVersion"], FORMAT_VERSION);
+    assert!(json.get("source_id").is_none());
+    let page = round_trip(&PaginatedResponse::new(vec![entry(&legacy)], 1, false));
+    assert_eq!(page["formatVersion"], FORMAT_VERSION);
+    assert_eq!(page["items"][0]["formatVersion"], FORMAT_VERSION);
+
+    let mut newer = json;
+    newer["formatVersion"] = json!(FORMAT_VERSION + 1);
+    let error = serde_json::from_value::<Series>(newer).unwrap_err();
+    assert!(error.to_string().contains("formatVersion"));
+}